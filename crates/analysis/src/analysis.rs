@@ -0,0 +1,836 @@
+//! The state of a workspace and the queries answered about it.
+//!
+//! Queries are computed on demand and their results are kept for as long as what they were
+//! computed from is unchanged: the syntax tree of a document until it is edited, the definitions of
+//! a file likewise, the merged schema until the definitions of a file change, and the diagnostics
+//! of a file, open or not, until it is edited or the schema or project configurations change.
+//! Typing within a query thus leaves the schema, and the diagnostics of every other file, as they
+//! were.
+
+/// An open document, with the syntax tree and diagnostics computed from it so far. The tree is
+/// shared with the schema index, so that every query about the document runs on the same tree.
+struct File {
+    parsed: std::sync::Arc<crate::parser::Parsed>,
+    diagnostics: std::sync::Mutex<Option<DiagnosticsMemo>>,
+    /// Set once the document is edited or closed, which outdates what was computed from it.
+    replaced: std::sync::atomic::AtomicBool,
+}
+
+impl File {
+    fn new(parsed: std::sync::Arc<crate::parser::Parsed>) -> Self {
+        Self {
+            parsed,
+            diagnostics: std::sync::Mutex::new(None),
+            replaced: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    fn text(&self) -> &str {
+        self.parsed.text()
+    }
+
+    /// Returns the syntax tree of the document, parsing it on first use.
+    fn tree(&self) -> Option<&tree_sitter::Tree> {
+        self.parsed.tree()
+    }
+
+    fn tokens(&self) -> Vec<crate::lexer::Token<'_>> {
+        self.parsed.tokens()
+    }
+}
+
+/// The diagnostics of a file, and the schema and project configurations they were computed with.
+struct DiagnosticsMemo {
+    schema: std::sync::Arc<crate::schema::Schema>,
+    configs: std::sync::Arc<Vec<crate::config::ProjectConfig>>,
+    diagnostics: std::sync::Arc<FileDiagnostics>,
+}
+
+impl DiagnosticsMemo {
+    fn is_valid(
+        &self,
+        analysis: &Analysis,
+        schema: &std::sync::Arc<crate::schema::Schema>,
+    ) -> bool {
+        std::sync::Arc::ptr_eq(&self.schema, schema)
+            && std::sync::Arc::ptr_eq(&self.configs, &analysis.configs)
+    }
+}
+
+/// The diagnostics of a closed file, keyed by its URL along with the text they were computed from.
+type ClosedDiagnostics = std::sync::Arc<
+    std::sync::Mutex<std::collections::HashMap<url::Url, (std::sync::Arc<str>, DiagnosticsMemo)>>,
+>;
+
+/// Numbers the computations of diagnostics, so that each [`FileDiagnostics`] has an ID of its own.
+static DIAGNOSTICS_IDS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// The diagnostics of a file, as computed once for as long as the file, the schema and the project
+/// configurations stay the same.
+#[derive(Debug)]
+pub struct FileDiagnostics {
+    /// Differs from the ID of every other computation of diagnostics within the process, so that
+    /// diagnostics that are still the same can be told apart from new ones without comparing them.
+    pub id: u64,
+    pub diagnostics: Vec<crate::Diagnostic>,
+}
+
+impl FileDiagnostics {
+    fn new(diagnostics: Vec<crate::Diagnostic>) -> std::sync::Arc<Self> {
+        let id = DIAGNOSTICS_IDS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        std::sync::Arc::new(Self { id, diagnostics })
+    }
+}
+
+/// What a completion inserts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    Table,
+}
+
+/// A keyword or table completion.
+#[derive(Clone, Debug)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// The Markdown documentation of the keyword, if any.
+    pub documentation: Option<String>,
+}
+
+/// The Markdown documentation of the keyword at `range`.
+#[derive(Clone, Debug)]
+pub struct Hover {
+    pub range: crate::TextRange,
+    pub markdown: String,
+}
+
+/// The open documents, project configurations and schema of a workspace, which change as the
+/// workspace does. Queries are answered by the [`Analysis`] snapshots it hands out.
+pub struct AnalysisHost {
+    files: std::sync::Arc<std::collections::BTreeMap<url::Url, std::sync::Arc<File>>>,
+    configs: std::sync::Arc<Vec<crate::config::ProjectConfig>>,
+    schema: std::sync::Arc<crate::schema::SchemaIndex>,
+    kw_docs: std::sync::Arc<crate::keywords::KeywordDocsMap>,
+    closed_diagnostics: ClosedDiagnostics,
+    /// The files indexed from disk before the workspace was re-indexed that have not been read
+    /// again since.
+    stale_files: std::collections::BTreeSet<url::Url>,
+    /// Counts the changes to the workspace, which outdate the snapshots taken before them.
+    revision: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl Default for AnalysisHost {
+    fn default() -> Self {
+        Self {
+            files: std::sync::Arc::default(),
+            configs: std::sync::Arc::default(),
+            schema: std::sync::Arc::default(),
+            kw_docs: std::sync::Arc::new(crate::keywords::load_kw_docs()),
+            closed_diagnostics: ClosedDiagnostics::default(),
+            stale_files: std::collections::BTreeSet::new(),
+            revision: std::sync::Arc::default(),
+        }
+    }
+}
+
+impl AnalysisHost {
+    /// Loads the workspace made of the files and directories at `paths` as a command line tool
+    /// sees it: the projects of the `surrealql.toml` files found in them or their ancestors, or
+    /// every `.surql` file below them when there are none.
+    pub fn load(paths: &[std::path::PathBuf]) -> Result<Self, String> {
+        let mut host = Self::default();
+        let roots = paths
+            .iter()
+            .map(|path| {
+                std::fs::canonicalize(path)
+                    .map_err(|err| format!("Failed to read {}: {}", path.display(), err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut config_paths: Vec<std::path::PathBuf> =
+            roots.iter().filter_map(|root| crate::config::discover(root)).collect();
+        config_paths.sort();
+        config_paths.dedup();
+
+        if config_paths.is_empty() {
+            let schema = std::sync::Arc::make_mut(&mut host.schema);
+            for root in &roots {
+                for path in crate::config::surql_files(root)? {
+                    if !schema.update_from_disk(&path) {
+                        return Err(format!("Failed to read {}", path.display()));
+                    }
+                }
+            }
+            return Ok(host);
+        }
+        let configs = config_paths
+            .iter()
+            .map(|path| crate::config::load(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        host.set_configs(configs);
+        match host.index_workspace().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(host),
+        }
+    }
+
+    /// Returns a snapshot of the workspace as it is now, which queries can be answered from on
+    /// any thread while the workspace keeps changing.
+    pub fn analysis(&self) -> Analysis {
+        Analysis {
+            files: self.files.clone(),
+            configs: self.configs.clone(),
+            schema: self.schema.clone(),
+            kw_docs: self.kw_docs.clone(),
+            closed_diagnostics: self.closed_diagnostics.clone(),
+            revision: self.revision.load(std::sync::atomic::Ordering::Acquire),
+            current_revision: self.revision.clone(),
+        }
+    }
+
+    /// Marks the snapshots taken so far as outdated.
+    fn changed(&mut self) {
+        self.revision.fetch_add(1, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Opens or replaces `file` with `open`, or closes it with `None`, flagging the document it
+    /// replaces. Returns whether the file was open.
+    fn replace_file(&mut self, file: &url::Url, open: Option<File>) -> bool {
+        let files = std::sync::Arc::make_mut(&mut self.files);
+        let replaced = match open {
+            Some(open) => files.insert(file.clone(), std::sync::Arc::new(open)),
+            None => files.remove(file),
+        };
+        if let Some(replaced) = &replaced {
+            replaced.replaced.store(true, std::sync::atomic::Ordering::Release);
+        }
+        replaced.is_some()
+    }
+
+    /// Replaces the project configurations. The schema is only re-read by
+    /// [`AnalysisHost::index_workspace`].
+    pub fn set_configs(&mut self, configs: Vec<crate::config::ProjectConfig>) {
+        self.changed();
+        let dialect = configs.first().map(|config| config.dialect).unwrap_or_default();
+        std::sync::Arc::make_mut(&mut self.schema).set_dialect(dialect);
+        self.configs = std::sync::Arc::new(configs);
+    }
+
+    /// Re-indexes the schema files and snapshots of every project, returning why any of them
+    /// could not be read. The schema files and then the migrations of each project are applied in
+    /// order, ahead of any other file.
+    ///
+    /// The other files indexed before are kept as they were until they are indexed again or
+    /// [`AnalysisHost::forget_stale_files`] forgets them, so that the schema stays whole while the
+    /// rest of the workspace is read again.
+    pub fn index_workspace(&mut self) -> Vec<String> {
+        self.changed();
+        let mut errors = Vec::new();
+        let schema = std::sync::Arc::make_mut(&mut self.schema);
+        let mut snapshots = Vec::new();
+        let mut order = Vec::new();
+        let mut migrations = std::collections::BTreeSet::new();
+        for config in self.configs.iter() {
+            for path in config.snapshot_sources() {
+                if let Err(err) = schema.update_from_snapshot(&path) {
+                    errors.push(err);
+                }
+                snapshots.extend(url::Url::from_file_path(&path));
+            }
+            for path in config.schema_sources() {
+                let Ok(uri) = url::Url::from_file_path(&path) else {
+                    continue;
+                };
+                // The text of an open document is kept, as the editor holds the latest version.
+                if !self.files.contains_key(&uri) && !schema.update_from_disk(&path) {
+                    errors.push(format!("Failed to index {}", path.display()));
+                }
+                order.push(uri);
+            }
+            let paths = config.migration_sources();
+            migrations.extend(paths.iter().filter_map(|path| url::Url::from_file_path(path).ok()));
+        }
+        let ordered: std::collections::HashSet<&url::Url> = order.iter().collect();
+        let stale = schema.files().filter(|file| !self.files.contains_key(*file));
+        self.stale_files = stale.filter(|file| !ordered.contains(file)).cloned().collect();
+        schema.retain_snapshots(&snapshots);
+        schema.set_order(order, migrations);
+        self.forget_closed_diagnostics();
+        errors
+    }
+
+    /// Replaces the schema of the database at `uri`, a [`crate::LIVE_SCHEME`] URI, with the
+    /// `DEFINE` statements of `definitions`.
+    pub fn set_live_schema(&mut self, uri: &url::Url, definitions: String) {
+        self.changed();
+        std::sync::Arc::make_mut(&mut self.schema).update_live(uri, definitions);
+    }
+
+    /// Forgets the schema of every database except those at `uris`.
+    pub fn retain_live_schemas(&mut self, uris: &[url::Url]) {
+        self.changed();
+        std::sync::Arc::make_mut(&mut self.schema).retain_live(uris);
+    }
+
+    /// Opens `file`, or replaces its text.
+    pub fn set_file_text(&mut self, file: url::Url, text: String) {
+        self.changed();
+        let parsed = std::sync::Arc::new(crate::parser::Parsed::new(text, None));
+        std::sync::Arc::make_mut(&mut self.schema).update_parsed(&file, parsed.clone());
+        self.replace_file(&file, Some(File::new(parsed)));
+    }
+
+    /// Indexes the text of the closed `file` as it is on disk, or forgets it when `text` is `None`
+    /// as the file was deleted. The text of an open document is kept, as the editor holds the
+    /// latest version of it.
+    pub fn set_disk_file(&mut self, file: &url::Url, text: Option<&str>) {
+        self.set_disk_files([(file, text)]);
+    }
+
+    /// Indexes the texts of several closed files as [`AnalysisHost::set_disk_file`] does, as a
+    /// single change to the workspace, e.g. for a batch of the files read while it is indexed.
+    pub fn set_disk_files<'f>(
+        &mut self,
+        files: impl IntoIterator<Item = (&'f url::Url, Option<&'f str>)>,
+    ) {
+        let mut changed = false;
+        let mut removed = false;
+        for (file, text) in files {
+            if self.files.contains_key(file) {
+                continue;
+            }
+            changed = true;
+            self.stale_files.remove(file);
+            let schema = std::sync::Arc::make_mut(&mut self.schema);
+            match text {
+                Some(text) => schema.update(file, text),
+                None => {
+                    schema.remove(file);
+                    removed = true;
+                }
+            }
+        }
+        if removed {
+            self.forget_closed_diagnostics();
+        }
+        if changed {
+            self.changed();
+        }
+    }
+
+    /// Forgets the files indexed from disk that were not indexed again since the workspace was,
+    /// once every file of the workspace has been read.
+    pub fn forget_stale_files(&mut self) {
+        if self.stale_files.is_empty() {
+            return;
+        }
+        self.changed();
+        let schema = std::sync::Arc::make_mut(&mut self.schema);
+        for file in std::mem::take(&mut self.stale_files) {
+            if !self.files.contains_key(&file) {
+                schema.remove(&file);
+            }
+        }
+        self.forget_closed_diagnostics();
+    }
+
+    /// Forgets the diagnostics of the closed files that are no longer indexed as they were.
+    fn forget_closed_diagnostics(&mut self) {
+        let mut memo =
+            self.closed_diagnostics.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        memo.retain(|file, (text, _)| {
+            self.schema
+                .shared_text(file)
+                .is_some_and(|indexed| std::sync::Arc::ptr_eq(indexed, text))
+        });
+    }
+
+    /// Closes `file`, indexing `disk_text` in place of the text of the editor, or forgetting the
+    /// file when it is not part of the workspace.
+    pub fn close_file(&mut self, file: &url::Url, disk_text: Option<&str>) {
+        if self.replace_file(file, None) {
+            self.set_disk_file(file, disk_text);
+        }
+    }
+
+    /// Replaces `range` of the open `file` with `new_text`. The document is parsed again on
+    /// demand, incrementally.
+    pub fn edit_file(
+        &mut self,
+        file: &url::Url,
+        range: crate::TextRange,
+        new_text: &str,
+    ) -> Result<(), String> {
+        let Some(open) = self.files.get(file).cloned() else {
+            return Err(format!("{} is not open", file));
+        };
+        let is_valid = range.start <= range.end
+            && open.text().is_char_boundary(range.start)
+            && open.text().is_char_boundary(range.end);
+        if !is_valid {
+            return Err(format!("Invalid edit range {:?}", range));
+        }
+        self.changed();
+
+        let mut text = open.text().to_string();
+        text.replace_range(range.clone(), new_text);
+        let new_end_byte = range.start + new_text.len();
+        // Edits accumulate on the last tree that was parsed until the document is parsed again.
+        let mut tree = open.parsed.last_tree().cloned();
+        if let Some(tree) = &mut tree {
+            tree.edit(&tree_sitter::InputEdit {
+                start_byte: range.start,
+                old_end_byte: range.end,
+                new_end_byte,
+                start_position: point(open.text(), range.start),
+                old_end_position: point(open.text(), range.end),
+                new_end_position: point(&text, new_end_byte),
+            });
+        }
+        let parsed = std::sync::Arc::new(crate::parser::Parsed::new(text, tree));
+        std::sync::Arc::make_mut(&mut self.schema).update_parsed(file, parsed.clone());
+        self.replace_file(file, Some(File::new(parsed)));
+        Ok(())
+    }
+}
+
+/// An immutable snapshot of an [`AnalysisHost`], which answers queries about the workspace as it
+/// was when the snapshot was taken. Snapshots are cheap to take and to clone, and can be queried
+/// from several threads at once.
+#[derive(Clone)]
+pub struct Analysis {
+    files: std::sync::Arc<std::collections::BTreeMap<url::Url, std::sync::Arc<File>>>,
+    configs: std::sync::Arc<Vec<crate::config::ProjectConfig>>,
+    schema: std::sync::Arc<crate::schema::SchemaIndex>,
+    kw_docs: std::sync::Arc<crate::keywords::KeywordDocsMap>,
+    closed_diagnostics: ClosedDiagnostics,
+    /// The revision of the workspace the snapshot was taken at.
+    revision: u64,
+    current_revision: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl Analysis {
+    /// Returns whether the workspace has changed since the snapshot was taken, so that answers
+    /// computed from it are outdated.
+    pub fn is_cancelled(&self) -> bool {
+        self.current_revision.load(std::sync::atomic::Ordering::Acquire) != self.revision
+    }
+
+    /// Returns whether the open `file` has been edited or closed since the snapshot was taken, so
+    /// that answers about it alone are outdated. Changes to the rest of the workspace, such as
+    /// files read while it is indexed, are only caught up with by the next snapshot.
+    pub fn is_modified(&self, file: &url::Url) -> bool {
+        match self.files.get(file) {
+            Some(open) => open.replaced.load(std::sync::atomic::Ordering::Acquire),
+            None => self.is_cancelled(),
+        }
+    }
+
+    pub fn configs(&self) -> &[crate::config::ProjectConfig] {
+        &self.configs
+    }
+
+    /// Returns the configuration of the project containing `file`, preferring the innermost one.
+    pub fn config(&self, file: &url::Url) -> Option<&crate::config::ProjectConfig> {
+        let path = file.to_file_path().ok()?;
+        self.configs
+            .iter()
+            .filter(|config| config.contains(&path))
+            .max_by_key(|config| config.root.components().count())
+    }
+
+    /// Returns the text of an open document, or of a file or database the schema was read from.
+    pub fn file_text(&self, file: &url::Url) -> Option<&str> {
+        match self.files.get(file) {
+            Some(open) => Some(open.text()),
+            None => self.schema.text(file),
+        }
+    }
+
+    /// Returns the open documents and the files indexed from disk, sorted by URL.
+    pub fn files(&self) -> Vec<url::Url> {
+        let indexed = self.schema.files().filter(|file| file.scheme() == "file");
+        let files: std::collections::BTreeSet<&url::Url> =
+            self.files.keys().chain(indexed).collect();
+        files.into_iter().cloned().collect()
+    }
+
+    /// Runs every semantic check on `file`, which is either open or indexed from disk. A migration
+    /// is checked against the schema as of itself, rather than after every migration.
+    pub fn diagnostics(&self, file: &url::Url) -> Vec<crate::Diagnostic> {
+        self.file_diagnostics(file).diagnostics.clone()
+    }
+
+    /// Returns the diagnostics of `file` like [`Analysis::diagnostics`], computing them again only
+    /// when the file, the schema or the project configurations have changed since.
+    pub fn file_diagnostics(&self, file: &url::Url) -> std::sync::Arc<FileDiagnostics> {
+        let schema = self.schema.schema_at(file);
+        let Some(open) = self.files.get(file) else {
+            return self.closed_file_diagnostics(file, schema);
+        };
+        // The lock is held while the diagnostics are computed, so that concurrent queries wait for
+        // them rather than compute them again.
+        let mut memo = open.diagnostics.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(memo) = memo.as_ref().filter(|memo| memo.is_valid(self, &schema)) {
+            return memo.diagnostics.clone();
+        }
+        let diagnostics = FileDiagnostics::new(crate::diagnostics::semantic_diagnostics(
+            file,
+            open.text(),
+            &open.tokens(),
+            &schema,
+            self.config(file),
+        ));
+        let configs = self.configs.clone();
+        *memo = Some(DiagnosticsMemo { schema, configs, diagnostics: diagnostics.clone() });
+        diagnostics
+    }
+
+    /// Checks a closed file as it was indexed. Unlike for open documents, the memo is not locked
+    /// while the diagnostics are computed, as it is shared by every closed file.
+    fn closed_file_diagnostics(
+        &self,
+        file: &url::Url,
+        schema: std::sync::Arc<crate::schema::Schema>,
+    ) -> std::sync::Arc<FileDiagnostics> {
+        static NONE: std::sync::OnceLock<std::sync::Arc<FileDiagnostics>> =
+            std::sync::OnceLock::new();
+        let Some(parsed) = self.schema.parsed(file).filter(|_| file.scheme() == "file") else {
+            return NONE.get_or_init(|| FileDiagnostics::new(Vec::new())).clone();
+        };
+        let text = parsed.text();
+        let lock =
+            || self.closed_diagnostics.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some((_, memo)) = lock().get(file).filter(|(memo_text, memo)| {
+            std::sync::Arc::ptr_eq(memo_text, text) && memo.is_valid(self, &schema)
+        }) {
+            return memo.diagnostics.clone();
+        }
+        let diagnostics = FileDiagnostics::new(crate::diagnostics::semantic_diagnostics(
+            file,
+            text,
+            &parsed.tokens(),
+            &schema,
+            self.config(file),
+        ));
+        let configs = self.configs.clone();
+        let memo = DiagnosticsMemo { schema, configs, diagnostics: diagnostics.clone() };
+        lock().insert(file.clone(), (text.clone(), memo));
+        diagnostics
+    }
+
+    /// Returns the keywords that may follow `offset` of the open `file`, or the tables that may
+    /// follow the arrow of a graph traversal before it.
+    pub fn completions(&self, file: &url::Url, offset: usize) -> Option<Vec<CompletionItem>> {
+        let open = self.files.get(file)?;
+        let schema = self.schema.schema_at(file);
+        if let Some(items) =
+            crate::completion::traversal_completions(&open.tokens(), offset, &schema)
+        {
+            return Some(items);
+        }
+        let keywords =
+            crate::completion::get_completion_list(open.text(), point(open.text(), offset))?;
+        let items = keywords
+            .into_iter()
+            .map(|keyword| CompletionItem {
+                documentation: self.kw_docs.get(&keyword).cloned(),
+                label: keyword,
+                kind: CompletionKind::Keyword,
+            })
+            .collect();
+        Some(items)
+    }
+
+    /// Returns the documentation of the keyword at `offset` of the open `file`.
+    pub fn hover(&self, file: &url::Url, offset: usize) -> Option<Hover> {
+        let open = self.files.get(file)?;
+        let tree = open.tree()?;
+        let (keyword, range) = crate::position::retrieve_keyword_at_position(
+            open.text(),
+            tree,
+            point(open.text(), offset),
+        )?;
+        let markdown = self.kw_docs.get(&keyword)?.clone();
+        Some(Hover { range, markdown })
+    }
+
+    /// Returns the inlay hints of the open `file` that fall within `range`.
+    pub fn inlay_hints(&self, file: &url::Url, range: crate::TextRange) -> Vec<crate::InlayHint> {
+        let Some(open) = self.files.get(file) else {
+            return Vec::new();
+        };
+        let default = crate::config::HintsConfig::default();
+        let config = self.config(file).map_or(&default, |config| &config.hints);
+        crate::hints::inlay_hints(&open.tokens(), &self.schema.schema_at(file), config, range)
+    }
+
+    /// Returns the refactorings of the selection `range` of the open `file` and its source
+    /// actions, limited to the kinds that are `wanted`. Quick fixes are part of the
+    /// [`crate::Diagnostic`]s instead.
+    pub fn assists(
+        &self,
+        file: &url::Url,
+        range: crate::TextRange,
+        wanted: &dyn Fn(crate::AssistKind) -> bool,
+    ) -> Vec<crate::SourceChange> {
+        let Some((open, tree)) = self.files.get(file).and_then(|open| Some((open, open.tree()?)))
+        else {
+            return Vec::new();
+        };
+        let mut changes = crate::casing::source_actions(file, open.text(), tree, wanted);
+        let refactorings = crate::refactor::refactorings(
+            file,
+            open.text(),
+            tree,
+            &self.schema.schema_at(file),
+            range,
+        );
+        changes.extend(refactorings.into_iter().filter(|change| wanted(change.kind)));
+        changes
+    }
+
+    /// Returns the edits formatting the open `file` according to the `[format]` settings of its
+    /// project.
+    pub fn format(&self, file: &url::Url) -> Vec<crate::TextEdit> {
+        let Some(open) = self.files.get(file) else {
+            return Vec::new();
+        };
+        let default = crate::config::FormatConfig::default();
+        let config = self.config(file).map_or(&default, |config| &config.format);
+        open.tree().map_or_else(Vec::new, |tree| crate::format::format(open.text(), tree, config))
+    }
+
+    /// Returns the edit changing the keyword just typed before `offset` of the open `file` to the
+    /// keyword case of its project.
+    pub fn on_type_edit(&self, file: &url::Url, offset: usize) -> Option<crate::TextEdit> {
+        let open = self.files.get(file)?;
+        let keyword_case = self
+            .config(file)
+            .map_or(crate::config::KeywordCase::default(), |config| config.format.keyword_case);
+        let case = crate::casing::Case::from_config(keyword_case)?;
+        crate::casing::on_type_edit(open.text(), open.tree()?, offset, case)
+    }
+
+    /// Returns the statements and transactions of the open `file` that can be run.
+    pub fn runnables(&self, file: &url::Url) -> Vec<crate::Runnable> {
+        let Some(open) = self.files.get(file) else {
+            return Vec::new();
+        };
+        crate::runnables::runnables(&open.tokens())
+    }
+
+    /// Returns the statements of the open `file` that `selection` selects, with an `EXPLAIN`
+    /// clause when `explain` is set.
+    pub fn query(
+        &self,
+        file: &url::Url,
+        selection: &crate::Selection,
+        explain: bool,
+    ) -> Option<crate::Query> {
+        let open = self.files.get(file)?;
+        crate::runnables::query(open.text(), &open.tokens(), selection, explain)
+    }
+
+    /// Returns a snapshot of the effective schema of the workspace.
+    pub fn schema_snapshot(&self) -> crate::Snapshot {
+        crate::Snapshot::new(&self.schema.schema())
+    }
+
+    /// Returns the effective schema of the workspace as an entity-relationship diagram, with the
+    /// tables related by the `RELATE` statements of its files as well as by its relation tables.
+    pub fn diagram(&self, format: crate::DiagramFormat) -> String {
+        let files = self.files();
+        let tokens = files.iter().filter_map(|file| {
+            let parsed = match self.files.get(file) {
+                Some(open) => &*open.parsed,
+                None => self.schema.parsed(file)?,
+            };
+            Some(parsed.tokens())
+        });
+        crate::diagram::diagram(&self.schema.schema(), tokens, format)
+    }
+
+    /// Returns the types of the tables of the effective schema in `language`, e.g. to mirror them
+    /// in application code.
+    pub fn types(&self, language: crate::TypeLanguage) -> String {
+        crate::typegen::types(&self.schema.schema(), language)
+    }
+
+    /// Returns the statements migrating the schema of `from` to that of `to`, e.g. from a database
+    /// to the workspace, or why either could not be read.
+    pub fn migration(
+        &self,
+        from: &crate::SchemaSource,
+        to: &crate::SchemaSource,
+    ) -> Result<crate::Migration, String> {
+        let dialect = self.configs.first().map(|config| config.dialect).unwrap_or_default();
+        Ok(crate::migration::migration(
+            &self.source_schema(from)?,
+            &self.source_schema(to)?,
+            dialect,
+        ))
+    }
+
+    fn source_schema(&self, source: &crate::SchemaSource) -> Result<crate::schema::Schema, String> {
+        let dialect = self.configs.first().map(|config| config.dialect).unwrap_or_default();
+        match source {
+            crate::SchemaSource::Workspace => Ok(self.schema.workspace_schema()),
+            crate::SchemaSource::Statements(text) => {
+                let uri = url::Url::parse(&format!("{}:statements", crate::LIVE_SCHEME))
+                    .map_err(|err| err.to_string())?;
+                Ok(crate::schema::Schema::from_changes(
+                    &crate::schema::changes(&uri, text),
+                    dialect,
+                ))
+            }
+            crate::SchemaSource::Snapshot(path) => {
+                let uri = url::Url::from_file_path(path)
+                    .map_err(|_| format!("Invalid path {}", path.display()))?;
+                let changes = crate::snapshot::Snapshot::load(path)?.changes(&uri);
+                Ok(crate::schema::Schema::from_changes(&changes, dialect))
+            }
+        }
+    }
+}
+
+/// Returns the row and byte column of `offset` in `text`, as tree-sitter counts them.
+fn point(text: &str, offset: usize) -> tree_sitter::Point {
+    let before = &text.as_bytes()[..offset.min(text.len())];
+    let line_start = before.iter().rposition(|byte| *byte == b'\n').map_or(0, |index| index + 1);
+    tree_sitter::Point {
+        row: before.iter().filter(|byte| **byte == b'\n').count(),
+        column: before.len() - line_start,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn uri(name: &str) -> url::Url {
+        url::Url::parse(&format!("file:///project/{}.surql", name)).unwrap()
+    }
+
+    #[test]
+    fn diagnostics_of_closed_files_are_kept_until_they_change() {
+        let mut host = super::AnalysisHost::default();
+        host.set_disk_file(&uri("schema"), Some("DEFINE TABLE person;"));
+        host.set_disk_file(&uri("queries"), Some("SELECT * FROM persn;"));
+        let diagnostics = host.analysis().file_diagnostics(&uri("queries"));
+        assert_eq!(diagnostics.diagnostics.len(), 1);
+        let again = host.analysis().file_diagnostics(&uri("queries"));
+        assert!(std::sync::Arc::ptr_eq(&diagnostics, &again));
+
+        host.set_disk_file(&uri("schema"), Some("DEFINE TABLE persn;"));
+        let schema_changed = host.analysis().file_diagnostics(&uri("queries"));
+        assert_ne!(schema_changed.id, diagnostics.id);
+        assert!(schema_changed.diagnostics.is_empty());
+
+        host.set_disk_file(&uri("queries"), Some("SELECT * FROM person;"));
+        let edited = host.analysis().file_diagnostics(&uri("queries"));
+        assert_ne!(edited.id, schema_changed.id);
+        assert_eq!(edited.diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn diagnostics_of_open_files_are_kept_until_they_change() {
+        let mut host = super::AnalysisHost::default();
+        host.set_file_text(uri("queries"), String::from("SELECT * FROM person;"));
+        let diagnostics = host.analysis().file_diagnostics(&uri("queries"));
+        let again = host.analysis().file_diagnostics(&uri("queries"));
+        assert_eq!(again.id, diagnostics.id);
+
+        host.set_file_text(uri("queries"), String::from("SELECT * FROM person"));
+        assert_ne!(host.analysis().file_diagnostics(&uri("queries")).id, diagnostics.id);
+    }
+
+    #[test]
+    fn snapshots_are_only_modified_by_changes_to_their_document() {
+        let mut host = super::AnalysisHost::default();
+        host.set_file_text(uri("queries"), String::from("SELECT * FROM person;"));
+        host.set_file_text(uri("other"), String::from("SELECT * FROM person;"));
+        let analysis = host.analysis();
+
+        host.set_disk_files([
+            (&uri("schema"), Some("DEFINE TABLE person;")),
+            (&uri("more"), Some("DEFINE TABLE post;")),
+        ]);
+        host.edit_file(&uri("other"), 0..0, "\n").unwrap();
+        assert!(analysis.is_cancelled());
+        assert!(!analysis.is_modified(&uri("queries")));
+        assert!(analysis.is_modified(&uri("schema")));
+
+        let analysis = host.analysis();
+        host.edit_file(&uri("queries"), 0..0, "\n").unwrap();
+        assert!(analysis.is_modified(&uri("queries")));
+        let analysis = host.analysis();
+        host.close_file(&uri("queries"), None);
+        assert!(analysis.is_modified(&uri("queries")));
+    }
+
+    #[test]
+    fn reindexing_keeps_files_until_they_are_read_again() {
+        let mut host = super::AnalysisHost::default();
+        host.set_disk_file(&uri("schema"), Some("DEFINE TABLE person;"));
+        host.set_disk_file(&uri("queries"), Some("SELECT * FROM person;"));
+        host.set_disk_file(&uri("deleted"), Some("DEFINE TABLE old;"));
+        host.set_file_text(uri("open"), String::from("DEFINE TABLE draft;"));
+        assert!(host.index_workspace().is_empty());
+        assert!(host.analysis().diagnostics(&uri("queries")).is_empty());
+
+        host.set_disk_file(&uri("queries"), Some("SELECT * FROM person;"));
+        assert!(host.analysis().diagnostics(&uri("queries")).is_empty());
+        host.set_disk_file(&uri("schema"), Some("DEFINE TABLE person;"));
+        host.forget_stale_files();
+        let files: Vec<String> = host.analysis().files().iter().map(url::Url::to_string).collect();
+        assert_eq!(
+            files,
+            [
+                "file:///project/open.surql",
+                "file:///project/queries.surql",
+                "file:///project/schema.surql",
+            ]
+        );
+    }
+
+    /// Returns the text of `marked` after typing `typed` at its `|` marker, with the on-type edit
+    /// applied.
+    fn typed(host: &mut super::AnalysisHost, marked: &str, typed: &str) -> String {
+        let offset = marked.find('|').unwrap();
+        host.set_file_text(uri("queries"), marked.replace('|', ""));
+        host.edit_file(&uri("queries"), offset..offset, typed).unwrap();
+        let text = marked.replace('|', typed);
+        let edit = host.analysis().on_type_edit(&uri("queries"), offset + typed.len());
+        crate::TextEdit::apply(&text, edit.as_slice())
+    }
+
+    #[test]
+    fn keywords_take_the_configured_case_as_they_are_typed() {
+        let mut host = super::AnalysisHost::default();
+        assert_eq!(typed(&mut host, "select|", " "), "SELECT ");
+        assert_eq!(typed(&mut host, "SELECT * from|", " "), "SELECT * FROM ");
+        assert_eq!(
+            typed(&mut host, "SELECT * FROM person where|", " "),
+            "SELECT * FROM person WHERE "
+        );
+
+        let project = |keyword_case: &str| {
+            let content = format!("[format]\nkeyword-case = \"{}\"\n", keyword_case);
+            crate::config::parse(std::path::Path::new("/project/surrealql.toml"), &content)
+                .0
+                .unwrap()
+        };
+        host.set_configs(vec![project("lower")]);
+        assert_eq!(typed(&mut host, "SELECT|", " "), "select ");
+        host.set_configs(vec![project("preserve")]);
+        assert_eq!(typed(&mut host, "Select|", " "), "Select ");
+    }
+
+    #[test]
+    fn identifiers_and_strings_that_look_like_keywords_are_left_alone() {
+        let mut host = super::AnalysisHost::default();
+        assert_eq!(typed(&mut host, "SELECT * FROM selected|", " "), "SELECT * FROM selected ");
+        assert_eq!(typed(&mut host, "SELECT * FROM `from`|", " "), "SELECT * FROM `from` ");
+        assert_eq!(typed(&mut host, "RETURN 'select|';", " "), "RETURN 'select ';");
+        assert_eq!(typed(&mut host, "RETURN \"from|\";", " "), "RETURN \"from \";");
+    }
+}
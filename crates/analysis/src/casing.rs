@@ -0,0 +1,146 @@
+//! Keyword case normalisation, for the "Uppercase all keywords" source actions, the formatter and
+//! on-type formatting.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Case {
+    Upper,
+    Lower,
+}
+
+impl Case {
+    /// Returns the case of `keyword_case`, unless keywords are to be left as written.
+    pub(crate) fn from_config(keyword_case: crate::config::KeywordCase) -> Option<Case> {
+        match keyword_case {
+            crate::config::KeywordCase::Upper => Some(Case::Upper),
+            crate::config::KeywordCase::Lower => Some(Case::Lower),
+            crate::config::KeywordCase::Preserve => None,
+        }
+    }
+
+    fn apply(self, text: &str) -> String {
+        match self {
+            Case::Upper => text.to_uppercase(),
+            Case::Lower => text.to_lowercase(),
+        }
+    }
+}
+
+/// Returns the keyword nodes of `tree`, which never include identifiers or strings.
+fn keywords(tree: &tree_sitter::Tree) -> Vec<tree_sitter::Node<'_>> {
+    let mut keywords = Vec::new();
+    let mut cursor = tree.walk();
+    loop {
+        let node = cursor.node();
+        if node.kind().starts_with("keyword_") {
+            keywords.push(node);
+        } else if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return keywords;
+            }
+        }
+    }
+}
+
+fn edit(source: &str, node: tree_sitter::Node<'_>, case: Case) -> Option<crate::TextEdit> {
+    let text = source.get(node.byte_range())?;
+    let new_text = case.apply(text);
+    (new_text != text).then(|| crate::TextEdit { range: node.byte_range(), new_text })
+}
+
+/// Returns the edits changing every keyword of `source` to `case`.
+pub(crate) fn keyword_edits(
+    source: &str,
+    tree: &tree_sitter::Tree,
+    case: Case,
+) -> Vec<crate::TextEdit> {
+    keywords(tree).into_iter().filter_map(|node| edit(source, node, case)).collect()
+}
+
+/// Returns the source changes normalising the case of every keyword of `source`, the text of
+/// `uri`, for the kinds that are `wanted`.
+pub(crate) fn source_actions(
+    uri: &url::Url,
+    source: &str,
+    tree: &tree_sitter::Tree,
+    wanted: &dyn Fn(crate::AssistKind) -> bool,
+) -> Vec<crate::SourceChange> {
+    let actions = [
+        (crate::AssistKind::UppercaseKeywords, "Uppercase all keywords", Case::Upper),
+        (crate::AssistKind::LowercaseKeywords, "Lowercase all keywords", Case::Lower),
+    ];
+    actions
+        .into_iter()
+        .filter(|(kind, _, _)| wanted(*kind))
+        .filter_map(|(kind, title, case)| {
+            let edits = keyword_edits(source, tree, case);
+            if edits.is_empty() {
+                return None;
+            }
+            Some(crate::SourceChange::new(String::from(title), kind, uri, edits))
+        })
+        .collect()
+}
+
+/// Changes the keyword that ends just before `offset`, where the user typed a space or `;`, to
+/// `case`.
+pub(crate) fn on_type_edit(
+    source: &str,
+    tree: &tree_sitter::Tree,
+    offset: usize,
+    case: Case,
+) -> Option<crate::TextEdit> {
+    let end = offset.checked_sub(1)?;
+    let start = end.checked_sub(1)?;
+    let mut node = tree.root_node().descendant_for_byte_range(start, end)?;
+    while !node.kind().starts_with("keyword_") {
+        node = node.parent()?;
+    }
+    // Only the keyword just typed, not one the space was inserted into.
+    if node.end_byte() != end {
+        return None;
+    }
+    edit(source, node, case)
+}
+
+#[cfg(test)]
+mod tests {
+    const SOURCE: &str = "select title, 'from', `where` from post where selected > 1;";
+
+    fn normalised(source: &str, case: super::Case) -> String {
+        let tree = crate::parser::parse(source, None).unwrap();
+        crate::TextEdit::apply(source, &super::keyword_edits(source, &tree, case))
+    }
+
+    #[test]
+    fn changes_keywords_but_not_identifiers_or_strings() {
+        let upper = normalised(SOURCE, super::Case::Upper);
+        assert_eq!(upper, "SELECT title, 'from', `where` FROM post WHERE selected > 1;");
+        assert_eq!(normalised(&upper, super::Case::Lower), SOURCE);
+    }
+
+    #[test]
+    fn preserve_leaves_keywords_as_written() {
+        assert_eq!(
+            super::Case::from_config(crate::config::KeywordCase::Upper),
+            Some(super::Case::Upper)
+        );
+        assert_eq!(
+            super::Case::from_config(crate::config::KeywordCase::Lower),
+            Some(super::Case::Lower)
+        );
+        assert_eq!(super::Case::from_config(crate::config::KeywordCase::Preserve), None);
+    }
+
+    #[test]
+    fn source_actions_are_only_offered_when_they_change_something() {
+        let uri = url::Url::parse("file:///project/query.surql").unwrap();
+        let source = "SELECT * FROM person;";
+        let tree = crate::parser::parse(source, None).unwrap();
+        let actions = super::source_actions(&uri, source, &tree, &|_| true);
+        let labels: Vec<&str> = actions.iter().map(|action| action.label.as_str()).collect();
+        assert_eq!(labels, ["Lowercase all keywords"]);
+    }
+}
@@ -206,20 +206,90 @@ fn get_completion_for_select(
     None
 }
 
+/// Returns the completions after the `->`, `<-` or `<->` of a graph traversal before `offset`: the
+/// relations that can be traversed from the table the traversal is at, or the tables that the
+/// relation it has just stepped through leads to. `None` when `offset` does not follow an arrow.
+pub(crate) fn traversal_completions(
+    tokens: &[crate::lexer::Token<'_>],
+    offset: usize,
+    schema: &crate::schema::Schema,
+) -> Option<Vec<crate::CompletionItem>> {
+    let mut arrow = tokens.iter().rposition(|token| token.end <= offset)?;
+    // Skip the name being typed.
+    if tokens[arrow].kind == crate::lexer::TokenKind::Ident && tokens[arrow].end == offset {
+        arrow = arrow.checked_sub(1)?;
+    }
+    if !["->", "<-", "<->"].iter().any(|punct| tokens[arrow].is_punct(punct)) {
+        return None;
+    }
+    let statement = crate::query::statements(tokens)
+        .into_iter()
+        .filter(|statement| statement.contains(&arrow))
+        .min_by_key(|statement| statement.len())?;
+    let traversals = crate::query::traversals(&tokens[statement.clone()]);
+    let (traversal, index) = traversals.iter().find_map(|traversal| {
+        let index =
+            traversal.steps.iter().position(|step| step.arrow == arrow - statement.start)?;
+        Some((traversal, index))
+    })?;
+    let step = &traversal.steps[index];
+
+    let items = if index % 2 == 0 {
+        let node = match index {
+            0 => traversal.start.clone(),
+            _ => traversal.steps[index - 1].table.as_ref().map(|table| table.name.clone()),
+        };
+        schema
+            .tables
+            .values()
+            .filter(|table| {
+                table.kind.ends(step.direction).is_some_and(|(sources, _)| {
+                    node.as_ref().map_or(true, |node| sources.is_empty() || sources.contains(node))
+                })
+            })
+            .map(|table| crate::CompletionItem {
+                label: table.name.clone(),
+                kind: crate::CompletionKind::Table,
+                documentation: Some(format!(
+                    "```surql\nDEFINE TABLE {} TYPE {}\n```",
+                    table.name, table.kind
+                )),
+            })
+            .collect()
+    } else {
+        let edge = &traversal.steps[index - 1];
+        let targets = edge
+            .table
+            .as_ref()
+            .and_then(|edge| schema.tables.get(&edge.name))
+            .and_then(|edge_definition| edge_definition.kind.ends(edge.direction))
+            .map(|(_, targets)| targets)
+            .filter(|targets| !targets.is_empty());
+        let tables = match targets {
+            Some(targets) => targets,
+            None => schema.tables.keys().cloned().collect(),
+        };
+        tables
+            .into_iter()
+            .map(|table| crate::CompletionItem {
+                label: table,
+                kind: crate::CompletionKind::Table,
+                documentation: None,
+            })
+            .collect()
+    };
+    Some(items)
+}
+
 pub(crate) fn get_completion_list(
     curr_doc: &str,
-    parser: &mut tree_sitter::Parser,
-    curr_tree: &mut Option<tree_sitter::Tree>,
-    params: &tower_lsp::lsp_types::CompletionParams,
+    cursor: tree_sitter::Point,
 ) -> Option<Vec<String>> {
-    let cursor_line = params.text_document_position.position.line as usize;
-    let cursor_char = params.text_document_position.position.character as usize;
-
     let (normalized_doc, cursor_line, cursor_char) =
-        normalize_document_and_cursor_position(curr_doc, cursor_line, cursor_char);
+        normalize_document_and_cursor_position(curr_doc, cursor.row, cursor.column);
 
-    *curr_tree = parser.parse(&normalized_doc, curr_tree.as_ref());
-    if let Some(tree) = curr_tree {
+    // The document is parsed afresh, as it no longer matches its own tree once normalised.
+    if let Some(tree) = crate::parser::parse(&normalized_doc, None) {
         let mut cursor = tree_sitter::QueryCursor::new();
         let doc_bytes = normalized_doc.as_bytes();
         let root_node = tree.root_node();
@@ -256,3 +326,47 @@ pub(crate) fn get_completion_list(
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    const SCHEMA: &str = "DEFINE TABLE person; DEFINE TABLE product; DEFINE TABLE shop;
+        DEFINE TABLE purchased TYPE RELATION IN person OUT product;
+        DEFINE TABLE knows TYPE RELATION IN person OUT person;
+        DEFINE TABLE stocks TYPE RELATION IN shop OUT product;";
+
+    /// Returns the labels of the traversal completions at the `|` marker of `marked`.
+    fn completions(marked: &str) -> Option<Vec<String>> {
+        let offset = marked.find('|').unwrap();
+        let query = marked.replace('|', "");
+        let uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        let schema = crate::schema::Schema::from_changes(
+            &crate::schema::changes(&uri, SCHEMA),
+            crate::config::Dialect::V2,
+        );
+        let tokens = crate::lexer::tokenize(&query);
+        let items = super::traversal_completions(&tokens, offset, &schema)?;
+        Some(items.into_iter().map(|item| item.label).collect())
+    }
+
+    #[test]
+    fn offers_the_relations_that_start_at_the_table() {
+        assert_eq!(completions("SELECT ->| FROM person").unwrap(), ["knows", "purchased"]);
+        assert_eq!(completions("SELECT ->pur| FROM person").unwrap(), ["knows", "purchased"]);
+        assert_eq!(completions("SELECT <-| FROM product").unwrap(), ["purchased", "stocks"]);
+        assert_eq!(completions("SELECT <-| FROM person").unwrap(), ["knows"]);
+        assert_eq!(completions("SELECT <->| FROM person").unwrap(), ["knows", "purchased"]);
+        assert_eq!(completions("SELECT <->| FROM shop").unwrap(), ["stocks"]);
+    }
+
+    #[test]
+    fn offers_the_tables_at_the_other_end_of_the_relation() {
+        assert_eq!(completions("RETURN person:ada->purchased->|").unwrap(), ["product"]);
+        assert_eq!(completions("RETURN product:1<-stocks<-|").unwrap(), ["shop"]);
+        assert_eq!(completions("RETURN person:ada<->knows<->|").unwrap(), ["person"]);
+        assert_eq!(
+            completions("RETURN person:ada->follows->|").unwrap(),
+            ["knows", "person", "product", "purchased", "shop", "stocks"]
+        );
+        assert_eq!(completions("SELECT * FROM |person"), None);
+    }
+}
@@ -0,0 +1,314 @@
+//! The `surrealql.toml` project file, which configures the dialect, schema sources, lints,
+//! formatting and database connection of a project.
+
+pub const CONFIG_FILE_NAME: &str = "surrealql.toml";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub enum Dialect {
+    #[serde(rename = "1.x")]
+    V1,
+    #[default]
+    #[serde(rename = "2.x")]
+    V2,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MigrationOrder {
+    /// Files are applied in the lexical order of their paths.
+    #[default]
+    Lexical,
+    /// Files are applied by the number their file name starts with, e.g. `0002_add_email.surql`.
+    Numeric,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintLevel {
+    Off,
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchemaConfig {
+    pub files: Vec<toml::Spanned<String>>,
+    /// JSON snapshots written by the `snapshot` subcommand.
+    pub snapshots: Vec<toml::Spanned<String>>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MigrationsConfig {
+    pub files: Vec<toml::Spanned<String>>,
+    pub order: MigrationOrder,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeywordCase {
+    #[default]
+    Upper,
+    Lower,
+    /// Keywords are left as written.
+    Preserve,
+}
+
+/// How the formatter lays out documents.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FormatConfig {
+    /// The case of keywords, which also applies to keywords as they are typed.
+    pub keyword_case: KeywordCase,
+    /// The number of spaces per level of indentation.
+    pub indent_width: usize,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self { keyword_case: KeywordCase::Upper, indent_width: 4 }
+    }
+}
+
+/// Which inlay hints are shown.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct HintsConfig {
+    /// The inferred type of `LET` bindings without a declared type.
+    pub let_types: bool,
+    /// The parameter names of positional arguments in built-in and `fn::` calls.
+    pub parameter_names: bool,
+    /// The table a record link resolves to in field paths such as `author.name`.
+    pub record_links: bool,
+}
+
+impl Default for HintsConfig {
+    fn default() -> Self {
+        Self { let_types: true, parameter_names: true, record_links: true }
+    }
+}
+
+/// The SurrealDB instance that queries are executed against.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectionConfig {
+    /// An `http(s)://` or `ws(s)://` URL, e.g. `http://localhost:8000`.
+    pub endpoint: toml::Spanned<String>,
+    /// Falls back to the `SURREAL_USER` environment variable.
+    pub username: Option<String>,
+    /// Falls back to the `SURREAL_PASS` environment variable, so that it need not be checked in.
+    pub password: Option<String>,
+    /// Whether to merge the schema of the database into the workspace schema.
+    #[serde(default)]
+    pub introspect: bool,
+}
+
+/// The contents of a `surrealql.toml` project file.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// The directory containing the project file, which relative globs are resolved against.
+    #[serde(skip)]
+    pub root: std::path::PathBuf,
+    pub dialect: Dialect,
+    pub namespace: Option<toml::Spanned<String>>,
+    pub database: Option<toml::Spanned<String>>,
+    pub schema: SchemaConfig,
+    pub migrations: MigrationsConfig,
+    /// The level of each lint by name, with the span of the name for diagnostics.
+    pub lints: std::collections::BTreeMap<toml::Spanned<String>, LintLevel>,
+    pub format: FormatConfig,
+    pub hints: HintsConfig,
+    pub connection: Option<ConnectionConfig>,
+}
+
+impl ProjectConfig {
+    /// Returns whether `path` lives inside the project.
+    pub fn contains(&self, path: &std::path::Path) -> bool {
+        path.starts_with(&self.root)
+    }
+
+    /// Returns the files whose definitions make up the project's schema: the schema files sorted
+    /// by path, followed by the migration files in the configured order.
+    pub fn schema_sources(&self) -> Vec<std::path::PathBuf> {
+        let mut files = self.expand_globs(&self.schema.files);
+        let migrations = self.migration_sources();
+        files.retain(|file| !migrations.contains(file));
+        files.extend(migrations);
+        files
+    }
+
+    /// Returns the migration files of the project in the configured order.
+    pub fn migration_sources(&self) -> Vec<std::path::PathBuf> {
+        let mut migrations = self.expand_globs(&self.migrations.files);
+        if self.migrations.order == MigrationOrder::Numeric {
+            migrations.sort_by_key(|path| (migration_number(path), path.clone()));
+        }
+        migrations
+    }
+
+    /// Returns the schema snapshots of the project, sorted by path.
+    pub fn snapshot_sources(&self) -> Vec<std::path::PathBuf> {
+        self.expand_globs(&self.schema.snapshots)
+    }
+
+    fn expand_globs(&self, patterns: &[toml::Spanned<String>]) -> Vec<std::path::PathBuf> {
+        let mut files = std::collections::BTreeSet::new();
+        for pattern in patterns {
+            let pattern = self.root.join(pattern.get_ref());
+            let Ok(paths) = glob::glob(&pattern.to_string_lossy()) else {
+                continue;
+            };
+            files.extend(paths.flatten().filter(|path| path.is_file()));
+        }
+        files.into_iter().collect()
+    }
+}
+
+fn migration_number(path: &std::path::Path) -> u64 {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.chars().take_while(char::is_ascii_digit).collect::<String>())
+        .and_then(|digits| digits.parse().ok())
+        .unwrap_or(u64::MAX)
+}
+
+/// Looks for a project file in `root` and then in each of its ancestors.
+pub fn discover(root: &std::path::Path) -> Option<std::path::PathBuf> {
+    root.ancestors().map(|dir| dir.join(CONFIG_FILE_NAME)).find(|path| path.is_file())
+}
+
+/// Returns `path` itself when it is a file, or else every `.surql` file below it, sorted by path.
+pub fn surql_files(path: &std::path::Path) -> Result<Vec<std::path::PathBuf>, String> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let paths =
+        glob::glob(&path.join("**/*.surql").to_string_lossy()).map_err(|err| err.to_string())?;
+    Ok(paths.flatten().filter(|path| path.is_file()).collect())
+}
+
+/// Reads the project file at `path`, returning why it could not be read or deserialised.
+pub fn load(path: &std::path::Path) -> Result<ProjectConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    match parse(path, &content) {
+        (Some(config), _) => Ok(config),
+        (None, diagnostics) => {
+            let message = diagnostics.first().map_or("", |diagnostic| &diagnostic.message);
+            Err(format!("Invalid {}: {}", path.display(), message))
+        }
+    }
+}
+
+/// Validates `content`, the text of the project file at `path`.
+///
+/// The diagnostics are reported against the project file itself. A configuration is only returned
+/// when the file could be deserialised; semantic problems such as malformed globs are reported
+/// without discarding the rest of the file.
+pub fn parse(
+    path: &std::path::Path,
+    content: &str,
+) -> (Option<ProjectConfig>, Vec<crate::Diagnostic>) {
+    let mut config = match toml::from_str::<ProjectConfig>(content) {
+        Ok(config) => config,
+        Err(err) => {
+            let diagnostic = config_diagnostic(
+                err.span().unwrap_or_default(),
+                crate::Severity::Error,
+                err.message().to_string(),
+            );
+            return (None, vec![diagnostic]);
+        }
+    };
+    config.root = path.parent().map(std::path::Path::to_path_buf).unwrap_or_default();
+
+    let mut diagnostics = Vec::new();
+    let patterns = [&config.schema.files, &config.schema.snapshots, &config.migrations.files];
+    for pattern in patterns.into_iter().flatten() {
+        if let Err(err) = glob::Pattern::new(pattern.get_ref()) {
+            diagnostics.push(config_diagnostic(
+                pattern.span(),
+                crate::Severity::Error,
+                format!("Invalid glob pattern: {}", err),
+            ));
+        }
+    }
+    for (name, value) in [("namespace", &config.namespace), ("database", &config.database)] {
+        if let Some(value) = value.as_ref().filter(|value| value.get_ref().is_empty()) {
+            diagnostics.push(config_diagnostic(
+                value.span(),
+                crate::Severity::Warning,
+                format!("`{}` should not be empty", name),
+            ));
+        }
+    }
+    if let Some(endpoint) = config.connection.as_ref().map(|connection| &connection.endpoint) {
+        let message = match url::Url::parse(endpoint.get_ref()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https" | "ws" | "wss") => None,
+            Ok(url) => Some(format!("Unsupported endpoint scheme `{}`", url.scheme())),
+            Err(err) => Some(format!("Invalid endpoint: {}", err)),
+        };
+        if let Some(message) = message {
+            diagnostics.push(config_diagnostic(endpoint.span(), crate::Severity::Error, message));
+        }
+    }
+
+    for name in config.lints.keys() {
+        if crate::Lint::from_name(name.get_ref()).is_none() {
+            diagnostics.push(config_diagnostic(
+                name.span(),
+                crate::Severity::Warning,
+                format!("Unknown lint `{}`", name.get_ref()),
+            ));
+        }
+    }
+
+    (Some(config), diagnostics)
+}
+
+fn config_diagnostic(
+    range: crate::TextRange,
+    severity: crate::Severity,
+    message: String,
+) -> crate::Diagnostic {
+    crate::Diagnostic {
+        range,
+        severity,
+        lint: None,
+        message,
+        tags: Vec::new(),
+        related: Vec::new(),
+        fixes: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn unknown_lint_is_reported_on_its_key() {
+        let content = "# unknown-tabel is misspelt below\n\
+                       namespace = \"unknown-tabel\"\n\
+                       [lints]\n\
+                       unknown-table = \"off\"\n\
+                       unknown-tabel = \"error\"\n";
+        let (config, diagnostics) = super::parse(std::path::Path::new("surrealql.toml"), content);
+        assert!(config.is_some());
+        assert_eq!(diagnostics.len(), 1);
+        let start = content.rfind("unknown-tabel").unwrap();
+        assert_eq!(diagnostics[0].range, start..start + "unknown-tabel".len());
+        assert_eq!(diagnostics[0].message, "Unknown lint `unknown-tabel`");
+    }
+
+    #[test]
+    fn quoted_lint_keys_are_looked_up_by_name() {
+        let content = "[lints]\n\"unused-param\" = \"warning\"\n";
+        let (config, diagnostics) = super::parse(std::path::Path::new("surrealql.toml"), content);
+        assert!(diagnostics.is_empty());
+        let config = config.unwrap();
+        assert_eq!(config.lints.get("unused-param"), Some(&super::LintLevel::Warning));
+    }
+}
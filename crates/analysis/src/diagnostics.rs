@@ -0,0 +1,1538 @@
+/// The semantic checks of a document, which can be configured in `surrealql.toml`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lint {
+    UnknownTable,
+    UnknownField,
+    UnknownIndex,
+    UndefinedParam,
+    UnusedParam,
+    ShadowedParam,
+    TypeMismatch,
+    MissingSemicolon,
+    UnknownFunction,
+    DeprecatedSyntax,
+    InvalidTraversal,
+}
+
+impl Lint {
+    pub const ALL: &'static [Lint] = &[
+        Lint::UnknownTable,
+        Lint::UnknownField,
+        Lint::UnknownIndex,
+        Lint::UndefinedParam,
+        Lint::UnusedParam,
+        Lint::ShadowedParam,
+        Lint::TypeMismatch,
+        Lint::MissingSemicolon,
+        Lint::UnknownFunction,
+        Lint::DeprecatedSyntax,
+        Lint::InvalidTraversal,
+    ];
+
+    /// The name used for the lint in `surrealql.toml` and as the diagnostic code.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnknownTable => "unknown-table",
+            Lint::UnknownField => "unknown-field",
+            Lint::UnknownIndex => "unknown-index",
+            Lint::UndefinedParam => "undefined-param",
+            Lint::UnusedParam => "unused-param",
+            Lint::ShadowedParam => "shadowed-param",
+            Lint::TypeMismatch => "type-mismatch",
+            Lint::MissingSemicolon => "missing-semicolon",
+            Lint::UnknownFunction => "unknown-function",
+            Lint::DeprecatedSyntax => "deprecated-syntax",
+            Lint::InvalidTraversal => "invalid-traversal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.iter().copied().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+/// Extra information about a diagnostic, which editors may render differently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tag {
+    /// The code is obsolete, e.g. 1.x syntax.
+    Deprecated,
+    /// The code is unused, e.g. a `LET` binding that is never read.
+    Unnecessary,
+}
+
+/// A location related to a diagnostic, such as the definition it refers to.
+#[derive(Clone, Debug)]
+pub struct Related {
+    pub location: crate::FileRange,
+    pub message: String,
+}
+
+/// A problem found in a file.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub range: crate::TextRange,
+    pub severity: Severity,
+    /// The check that reported the problem, or `None` for syntax errors and problems with the
+    /// project file, which cannot be turned off.
+    pub lint: Option<Lint>,
+    pub message: String,
+    pub tags: Vec<Tag>,
+    pub related: Vec<Related>,
+    /// The quick fixes of the problem.
+    pub fixes: Vec<crate::SourceChange>,
+}
+
+/// A quick fix replacing `span` of `uri` with `text`.
+fn fix(
+    title: String,
+    uri: &url::Url,
+    span: std::ops::Range<usize>,
+    text: String,
+) -> crate::SourceChange {
+    let edit = crate::TextEdit { range: span, new_text: text };
+    crate::SourceChange::new(title, crate::AssistKind::QuickFix, uri, vec![edit])
+}
+
+fn with_fixes(mut diagnostic: Diagnostic, fixes: Vec<crate::SourceChange>) -> Diagnostic {
+    diagnostic.fixes = fixes;
+    diagnostic
+}
+
+/// Resolves the severity of a lint, applying any override from the project configuration.
+fn severity(
+    config: Option<&crate::config::ProjectConfig>,
+    lint: Lint,
+    default: Severity,
+) -> Option<Severity> {
+    let level = config.and_then(|config| config.lints.get(lint.name()));
+    match level {
+        None => Some(default),
+        Some(crate::config::LintLevel::Off) => None,
+        Some(crate::config::LintLevel::Hint) => Some(Severity::Hint),
+        Some(crate::config::LintLevel::Info) => Some(Severity::Info),
+        Some(crate::config::LintLevel::Warning) => Some(Severity::Warning),
+        Some(crate::config::LintLevel::Error) => Some(Severity::Error),
+    }
+}
+
+fn diagnostic(
+    span: std::ops::Range<usize>,
+    lint: Lint,
+    severity: Severity,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range: span,
+        severity,
+        lint: Some(lint),
+        message,
+        tags: Vec::new(),
+        related: Vec::new(),
+        fixes: Vec::new(),
+    }
+}
+
+fn did_you_mean(sigil: &str, suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(" Did you mean `{}{}`?", sigil, suggestion),
+        None => String::new(),
+    }
+}
+
+/// A fix replacing a misspelled name at `span` with `suggestion`.
+fn rename_fix(
+    uri: &url::Url,
+    span: std::ops::Range<usize>,
+    suggestion: Option<&str>,
+) -> Vec<crate::SourceChange> {
+    suggestion
+        .map(|suggestion| {
+            let title = format!("Change to `{}`", suggestion);
+            fix(title, uri, span, suggestion.to_string()).preferred()
+        })
+        .into_iter()
+        .collect()
+}
+
+/// Runs every semantic check on `source`, the text of `uri` made of `tokens`.
+pub(crate) fn semantic_diagnostics(
+    uri: &url::Url,
+    source: &str,
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = syntax_diagnostics(uri, source, tokens);
+    diagnostics.extend(semicolon_diagnostics(uri, source, tokens, config));
+    diagnostics.extend(schema_diagnostics(uri, tokens, schema, config));
+    diagnostics.extend(traversal_diagnostics(uri, tokens, schema, config));
+    diagnostics.extend(function_diagnostics(uri, tokens, schema, config));
+    diagnostics.extend(deprecation_diagnostics(uri, source, tokens, config));
+    diagnostics.extend(param_diagnostics(uri, tokens, schema, config));
+    diagnostics.extend(type_diagnostics(tokens, schema, config));
+    diagnostics
+}
+
+/// Reports the errors the lexer can find: unterminated strings, quoted identifiers and comments,
+/// unknown characters and unbalanced brackets.
+///
+/// They are found from the tokens rather than the syntax tree, as the tree of a document being
+/// typed is full of errors that are only incomplete statements.
+fn syntax_diagnostics(
+    uri: &url::Url,
+    source: &str,
+    tokens: &[crate::lexer::Token<'_>],
+) -> Vec<Diagnostic> {
+    let error = |span, message| Diagnostic {
+        range: span,
+        severity: Severity::Error,
+        lint: None,
+        message,
+        tags: Vec::new(),
+        related: Vec::new(),
+        fixes: Vec::new(),
+    };
+    let mut diagnostics = Vec::new();
+    let mut open: Vec<&crate::lexer::Token<'_>> = Vec::new();
+    let mut previous_end = 0;
+    for token in tokens {
+        if let Some(start) = unterminated_comment(&source[previous_end..token.start]) {
+            let span = previous_end + start..token.start;
+            diagnostics.push(error(span, String::from("Unterminated comment")));
+        }
+        previous_end = token.end;
+        match token.kind {
+            crate::lexer::TokenKind::String if !is_terminated(token.text) => {
+                diagnostics.push(error(token.span(), String::from("Unterminated string")));
+            }
+            crate::lexer::TokenKind::Ident if !is_terminated(token.text) => {
+                diagnostics.push(error(token.span(), String::from("Unterminated identifier")));
+            }
+            crate::lexer::TokenKind::Unknown => {
+                let message = format!("Unexpected character `{}`", token.text);
+                diagnostics.push(error(token.span(), message));
+            }
+            crate::lexer::TokenKind::Punct => match token.text {
+                "(" | "[" | "{" => open.push(token),
+                ")" | "]" | "}" => match open.pop() {
+                    Some(opening) if closing_bracket(opening.text) == token.text => {}
+                    Some(opening) => {
+                        let message = format!(
+                            "Expected `{}` to close the `{}`, found `{}`",
+                            closing_bracket(opening.text),
+                            opening.text,
+                            token.text
+                        );
+                        let mut diagnostic = error(token.span(), message);
+                        diagnostic.related.push(Related {
+                            location: crate::FileRange { file: uri.clone(), range: opening.span() },
+                            message: String::from("Opened here"),
+                        });
+                        diagnostics.push(diagnostic);
+                    }
+                    None => {
+                        let message = format!("Unexpected `{}`", token.text);
+                        diagnostics.push(error(token.span(), message));
+                    }
+                },
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    if let Some(start) = unterminated_comment(&source[previous_end..]) {
+        diagnostics
+            .push(error(previous_end + start..source.len(), String::from("Unterminated comment")));
+    }
+    for opening in open {
+        let message = format!("`{}` is never closed", opening.text);
+        diagnostics.push(error(opening.span(), message));
+    }
+    diagnostics
+}
+
+fn closing_bracket(opening: &str) -> &'static str {
+    match opening {
+        "(" => ")",
+        "[" => "]",
+        _ => "}",
+    }
+}
+
+/// Returns whether a string or quoted identifier ends with its closing quote. Bare identifiers
+/// are always terminated.
+fn is_terminated(text: &str) -> bool {
+    let quoted = text.trim_start_matches(['r', 'd', 'u', 's']);
+    let mut chars = quoted.chars();
+    let close = match chars.next() {
+        Some(quote @ ('\'' | '"' | '`')) => quote,
+        Some('⟨') => '⟩',
+        _ => return true,
+    };
+    let mut escaped = false;
+    for ch in chars {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if close != '`' && close != '⟩' => escaped = true,
+            _ if ch == close => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Returns the offset of a block comment within `gap`, the text between two tokens, that is
+/// never closed.
+fn unterminated_comment(gap: &str) -> Option<usize> {
+    let mut rest = gap;
+    while let Some(start) = rest.find(['-', '/', '#']) {
+        let offset = gap.len() - rest.len() + start;
+        let comment = &rest[start..];
+        if let Some(body) = comment.strip_prefix("/*") {
+            match body.find("*/") {
+                Some(end) => rest = &body[end + 2..],
+                None => return Some(offset),
+            }
+        } else {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+        }
+    }
+    None
+}
+
+/// Checks the tables and fields referenced by `tokens` against the workspace schema.
+///
+/// Unknown tables are only reported once the workspace defines at least one table, so that
+/// projects without a schema are not flooded with warnings.
+fn schema_diagnostics(
+    uri: &url::Url,
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if schema.tables.is_empty() {
+        return diagnostics;
+    }
+
+    for range in crate::query::statements(tokens) {
+        let statement = &tokens[range];
+        if matches!(
+            crate::query::kind(statement),
+            crate::query::StatementKind::Define | crate::query::StatementKind::Remove
+        ) {
+            continue;
+        }
+
+        let tables = crate::query::table_references(statement);
+        for table in &tables {
+            if schema.tables.contains_key(&table.name) {
+                continue;
+            }
+            let Some(severity) = severity(config, Lint::UnknownTable, Severity::Warning) else {
+                continue;
+            };
+            let suggestion =
+                crate::schema::suggest(&table.name, schema.tables.keys().map(String::as_str));
+            let diagnostic = diagnostic(
+                table.span.clone(),
+                Lint::UnknownTable,
+                severity,
+                format!("Table `{}` is not defined.{}", table.name, did_you_mean("", suggestion)),
+            );
+            diagnostics
+                .push(with_fixes(diagnostic, rename_fix(uri, table.span.clone(), suggestion)));
+        }
+
+        // Fields can only be attributed to a table when the statement has a single target.
+        let [table] = tables.as_slice() else {
+            continue;
+        };
+        let Some(definition) = schema.tables.get(&table.name) else {
+            continue;
+        };
+        diagnostics.extend(index_diagnostics(uri, statement, &table.name, schema, config));
+        let default_severity =
+            if definition.schemafull { Severity::Warning } else { Severity::Hint };
+        let Some(severity) = severity(config, Lint::UnknownField, default_severity) else {
+            continue;
+        };
+        let assignments = crate::query::assignments(statement);
+        for field in crate::query::field_references(statement) {
+            if schema.has_field(&table.name, &field.name) {
+                continue;
+            }
+            let top_level_fields = schema
+                .fields_of(&table.name)
+                .map(|field| field.name.as_str())
+                .filter(|name| !name.contains(['.', '[']));
+            let suggestion = crate::schema::suggest(&field.name, top_level_fields);
+            let mut diagnostic = diagnostic(
+                field.span.clone(),
+                Lint::UnknownField,
+                severity,
+                format!(
+                    "Field `{}` is not defined on table `{}`.{}",
+                    field.name,
+                    table.name,
+                    did_you_mean("", suggestion)
+                ),
+            );
+            // Tables from a database or a snapshot have no document to point to.
+            if definition.end.is_some() {
+                diagnostic.related = vec![Related {
+                    location: definition.location.clone(),
+                    message: format!("`{}` is defined here", table.name),
+                }];
+            }
+
+            let mut fixes = rename_fix(uri, field.span.clone(), suggestion);
+            if let Some(end) = definition.end.as_ref().filter(|_| definition.schemafull) {
+                // Declare the field with the type of the value assigned to it, if any.
+                let kind = assignments
+                    .iter()
+                    .find(|assignment| {
+                        assignment.field_span == field.span && assignment.field == field.name
+                    })
+                    .and_then(|assignment| {
+                        crate::types::infer(&statement[assignment.value.clone()], schema)
+                    })
+                    .map_or(crate::types::Kind::Any, |kind| kind.widened());
+                let edit = crate::TextEdit {
+                    range: end.range.clone(),
+                    new_text: format!(
+                        ";\nDEFINE FIELD {} ON {} TYPE {}",
+                        field.name, table.name, kind
+                    ),
+                };
+                let title = format!("Add `DEFINE FIELD {} ON {}`", field.name, table.name);
+                fixes.push(crate::SourceChange::new(
+                    title,
+                    crate::AssistKind::QuickFix,
+                    &end.file,
+                    vec![edit],
+                ));
+            }
+            diagnostics.push(with_fixes(diagnostic, fixes));
+        }
+    }
+
+    diagnostics
+}
+
+/// Checks the indexes named in the `WITH INDEX` clause of `statement` against those defined on
+/// `table`.
+fn index_diagnostics(
+    uri: &url::Url,
+    statement: &[crate::lexer::Token<'_>],
+    table: &str,
+    schema: &crate::schema::Schema,
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(severity) = severity(config, Lint::UnknownIndex, Severity::Warning) else {
+        return diagnostics;
+    };
+    let Some(with) = statement
+        .windows(2)
+        .position(|pair| pair[0].is_keyword("WITH") && pair[1].is_keyword("INDEX"))
+    else {
+        return diagnostics;
+    };
+
+    let indexes = schema.indexes.get(table);
+    let mut index = with + 2;
+    while let Some(name) =
+        statement.get(index).filter(|token| token.kind == crate::lexer::TokenKind::Ident)
+    {
+        if !indexes.is_some_and(|indexes| indexes.contains_key(name.ident())) {
+            let candidates = indexes.into_iter().flat_map(|indexes| indexes.keys());
+            let suggestion = crate::schema::suggest(name.ident(), candidates.map(String::as_str));
+            let diagnostic = diagnostic(
+                name.span(),
+                Lint::UnknownIndex,
+                severity,
+                format!(
+                    "Index `{}` is not defined on table `{}`.{}",
+                    name.ident(),
+                    table,
+                    did_you_mean("", suggestion)
+                ),
+            );
+            diagnostics.push(with_fixes(diagnostic, rename_fix(uri, name.span(), suggestion)));
+        }
+        if !statement.get(index + 1).is_some_and(|token| token.is_punct(",")) {
+            break;
+        }
+        index += 2;
+    }
+    diagnostics
+}
+
+/// Checks the graph traversals of `tokens` against the `IN` and `OUT` tables of the relations they
+/// step through, e.g. `->purchased->product` from a table that `purchased` does not start at.
+fn traversal_diagnostics(
+    uri: &url::Url,
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(severity) = severity(config, Lint::InvalidTraversal, Severity::Warning) else {
+        return diagnostics;
+    };
+
+    for range in crate::query::statements(tokens) {
+        for traversal in crate::query::traversals(&tokens[range]) {
+            let mut node = traversal.start;
+            // The tables that the last edge leads to, or `None` when it could be any.
+            let mut targets: Option<(&str, Vec<String>)> = None;
+            for (index, step) in traversal.steps.iter().enumerate() {
+                if index % 2 == 1 {
+                    if let (Some((edge, targets)), Some(table)) = (&targets, &step.table) {
+                        if !targets.is_empty() && !targets.contains(&table.name) {
+                            let suggestion = crate::schema::suggest(
+                                &table.name,
+                                targets.iter().map(String::as_str),
+                            );
+                            let message = format!(
+                                "`{}` {} `{}`, not `{}`.{}",
+                                edge,
+                                match step.direction {
+                                    crate::query::Direction::Out => "leads to",
+                                    crate::query::Direction::In => "comes from",
+                                    crate::query::Direction::Both => "connects",
+                                },
+                                targets.join("` | `"),
+                                table.name,
+                                did_you_mean("", suggestion)
+                            );
+                            let diagnostic = diagnostic(
+                                table.span.clone(),
+                                Lint::InvalidTraversal,
+                                severity,
+                                message,
+                            );
+                            let fixes = rename_fix(uri, table.span.clone(), suggestion);
+                            diagnostics.push(with_fixes(diagnostic, fixes));
+                        }
+                    }
+                    node = step.table.as_ref().map(|table| table.name.clone());
+                    targets = None;
+                    continue;
+                }
+
+                let Some(edge) = &step.table else {
+                    targets = None;
+                    continue;
+                };
+                let Some(definition) = schema.tables.get(&edge.name) else {
+                    targets = None;
+                    continue;
+                };
+                let message = match definition.kind.ends(step.direction) {
+                    None if definition.kind == crate::schema::TableKind::Normal => Some(format!(
+                        "`{}` is a `TYPE NORMAL` table, so it has no edges to traverse.",
+                        edge.name
+                    )),
+                    None => None,
+                    Some((sources, to)) => {
+                        let message = node
+                            .as_ref()
+                            .filter(|node| !sources.is_empty() && !sources.contains(node))
+                            .map(|node| {
+                                format!(
+                                    "`{}` {} `{}`, so it cannot be traversed from `{}`.",
+                                    edge.name,
+                                    match step.direction {
+                                        crate::query::Direction::Out => "starts at",
+                                        crate::query::Direction::In => "ends at",
+                                        crate::query::Direction::Both => "connects",
+                                    },
+                                    sources.join("` | `"),
+                                    node
+                                )
+                            });
+                        targets = Some((edge.name.as_str(), to));
+                        message
+                    }
+                };
+                let Some(message) = message else {
+                    continue;
+                };
+                let mut diagnostic =
+                    diagnostic(edge.span.clone(), Lint::InvalidTraversal, severity, message);
+                if definition.end.is_some() {
+                    diagnostic.related = vec![Related {
+                        location: definition.location.clone(),
+                        message: format!("`{}` is defined here", edge.name),
+                    }];
+                }
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Keywords that start a statement.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "ALTER", "BEGIN", "CANCEL", "COMMIT", "CREATE", "DEFINE", "DELETE", "FOR", "IF", "INFO",
+    "INSERT", "KILL", "LET", "LIVE", "OPTION", "REBUILD", "RELATE", "REMOVE", "RETURN", "SELECT",
+    "SHOW", "SLEEP", "THROW", "UPDATE", "UPSERT", "USE",
+];
+
+/// Keywords after which a statement keyword introduces a subquery or clause rather than a new
+/// statement, e.g. `ON DUPLICATE KEY UPDATE` or `DEFINE TABLE ... AS SELECT`.
+const CONTINUATION_KEYWORDS: &[&str] = &[
+    "AND", "AS", "ASSERT", "BY", "CONTENT", "DEFAULT", "ELSE", "FOR", "FROM", "IF", "IN", "INFO",
+    "INTO", "KEY", "LIVE", "NOT", "ONLY", "OR", "RETURN", "SET", "SHOW", "THEN", "VALUE", "WHEN",
+    "WHERE",
+];
+
+/// Reports a statement keyword at the start of a line that continues the previous statement,
+/// which is almost always a forgotten `;`.
+fn semicolon_diagnostics(
+    uri: &url::Url,
+    source: &str,
+    tokens: &[crate::lexer::Token<'_>],
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(severity) = severity(config, Lint::MissingSemicolon, Severity::Error) else {
+        return diagnostics;
+    };
+
+    for range in crate::query::statements(tokens) {
+        let statement = &tokens[range];
+        let is_write = matches!(
+            crate::query::kind(statement),
+            crate::query::StatementKind::Create
+                | crate::query::StatementKind::Update
+                | crate::query::StatementKind::Upsert
+                | crate::query::StatementKind::Delete
+                | crate::query::StatementKind::Insert
+                | crate::query::StatementKind::Relate
+        );
+        let mut depth = 0usize;
+        let mut permissions = false;
+        for (index, token) in statement.iter().enumerate() {
+            match token.text {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            permissions |= token.is_keyword("PERMISSIONS");
+            if index == 0 || depth > 0 {
+                continue;
+            }
+            if !STATEMENT_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword))
+                // `PERMISSIONS FOR select` and `CREATE ... RETURN AFTER` are clauses.
+                || (permissions && token.is_keyword("FOR"))
+                || (is_write && token.is_keyword("RETURN"))
+            {
+                continue;
+            }
+            let previous = &statement[index - 1];
+            let continues = (previous.kind == crate::lexer::TokenKind::Punct
+                && !matches!(previous.text, ")" | "]" | "}"))
+                || CONTINUATION_KEYWORDS.iter().any(|keyword| previous.is_keyword(keyword));
+            if continues || !source[previous.end..token.start].contains('\n') {
+                continue;
+            }
+            let diagnostic = diagnostic(
+                previous.span(),
+                Lint::MissingSemicolon,
+                severity,
+                format!("Expected `;` before `{}`.", token.text),
+            );
+            let fix = fix(
+                String::from("Insert missing `;`"),
+                uri,
+                previous.end..previous.end,
+                String::from(";"),
+            );
+            diagnostics.push(with_fixes(diagnostic, vec![fix.preferred()]));
+        }
+    }
+    diagnostics
+}
+
+/// Checks calls against the built-in functions and the `DEFINE FUNCTION`s of the workspace.
+///
+/// Like tables, custom functions are only checked once the workspace defines at least one.
+fn function_diagnostics(
+    uri: &url::Url,
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(severity) = severity(config, Lint::UnknownFunction, Severity::Warning) else {
+        return diagnostics;
+    };
+
+    for call in crate::query::calls(tokens) {
+        // Renamed 1.x functions are reported as deprecated syntax instead.
+        if crate::functions::RENAMED.iter().any(|(old, _)| old.eq_ignore_ascii_case(&call.name)) {
+            continue;
+        }
+        let suggestion = if call.name.starts_with("fn::") {
+            if schema.functions.is_empty() || schema.functions.contains_key(&call.name) {
+                continue;
+            }
+            crate::schema::suggest(&call.name, schema.functions.keys().map(String::as_str))
+        } else {
+            let name = call.name.to_lowercase();
+            let Some((namespace, _)) = name.split_once("::") else {
+                continue;
+            };
+            if crate::functions::builtin(&name).is_some() {
+                continue;
+            }
+            let suggestion = crate::schema::suggest(&name, crate::functions::names());
+            // Outside of the built-in namespaces only report likely typos, e.g. `strng::len`.
+            if suggestion.is_none() && !crate::functions::is_namespace(namespace) {
+                continue;
+            }
+            suggestion
+        };
+        let diagnostic = diagnostic(
+            call.span.clone(),
+            Lint::UnknownFunction,
+            severity,
+            format!("Function `{}` does not exist.{}", call.name, did_you_mean("", suggestion)),
+        );
+        diagnostics.push(with_fixes(diagnostic, rename_fix(uri, call.span, suggestion)));
+    }
+    diagnostics
+}
+
+/// Reports SurrealDB 1.x syntax that was removed or renamed in 2.x, unless the project targets
+/// the 1.x dialect.
+fn deprecation_diagnostics(
+    uri: &url::Url,
+    source: &str,
+    tokens: &[crate::lexer::Token<'_>],
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let dialect = config.map(|config| config.dialect).unwrap_or_default();
+    if dialect == crate::config::Dialect::V1 {
+        return diagnostics;
+    }
+    let Some(severity) = severity(config, Lint::DeprecatedSyntax, Severity::Warning) else {
+        return diagnostics;
+    };
+    let mut report =
+        |span: std::ops::Range<usize>, message: String, fixes: Vec<crate::SourceChange>| {
+            let mut diagnostic = diagnostic(span, Lint::DeprecatedSyntax, severity, message);
+            diagnostic.tags = vec![Tag::Deprecated];
+            diagnostics.push(with_fixes(diagnostic, fixes));
+        };
+
+    for call in crate::query::calls(tokens) {
+        let renamed =
+            crate::functions::RENAMED.iter().find(|(old, _)| old.eq_ignore_ascii_case(&call.name));
+        if let Some((_, new)) = renamed {
+            let title = format!("Replace with `{}`", new);
+            let fix = fix(title, uri, call.span.clone(), new.to_string());
+            report(
+                call.span,
+                format!("`{}` was renamed to `{}` in SurrealDB 2.x.", call.name, new),
+                vec![fix.preferred()],
+            );
+        }
+    }
+
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind == crate::lexer::TokenKind::Param && token.ident() == "scope" {
+            let fix = fix(
+                String::from("Replace with `$access`"),
+                uri,
+                token.span(),
+                String::from("$access"),
+            );
+            report(
+                token.span(),
+                String::from("`$scope` was replaced by `$access` in SurrealDB 2.x."),
+                vec![fix.preferred()],
+            );
+        }
+
+        // The `record(a, b)` and `geometry(point)` type syntax became `record<a | b>`.
+        let is_type_position = index > 0
+            && (tokens[index - 1].is_keyword("TYPE")
+                || ["<", "|", ":"].iter().any(|punct| tokens[index - 1].is_punct(punct)));
+        let is_parenthesised_type = (token.is_keyword("record") || token.is_keyword("geometry"))
+            && tokens.get(index + 1).is_some_and(|next| next.is_punct("("));
+        if is_type_position && is_parenthesised_type {
+            let Some(close) = crate::lexer::matching_bracket(tokens, index + 1) else {
+                continue;
+            };
+            let names: Vec<&str> = tokens[index + 2..close]
+                .iter()
+                .filter(|token| token.kind == crate::lexer::TokenKind::Ident)
+                .map(|token| token.text)
+                .collect();
+            let replacement = if names.is_empty() {
+                token.text.to_string()
+            } else {
+                format!("{}<{}>", token.text, names.join(" | "))
+            };
+            let span = token.start..tokens[close].end;
+            let title = format!("Replace with `{}`", replacement);
+            let fix = fix(title, uri, span.clone(), replacement.clone());
+            report(
+                span.clone(),
+                format!("Use `{}` instead of the 1.x `{}` syntax.", replacement, &source[span]),
+                vec![fix.preferred()],
+            );
+        }
+    }
+
+    for statement in crate::query::statements(tokens) {
+        let statement = &tokens[statement];
+        let [define, kind, ..] = statement else {
+            continue;
+        };
+        if !define.is_keyword("DEFINE") {
+            continue;
+        }
+        let span = define.start..kind.end;
+        if kind.is_keyword("SCOPE") {
+            let fixes = scope_to_access(source, statement)
+                .map(|text| {
+                    let span = define.start..statement[statement.len() - 1].end;
+                    let title = String::from("Convert to `DEFINE ACCESS ... TYPE RECORD`");
+                    fix(title, uri, span, text).preferred()
+                })
+                .into_iter()
+                .collect();
+            report(
+                span,
+                String::from(
+                    "`DEFINE SCOPE` was replaced by `DEFINE ACCESS ... TYPE RECORD` in SurrealDB \
+                     2.x.",
+                ),
+                fixes,
+            );
+        } else if kind.is_keyword("TOKEN") {
+            let fixes = token_to_access(statement)
+                .map(|edits| {
+                    let edits = edits
+                        .into_iter()
+                        .map(|(span, text)| crate::TextEdit { range: span, new_text: text })
+                        .collect();
+                    let title = String::from("Convert to `DEFINE ACCESS ... TYPE JWT`");
+                    crate::SourceChange::new(title, crate::AssistKind::QuickFix, uri, edits)
+                        .preferred()
+                })
+                .into_iter()
+                .collect();
+            report(
+                span,
+                String::from(
+                    "`DEFINE TOKEN` was replaced by `DEFINE ACCESS ... TYPE JWT` in SurrealDB 2.x.",
+                ),
+                fixes,
+            );
+        }
+    }
+    diagnostics
+}
+
+/// Rewrites `DEFINE SCOPE name SESSION 1h SIGNUP (...) SIGNIN (...)` as
+/// `DEFINE ACCESS name ON DATABASE TYPE RECORD SIGNUP (...) SIGNIN (...) DURATION FOR SESSION 1h`.
+fn scope_to_access(source: &str, statement: &[crate::lexer::Token<'_>]) -> Option<String> {
+    let name = statement.get(crate::schema::skip_define_modifiers(statement, 2))?;
+    let last = statement.last()?;
+    let session = statement.iter().position(|token| token.is_keyword("SESSION"));
+    let (rest, duration) = match session {
+        Some(session) => {
+            let duration = statement.get(session + 1)?;
+            let rest = format!(
+                "{}{}",
+                source[name.end..statement[session].start].trim_end(),
+                &source[duration.end..last.end]
+            );
+            (rest, format!(" DURATION FOR SESSION {}", duration.text))
+        }
+        None => (source[name.end..last.end].to_string(), String::new()),
+    };
+    Some(format!(
+        "DEFINE ACCESS {} ON DATABASE TYPE RECORD{}{}",
+        &source[statement[2].start..name.end],
+        rest,
+        duration
+    ))
+}
+
+/// Returns the edits turning `DEFINE TOKEN name ON DATABASE TYPE HS512 VALUE "..."` into
+/// `DEFINE ACCESS name ON DATABASE TYPE JWT ALGORITHM HS512 KEY "..."`. Tokens on a scope have no
+/// direct equivalent.
+fn token_to_access(
+    statement: &[crate::lexer::Token<'_>],
+) -> Option<Vec<(std::ops::Range<usize>, String)>> {
+    let on = statement.iter().position(|token| token.is_keyword("ON"))?;
+    if statement.get(on + 1)?.is_keyword("SCOPE") {
+        return None;
+    }
+    let kind = statement.iter().position(|token| token.is_keyword("TYPE"))?;
+    let algorithm = statement.get(kind + 1)?;
+    let value = statement.iter().position(|token| token.is_keyword("VALUE"))?;
+    Some(vec![
+        (statement[1].span(), String::from("ACCESS")),
+        (statement[kind].start..algorithm.end, format!("TYPE JWT ALGORITHM {}", algorithm.text)),
+        (statement[value].span(), String::from("KEY")),
+    ])
+}
+
+/// Reports undefined `$param` uses, unused `LET` bindings and shadowing `LET` bindings.
+fn param_diagnostics(
+    uri: &url::Url,
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for finding in crate::scope::analyse(tokens, schema) {
+        let diagnostic = match finding {
+            crate::scope::Finding::Undefined { name, span } => {
+                let Some(severity) = severity(config, Lint::UndefinedParam, Severity::Warning)
+                else {
+                    continue;
+                };
+                let known = schema.params.keys().map(String::as_str);
+                let suggestion = did_you_mean(
+                    "$",
+                    crate::schema::suggest(
+                        &name,
+                        known.chain(crate::scope::BUILTIN_PARAMS.iter().copied()),
+                    ),
+                );
+                diagnostic(
+                    span,
+                    Lint::UndefinedParam,
+                    severity,
+                    format!("Parameter `${}` is not defined.{}", name, suggestion),
+                )
+            }
+            crate::scope::Finding::Unused { name, span } => {
+                let Some(severity) = severity(config, Lint::UnusedParam, Severity::Hint) else {
+                    continue;
+                };
+                let mut diagnostic = diagnostic(
+                    span,
+                    Lint::UnusedParam,
+                    severity,
+                    format!("Parameter `${}` is never used.", name),
+                );
+                diagnostic.tags = vec![Tag::Unnecessary];
+                diagnostic
+            }
+            crate::scope::Finding::Shadowing { name, span, shadowed } => {
+                let default_severity = match shadowed {
+                    crate::scope::Shadowed::Builtin => Severity::Warning,
+                    _ => Severity::Info,
+                };
+                let Some(severity) = severity(config, Lint::ShadowedParam, default_severity) else {
+                    continue;
+                };
+                let (message, location) = match shadowed {
+                    crate::scope::Shadowed::Builtin => {
+                        (format!("`${}` shadows a built-in parameter.", name), None)
+                    }
+                    crate::scope::Shadowed::Defined(location) => (
+                        format!("`${}` shadows a parameter defined with `DEFINE PARAM`.", name),
+                        Some(location),
+                    ),
+                    crate::scope::Shadowed::Local(outer) => {
+                        let location = crate::FileRange { file: uri.clone(), range: outer };
+                        (
+                            format!("`${}` shadows a parameter of an enclosing scope.", name),
+                            Some(location),
+                        )
+                    }
+                };
+                let mut diagnostic = diagnostic(span, Lint::ShadowedParam, severity, message);
+                diagnostic.related = location
+                    .map(|location| Related {
+                        location,
+                        message: format!("`${}` is defined here", name),
+                    })
+                    .into_iter()
+                    .collect();
+                diagnostic
+            }
+        };
+        diagnostics.push(diagnostic);
+    }
+    diagnostics
+}
+
+/// Checks the values assigned to typed fields in `SET`, `CONTENT`, `MERGE`, `REPLACE` and `INSERT`
+/// against the `TYPE` of their `DEFINE FIELD`.
+fn type_diagnostics(
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+    config: Option<&crate::config::ProjectConfig>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(severity) = severity(config, Lint::TypeMismatch, Severity::Error) else {
+        return diagnostics;
+    };
+    let dialect = config.map(|config| config.dialect).unwrap_or_default();
+
+    for range in crate::query::statements(tokens) {
+        let statement = &tokens[range];
+        let tables = crate::query::table_references(statement);
+        let [table] = tables.as_slice() else {
+            continue;
+        };
+        let Some(fields) = schema.fields.get(&table.name) else {
+            continue;
+        };
+        for assignment in crate::query::assignments(statement) {
+            let Some(expected) =
+                fields.get(&assignment.field).and_then(|field| field.kind.as_ref())
+            else {
+                continue;
+            };
+            let value = &statement[assignment.value.clone()];
+            let Some(found) = crate::types::infer(value, schema) else {
+                continue;
+            };
+            let matches = match assignment.operator.as_str() {
+                "+=" | "-=" | "+?=" => is_compound_assignable(&found, expected, dialect),
+                _ => found.is_assignable_to(expected, dialect),
+            };
+            if matches {
+                continue;
+            }
+            let (Some(first), Some(last)) = (value.first(), value.last()) else {
+                continue;
+            };
+            diagnostics.push(diagnostic(
+                first.start..last.end,
+                Lint::TypeMismatch,
+                severity,
+                format!(
+                    "Expected `{}` for field `{}`, found `{}`.",
+                    expected, assignment.field, found
+                ),
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// Returns whether `value` can be added to or removed from a field of kind `target` with `+=`
+/// or `-=`: numbers, durations and strings combine with their own kind, while arrays and sets
+/// take either an element or another array.
+fn is_compound_assignable(
+    value: &crate::types::Kind,
+    target: &crate::types::Kind,
+    dialect: crate::config::Dialect,
+) -> bool {
+    if let Some(element) = target.element() {
+        return value.is_assignable_to(element, dialect)
+            || value.is_assignable_to(
+                &crate::types::Kind::Array(Box::new(element.clone()), None),
+                dialect,
+            );
+    }
+    value.is_assignable_to(target, dialect)
+}
+
+#[cfg(test)]
+mod tests {
+    const SCHEMA: &str = "DEFINE TABLE person SCHEMAFULL;
+        DEFINE FIELD name ON person TYPE string;
+        DEFINE FIELD age ON person TYPE int;
+        DEFINE FIELD address.city ON person TYPE string;
+        DEFINE TABLE post SCHEMALESS;
+        DEFINE FIELD title ON post TYPE string;";
+
+    fn check(schema: &str, query: &str) -> Vec<super::Diagnostic> {
+        let schema_uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        let changes = crate::schema::changes(&schema_uri, schema);
+        let schema = crate::schema::Schema::from_changes(&changes, crate::config::Dialect::V2);
+        let uri = url::Url::parse("file:///project/query.surql").unwrap();
+        super::semantic_diagnostics(&uri, query, &crate::lexer::tokenize(query), &schema, None)
+    }
+
+    /// Returns the text, severity and message of each diagnostic of `lint`.
+    fn findings(
+        query: &str,
+        diagnostics: &[super::Diagnostic],
+        lint: super::Lint,
+    ) -> Vec<(String, super::Severity, String)> {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.lint == Some(lint))
+            .map(|diagnostic| {
+                let text = query[diagnostic.range.clone()].to_string();
+                (text, diagnostic.severity, diagnostic.message.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unknown_table_suggests_the_closest_table() {
+        let query = "SELECT * FROM persn WHERE age > 18;";
+        let diagnostics = check(SCHEMA, query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::UnknownTable),
+            [(
+                String::from("persn"),
+                super::Severity::Warning,
+                String::from("Table `persn` is not defined. Did you mean `person`?")
+            )]
+        );
+        let fix = &diagnostics[0].fixes[0];
+        assert!(fix.preferred);
+        let edits = fix.edits.values().next().unwrap();
+        assert_eq!(crate::TextEdit::apply(query, edits), "SELECT * FROM person WHERE age > 18;");
+    }
+
+    #[test]
+    fn unknown_table_without_a_close_match_has_no_suggestion() {
+        let query = "CREATE invoice SET total = 1;";
+        let diagnostics = check(SCHEMA, query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::UnknownTable),
+            [(
+                String::from("invoice"),
+                super::Severity::Warning,
+                String::from("Table `invoice` is not defined.")
+            )]
+        );
+        assert!(diagnostics[0].fixes.is_empty());
+    }
+
+    #[test]
+    fn tables_are_not_checked_without_a_schema() {
+        let query = "SELECT * FROM anything;";
+        assert!(check("", query).is_empty());
+    }
+
+    #[test]
+    fn unknown_field_of_a_schemafull_table_is_a_warning() {
+        let query = "UPDATE person SET nmae = 'Tobie', address.city = 'London', id = person:1;";
+        let diagnostics = check(SCHEMA, query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::UnknownField),
+            [(
+                String::from("nmae"),
+                super::Severity::Warning,
+                String::from("Field `nmae` is not defined on table `person`. Did you mean `name`?")
+            )]
+        );
+        let labels: Vec<&str> = diagnostics[0].fixes.iter().map(|fix| fix.label.as_str()).collect();
+        assert_eq!(labels, ["Change to `name`", "Add `DEFINE FIELD nmae ON person`"]);
+        assert_eq!(diagnostics[0].related[0].message, "`person` is defined here");
+    }
+
+    #[test]
+    fn unknown_field_of_a_schemaless_table_is_a_hint() {
+        let query = "SELECT titel FROM post;";
+        let diagnostics = check(SCHEMA, query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::UnknownField),
+            [(
+                String::from("titel"),
+                super::Severity::Hint,
+                String::from("Field `titel` is not defined on table `post`. Did you mean `title`?")
+            )]
+        );
+    }
+
+    #[test]
+    fn defined_fields_and_nested_paths_are_known() {
+        let query = "SELECT name, address.city, address FROM person WHERE age > 18;";
+        assert!(check(SCHEMA, query).is_empty());
+    }
+
+    const GRAPH: &str = "DEFINE TABLE person; DEFINE TABLE product; DEFINE TABLE post TYPE NORMAL;
+        DEFINE TABLE purchased TYPE RELATION IN person OUT product;
+        DEFINE TABLE knows TYPE RELATION IN person OUT person;";
+
+    /// Returns the text and message of each invalid traversal of `query` against [`GRAPH`].
+    fn traversal_findings(query: &str) -> Vec<(String, String)> {
+        let diagnostics = check(GRAPH, query);
+        findings(query, &diagnostics, super::Lint::InvalidTraversal)
+            .into_iter()
+            .map(|(text, _, message)| (text, message))
+            .collect()
+    }
+
+    #[test]
+    fn traversals_follow_the_ends_of_relation_tables() {
+        for query in [
+            "SELECT ->purchased->product FROM person;",
+            "SELECT <-purchased<-person FROM product;",
+            "SELECT <->knows<->person FROM person;",
+            "RETURN person:ada->follows->post;",
+        ] {
+            assert_eq!(traversal_findings(query), [], "{}", query);
+        }
+
+        let finding = |text: &str, message: &str| (String::from(text), String::from(message));
+        assert_eq!(
+            traversal_findings("SELECT ->purchased->post FROM person;"),
+            [finding("post", "`purchased` leads to `product`, not `post`.")]
+        );
+        assert_eq!(
+            traversal_findings("SELECT ->purchased FROM product;"),
+            [finding(
+                "purchased",
+                "`purchased` starts at `person`, so it cannot be traversed from `product`."
+            )]
+        );
+        assert_eq!(
+            traversal_findings("SELECT <-purchased<-post FROM product;"),
+            [finding("post", "`purchased` comes from `person`, not `post`.")]
+        );
+        assert_eq!(
+            traversal_findings("SELECT <->knows FROM post;"),
+            [finding("knows", "`knows` connects `person`, so it cannot be traversed from `post`.")]
+        );
+        assert_eq!(
+            traversal_findings("SELECT ->post FROM person;"),
+            [finding("post", "`post` is a `TYPE NORMAL` table, so it has no edges to traverse.")]
+        );
+    }
+
+    #[test]
+    fn lints_can_be_turned_off_or_raised() {
+        let query = "SELECT * FROM persn;";
+        let (config, _) = crate::config::parse(
+            std::path::Path::new("/project/surrealql.toml"),
+            "[lints]\nunknown-table = \"error\"\nunknown-field = \"off\"\n",
+        );
+        let schema_uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        let schema = crate::schema::Schema::from_changes(
+            &crate::schema::changes(&schema_uri, SCHEMA),
+            crate::config::Dialect::V2,
+        );
+        let uri = url::Url::parse("file:///project/query.surql").unwrap();
+        let check = |query| {
+            let tokens = crate::lexer::tokenize(query);
+            super::semantic_diagnostics(&uri, query, &tokens, &schema, config.as_ref())
+        };
+        let diagnostics = check(query);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, super::Severity::Error);
+
+        let query = "SELECT nmae FROM person;";
+        let diagnostics = check(query);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn param_findings_have_their_default_severities() {
+        let query = "LET $unused = 1; LET $value = 2; RETURN $value + $missing + $limti";
+        let diagnostics = check("DEFINE PARAM $limit VALUE 10;", query);
+        let mut findings: Vec<(String, super::Severity, String)> =
+            [super::Lint::UndefinedParam, super::Lint::UnusedParam, super::Lint::ShadowedParam]
+                .into_iter()
+                .flat_map(|lint| findings(query, &diagnostics, lint))
+                .collect();
+        findings.sort();
+        assert_eq!(
+            findings,
+            [
+                (
+                    String::from("$limti"),
+                    super::Severity::Warning,
+                    String::from("Parameter `$limti` is not defined. Did you mean `$limit`?")
+                ),
+                (
+                    String::from("$missing"),
+                    super::Severity::Warning,
+                    String::from("Parameter `$missing` is not defined.")
+                ),
+                (
+                    String::from("$unused"),
+                    super::Severity::Hint,
+                    String::from("Parameter `$unused` is never used.")
+                ),
+                (
+                    String::from("$value"),
+                    super::Severity::Warning,
+                    String::from("`$value` shadows a built-in parameter.")
+                ),
+            ]
+        );
+        let unused = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.lint == Some(super::Lint::UnusedParam))
+            .unwrap();
+        assert_eq!(unused.tags, [super::Tag::Unnecessary]);
+    }
+
+    #[test]
+    fn shadowing_a_defined_param_points_at_its_definition() {
+        let diagnostics = check("DEFINE PARAM $limit VALUE 10;", "LET $limit = 5; RETURN $limit");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, super::Severity::Info);
+        let related = &diagnostics[0].related[0];
+        assert_eq!(related.location.file.path(), "/project/schema.surql");
+        assert_eq!(related.location.range, 13..19);
+    }
+
+    #[test]
+    fn reports_values_of_the_wrong_type() {
+        let query = "UPDATE person SET age = \"twelve\", name = 'Tobie';";
+        let diagnostics = check(SCHEMA, query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::TypeMismatch),
+            [(
+                String::from("\"twelve\""),
+                super::Severity::Error,
+                String::from("Expected `int` for field `age`, found `string`.")
+            )]
+        );
+
+        let query = "CREATE person CONTENT { name: 42 };";
+        let diagnostics = check(SCHEMA, query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::TypeMismatch)[0].2,
+            "Expected `string` for field `name`, found `int`."
+        );
+    }
+
+    #[test]
+    fn compound_assignments_combine_with_the_field() {
+        let schema = "DEFINE TABLE person SCHEMAFULL;
+            DEFINE FIELD age ON person TYPE int;
+            DEFINE FIELD tags ON person TYPE array<string>;";
+        let query = "UPDATE person SET age += 'x';";
+        let diagnostics = check(schema, query);
+        assert_eq!(findings(query, &diagnostics, super::Lint::TypeMismatch)[0].0, "'x'");
+
+        let query = "UPDATE person SET age += 1, tags += 'x', tags += ['y'], tags -= 'z';";
+        assert!(check(schema, query).is_empty());
+        let query = "UPDATE person SET tags += 1;";
+        assert_eq!(check(schema, query).len(), 1);
+    }
+
+    #[test]
+    fn casts_within_values_are_not_comparisons() {
+        let queries = [
+            "CREATE person SET name = \"a\" + <string> $n;",
+            "CREATE person SET name = <string> $n + 'a', age = <int> $n + 1;",
+            "CREATE person SET age = <int> '12' + $n;",
+        ];
+        for query in queries {
+            let diagnostics = check(SCHEMA, &format!("LET $n = 1; {}", query));
+            assert!(diagnostics.is_empty(), "{}: {:?}", query, diagnostics);
+        }
+        let query = "CREATE person SET age = <string> $n;";
+        assert_eq!(check(SCHEMA, &format!("LET $n = 1; {}", query)).len(), 1);
+    }
+
+    /// Returns the text and message of each syntax error of `query`, which are all errors.
+    fn syntax_errors(query: &str) -> Vec<(String, String)> {
+        check("", query)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.lint.is_none())
+            .map(|diagnostic| {
+                assert_eq!(diagnostic.severity, super::Severity::Error);
+                (query[diagnostic.range].to_string(), diagnostic.message)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_unterminated_strings_identifiers_and_comments() {
+        assert_eq!(syntax_errors("SELECT * FROM person WHERE name = 'it\\'s' /* ok */;"), []);
+        assert_eq!(
+            syntax_errors("SELECT * FROM person WHERE name = 'Tobie;"),
+            [(String::from("'Tobie;"), String::from("Unterminated string"))]
+        );
+        assert_eq!(
+            syntax_errors("SELECT * FROM r\"person:tobie"),
+            [(String::from("r\"person:tobie"), String::from("Unterminated string"))]
+        );
+        assert_eq!(
+            syntax_errors("SELECT * FROM `person"),
+            [(String::from("`person"), String::from("Unterminated identifier"))]
+        );
+        assert_eq!(
+            syntax_errors("SELECT * -- /* not a block\nFROM person; /* unclosed"),
+            [(String::from("/* unclosed"), String::from("Unterminated comment"))]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_characters_and_unbalanced_brackets() {
+        assert_eq!(
+            syntax_errors("SELECT * FROM person WHERE age > 18 § 2;"),
+            [(String::from("§"), String::from("Unexpected character `§`"))]
+        );
+        assert_eq!(
+            syntax_errors("SELECT * FROM [person, (post];"),
+            [
+                (String::from("]"), String::from("Expected `)` to close the `(`, found `]`")),
+                (String::from("["), String::from("`[` is never closed")),
+            ]
+        );
+        assert_eq!(
+            syntax_errors("RETURN { a: [1, 2] }};"),
+            [(String::from("}"), String::from("Unexpected `}`"))]
+        );
+    }
+
+    /// Returns `query` with the preferred fix of its only diagnostic of `lint` applied.
+    fn fixed(query: &str, diagnostics: &[super::Diagnostic], lint: super::Lint) -> String {
+        let [diagnostic] = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.lint == Some(lint))
+            .collect::<Vec<_>>()[..]
+        else {
+            panic!("expected a single {} diagnostic", lint.name());
+        };
+        let fix = diagnostic.fixes.iter().find(|fix| fix.preferred).unwrap();
+        crate::TextEdit::apply(query, fix.edits.values().next().unwrap())
+    }
+
+    #[test]
+    fn missing_semicolon_is_inserted_after_the_previous_statement() {
+        let query = "SELECT * FROM person\nSELECT * FROM post;";
+        let diagnostics = check(SCHEMA, query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::MissingSemicolon),
+            [(
+                String::from("person"),
+                super::Severity::Error,
+                String::from("Expected `;` before `SELECT`.")
+            )]
+        );
+        assert_eq!(
+            fixed(query, &diagnostics, super::Lint::MissingSemicolon),
+            "SELECT * FROM person;\nSELECT * FROM post;"
+        );
+    }
+
+    #[test]
+    fn unknown_functions_suggest_the_closest_function() {
+        let schema = "DEFINE FUNCTION fn::greet($name: string) { RETURN 'Hello ' + $name; };";
+        let query = "RETURN string::lenght('a') + fn::gret('Ada');";
+        let diagnostics = check(schema, query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::UnknownFunction),
+            [
+                (
+                    String::from("string::lenght"),
+                    super::Severity::Warning,
+                    String::from(
+                        "Function `string::lenght` does not exist. Did you mean `string::len`?"
+                    )
+                ),
+                (
+                    String::from("fn::gret"),
+                    super::Severity::Warning,
+                    String::from("Function `fn::gret` does not exist. Did you mean `fn::greet`?")
+                ),
+            ]
+        );
+        let fixes: Vec<String> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let edits = diagnostic.fixes[0].edits.values().next().unwrap();
+                crate::TextEdit::apply(query, edits)
+            })
+            .collect();
+        assert_eq!(
+            fixes,
+            [
+                "RETURN string::len('a') + fn::gret('Ada');",
+                "RETURN string::lenght('a') + fn::greet('Ada');"
+            ]
+        );
+    }
+
+    #[test]
+    fn parenthesised_record_types_become_angle_brackets() {
+        let query = "DEFINE FIELD author ON post TYPE record(person, post);";
+        let diagnostics = check("", query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::DeprecatedSyntax),
+            [(
+                String::from("record(person, post)"),
+                super::Severity::Warning,
+                String::from(
+                    "Use `record<person | post>` instead of the 1.x `record(person, post)` syntax."
+                )
+            )]
+        );
+        assert_eq!(diagnostics[0].tags, [super::Tag::Deprecated]);
+        assert_eq!(
+            fixed(query, &diagnostics, super::Lint::DeprecatedSyntax),
+            "DEFINE FIELD author ON post TYPE record<person | post>;"
+        );
+    }
+
+    #[test]
+    fn define_scope_becomes_a_record_access() {
+        let query = "DEFINE SCOPE account SESSION 24h\n\
+                     SIGNUP (CREATE user SET email = $email)\n\
+                     SIGNIN (SELECT * FROM user WHERE email = $email);";
+        let diagnostics = check("", query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::DeprecatedSyntax),
+            [(
+                String::from("DEFINE SCOPE"),
+                super::Severity::Warning,
+                String::from(
+                    "`DEFINE SCOPE` was replaced by `DEFINE ACCESS ... TYPE RECORD` in SurrealDB \
+                     2.x."
+                )
+            )]
+        );
+        assert_eq!(
+            fixed(query, &diagnostics, super::Lint::DeprecatedSyntax),
+            "DEFINE ACCESS account ON DATABASE TYPE RECORD\n\
+             SIGNUP (CREATE user SET email = $email)\n\
+             SIGNIN (SELECT * FROM user WHERE email = $email) DURATION FOR SESSION 24h;"
+        );
+    }
+
+    #[test]
+    fn define_token_becomes_a_jwt_access() {
+        let query = "DEFINE TOKEN backend ON DATABASE TYPE HS512 VALUE 'secret';";
+        let diagnostics = check("", query);
+        assert_eq!(
+            findings(query, &diagnostics, super::Lint::DeprecatedSyntax),
+            [(
+                String::from("DEFINE TOKEN"),
+                super::Severity::Warning,
+                String::from(
+                    "`DEFINE TOKEN` was replaced by `DEFINE ACCESS ... TYPE JWT` in SurrealDB 2.x."
+                )
+            )]
+        );
+        assert_eq!(
+            fixed(query, &diagnostics, super::Lint::DeprecatedSyntax),
+            "DEFINE ACCESS backend ON DATABASE TYPE JWT ALGORITHM HS512 KEY 'secret';"
+        );
+    }
+
+    #[test]
+    fn tokens_on_a_scope_have_no_fix() {
+        let query = "DEFINE TOKEN backend ON SCOPE account TYPE HS512 VALUE 'secret';";
+        let diagnostics = check("", query);
+        assert_eq!(findings(query, &diagnostics, super::Lint::DeprecatedSyntax).len(), 1);
+        assert!(diagnostics[0].fixes.is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+//! Entity-relationship diagrams of a schema: its tables and their top-level fields as entities, and
+//! record links, relation tables and `RELATE` statements as the relationships between them.
+
+/// The language a diagram is written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiagramFormat {
+    /// A Mermaid `erDiagram`, as rendered by GitHub and most documentation sites.
+    #[default]
+    Mermaid,
+    /// A Graphviz `digraph`, for `dot` and the tools built on it.
+    Dot,
+}
+
+/// How many records of the target table a record of the source table links to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Cardinality {
+    One,
+    ZeroOrOne,
+    Many,
+}
+
+impl Cardinality {
+    fn optional(self) -> Self {
+        match self {
+            Cardinality::One => Cardinality::ZeroOrOne,
+            cardinality => cardinality,
+        }
+    }
+}
+
+/// A link from the records of one table to those of another, named after the field holding it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Link {
+    from: String,
+    to: String,
+    field: String,
+    cardinality: Cardinality,
+}
+
+/// The entities and relationships of a diagram.
+#[derive(Debug, Default)]
+struct Model {
+    /// The top-level fields of each table and their types.
+    entities: std::collections::BTreeMap<String, Vec<(String, String)>>,
+    /// The tables whose records are edges, which link their `in` and `out` records.
+    edges: std::collections::BTreeSet<String>,
+    links: std::collections::BTreeSet<Link>,
+}
+
+/// Returns the diagram of `schema`, with the tables that the `RELATE` statements of `files`, the
+/// tokens of each, relate as the ends of their edges, in addition to those of
+/// `TYPE RELATION IN ... OUT ...`.
+pub(crate) fn diagram<'t>(
+    schema: &crate::schema::Schema,
+    files: impl IntoIterator<Item = Vec<crate::lexer::Token<'t>>>,
+    format: DiagramFormat,
+) -> String {
+    let model = model(schema, files);
+    match format {
+        DiagramFormat::Mermaid => mermaid(&model),
+        DiagramFormat::Dot => dot(&model),
+    }
+}
+
+fn model<'t>(
+    schema: &crate::schema::Schema,
+    files: impl IntoIterator<Item = Vec<crate::lexer::Token<'t>>>,
+) -> Model {
+    let mut model = Model::default();
+    let tables = schema.tables.keys().chain(schema.fields.keys()).chain(schema.indexes.keys());
+    for table in tables {
+        let fields = schema
+            .fields_of(table)
+            // Nested fields such as `address.city` describe a field rather than the table.
+            .filter(|field| !field.name.contains(['.', '[']))
+            .map(|field| {
+                let kind = field.kind.as_ref().map_or(String::from("any"), ToString::to_string);
+                (field.name.clone(), kind)
+            })
+            .collect();
+        model.entities.insert(table.clone(), fields);
+    }
+
+    for field in schema.fields.values().flat_map(|fields| fields.values()) {
+        let Some(kind) = field.kind.as_ref().filter(|_| !field.name.contains(['.', '['])) else {
+            continue;
+        };
+        let mut targets = Vec::new();
+        record_targets(kind, Cardinality::One, &mut targets);
+        for (table, cardinality) in targets {
+            model.link(&field.table, &table, &field.name, cardinality);
+        }
+    }
+
+    for table in schema.tables.values() {
+        if let crate::schema::TableKind::Relation { from, to } = &table.kind {
+            model.edges.insert(table.name.clone());
+            for from in from {
+                model.link(&table.name, from, "in", Cardinality::One);
+            }
+            for to in to {
+                model.link(&table.name, to, "out", Cardinality::One);
+            }
+        }
+    }
+
+    for tokens in files {
+        for range in crate::query::statements(&tokens) {
+            let Some((from, edge, to)) = crate::query::relation(&tokens[range]) else {
+                continue;
+            };
+            model.edges.insert(edge.name.clone());
+            if let Some(from) = from {
+                model.link(&edge.name, &from.name, "in", Cardinality::One);
+            }
+            if let Some(to) = to {
+                model.link(&edge.name, &to.name, "out", Cardinality::One);
+            }
+        }
+    }
+    model
+}
+
+impl Model {
+    /// Adds a link, and its tables as entities when they are not defined.
+    fn link(&mut self, from: &str, to: &str, field: &str, cardinality: Cardinality) {
+        self.entities.entry(from.to_string()).or_default();
+        self.entities.entry(to.to_string()).or_default();
+        self.links.insert(Link {
+            from: from.to_string(),
+            to: to.to_string(),
+            field: field.to_string(),
+            cardinality,
+        });
+    }
+}
+
+/// Collects the tables that a value of `kind` links to, and how many records of each it holds.
+fn record_targets(
+    kind: &crate::types::Kind,
+    cardinality: Cardinality,
+    targets: &mut Vec<(String, Cardinality)>,
+) {
+    match kind {
+        crate::types::Kind::Record(tables) => {
+            targets.extend(tables.iter().map(|table| (table.clone(), cardinality)));
+        }
+        crate::types::Kind::Option(kind) => record_targets(kind, cardinality.optional(), targets),
+        crate::types::Kind::Array(kind, _) | crate::types::Kind::Set(kind, _) => {
+            record_targets(kind, Cardinality::Many, targets);
+        }
+        crate::types::Kind::Either(kinds) => {
+            let is_optional = kinds
+                .iter()
+                .any(|kind| matches!(kind, crate::types::Kind::None | crate::types::Kind::Null));
+            let cardinality = if is_optional { cardinality.optional() } else { cardinality };
+            for kind in kinds {
+                record_targets(kind, cardinality, targets);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn mermaid(model: &Model) -> String {
+    let mut out = String::from("erDiagram\n");
+    for (table, fields) in &model.entities {
+        if fields.is_empty() {
+            out += &format!("    {}\n", mermaid_name(table));
+            continue;
+        }
+        out += &format!("    {} {{\n", mermaid_name(table));
+        for (field, kind) in fields {
+            // Attribute types are single words, so that the full type goes into the comment.
+            let is_word = kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if is_word {
+                out += &format!("        {} {}\n", kind, mermaid_name(field));
+            } else {
+                let base: String =
+                    kind.chars().take_while(|c| c.is_ascii_alphanumeric() || *c == '_').collect();
+                let base = if base.is_empty() { String::from("literal") } else { base };
+                let comment = kind.replace('"', "'");
+                out += &format!("        {} {} \"{}\"\n", base, mermaid_name(field), comment);
+            }
+        }
+        out += "    }\n";
+    }
+    for link in &model.links {
+        let target = match link.cardinality {
+            Cardinality::One => "||",
+            Cardinality::ZeroOrOne => "o|",
+            Cardinality::Many => "o{",
+        };
+        out += &format!(
+            "    {} }}o--{} {} : \"{}\"\n",
+            mermaid_name(&link.from),
+            target,
+            mermaid_name(&link.to),
+            link.field.replace('"', "'")
+        );
+    }
+    out
+}
+
+/// Returns `name` with the characters that Mermaid does not allow in names replaced by `_`.
+fn mermaid_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' }).collect()
+}
+
+fn dot(model: &Model) -> String {
+    let mut out = String::from("digraph schema {\n    rankdir=LR;\n    node [shape=record];\n");
+    for (table, fields) in &model.entities {
+        let mut label = dot_record(table);
+        if !fields.is_empty() {
+            label += "|";
+            for (field, kind) in fields {
+                label += &format!("{}: {}\\l", dot_record(field), dot_record(kind));
+            }
+        }
+        // Edges are drawn with rounded corners to tell them from the tables they relate.
+        let shape = if model.edges.contains(table) { ", shape=Mrecord" } else { "" };
+        out += &format!("    {} [label=\"{{{}}}\"{}];\n", dot_id(table), label, shape);
+    }
+    for link in &model.links {
+        let style = match link.cardinality {
+            Cardinality::One => "",
+            Cardinality::ZeroOrOne => ", style=dashed",
+            Cardinality::Many => ", arrowhead=crow",
+        };
+        out += &format!(
+            "    {} -> {} [label={}{}];\n",
+            dot_id(&link.from),
+            dot_id(&link.to),
+            dot_id(&link.field),
+            style
+        );
+    }
+    out += "}\n";
+    out
+}
+
+/// Returns `text` as a quoted DOT identifier.
+fn dot_id(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Escapes the characters that delimit the fields of a record label.
+fn dot_record(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if matches!(c, '\\' | '"' | '{' | '}' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    const SCHEMA: &str = "DEFINE TABLE person SCHEMAFULL;\n\
+                          DEFINE FIELD name ON person TYPE string;\n\
+                          DEFINE TABLE post SCHEMAFULL;\n\
+                          DEFINE FIELD author ON post TYPE option<record<person>>;\n\
+                          DEFINE TABLE wrote TYPE RELATION IN person OUT post;\n";
+
+    fn diagram(format: super::DiagramFormat) -> String {
+        let uri = url::Url::parse("file:///schema.surql").unwrap();
+        let schema = crate::schema::Schema::from_changes(
+            &crate::schema::changes(&uri, SCHEMA),
+            crate::config::Dialect::V2,
+        );
+        let tokens = crate::lexer::tokenize("RELATE person:ada->likes->post:hello;");
+        super::diagram(&schema, [tokens], format)
+    }
+
+    /// Returns the lines of `diagram` that draw the links between tables.
+    fn links(diagram: &str) -> Vec<&str> {
+        diagram.lines().filter(|line| line.contains("--") || line.contains("->")).collect()
+    }
+
+    #[test]
+    fn draws_relation_tables_and_relate_statements_as_edges() {
+        assert_eq!(
+            links(&diagram(super::DiagramFormat::Mermaid)),
+            [
+                "    likes }o--|| person : \"in\"",
+                "    likes }o--|| post : \"out\"",
+                "    post }o--o| person : \"author\"",
+                "    wrote }o--|| person : \"in\"",
+                "    wrote }o--|| post : \"out\"",
+            ]
+        );
+        let dot = diagram(super::DiagramFormat::Dot);
+        assert_eq!(
+            links(&dot),
+            [
+                "    \"likes\" -> \"person\" [label=\"in\"];",
+                "    \"likes\" -> \"post\" [label=\"out\"];",
+                "    \"post\" -> \"person\" [label=\"author\", style=dashed];",
+                "    \"wrote\" -> \"person\" [label=\"in\"];",
+                "    \"wrote\" -> \"post\" [label=\"out\"];",
+            ]
+        );
+        assert!(dot.contains("    \"wrote\" [label=\"{wrote}\", shape=Mrecord];"));
+    }
+}
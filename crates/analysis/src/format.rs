@@ -0,0 +1,318 @@
+//! The document formatter. It normalises the case of keywords, the indentation of lines and blank
+//! lines, but leaves the spacing within a line as written, so that it never changes what a
+//! document means.
+
+/// Whether a bracket holds statements, like the body of a function, or a list of items, like
+/// arguments, arrays and objects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Context {
+    Statements,
+    Items,
+}
+
+/// An open bracket, or the document itself.
+#[derive(Debug)]
+struct Frame {
+    context: Context,
+    /// The indentation level of the lines within the bracket.
+    indent: usize,
+    /// The indentation level of the line that opened the bracket, which its closing bracket lines
+    /// up with.
+    outer_indent: usize,
+    /// Whether the current statement or item has begun, which makes its next line a continuation.
+    started: bool,
+}
+
+/// Returns the edits formatting `source`, whose syntax tree is `tree`, according to `config`.
+pub(crate) fn format(
+    source: &str,
+    tree: &tree_sitter::Tree,
+    config: &crate::config::FormatConfig,
+) -> Vec<crate::TextEdit> {
+    let tokens = crate::lexer::tokens(source, tree);
+    let mut edits = layout_edits(source, &tokens, config.indent_width);
+    if let Some(case) = crate::casing::Case::from_config(config.keyword_case) {
+        edits.extend(crate::casing::keyword_edits(source, tree, case));
+        edits.sort_by_key(|edit| edit.range.start);
+    }
+    edits
+}
+
+/// Returns the edits re-indenting the lines of `source` by `indent_width` spaces per level,
+/// removing trailing whitespace, collapsing runs of blank lines and ending it with a single
+/// newline.
+///
+/// A line is indented one level deeper than the bracket it is in, and one more when it continues
+/// a statement or item begun on a previous line. A line starting with a closing bracket lines up
+/// with the line that opened it.
+fn layout_edits(
+    source: &str,
+    tokens: &[crate::lexer::Token<'_>],
+    indent_width: usize,
+) -> Vec<crate::TextEdit> {
+    let verbatim = verbatim_ranges(source, tokens);
+    let is_verbatim =
+        |offset: usize| verbatim.iter().any(|range| range.start < offset && offset < range.end);
+    let content_end = source.trim_end().len();
+
+    let mut edits = Vec::new();
+    let mut stack =
+        vec![Frame { context: Context::Statements, indent: 0, outer_indent: 0, started: false }];
+    let mut next_token = 0;
+    let mut line_indent = 0;
+    // Blank lines are deleted at the start of the document, and after another blank line.
+    let mut previous_blank = true;
+    let mut start = 0;
+    while start < content_end {
+        let newline = source[start..].find('\n').map(|index| start + index);
+        let next_start = newline.map_or(source.len(), |newline| newline + 1);
+        let mut end = newline.map_or(content_end, |newline| newline.min(content_end));
+        if source[start..end].ends_with('\r') {
+            end -= 1;
+        }
+        let text = &source[start..end];
+        let starts_verbatim = is_verbatim(start);
+        let ends_verbatim = is_verbatim(end);
+
+        if !starts_verbatim && !ends_verbatim && text.trim().is_empty() {
+            if previous_blank {
+                edits.push(crate::TextEdit { range: start..next_start, new_text: String::new() });
+            } else if !text.is_empty() {
+                edits.push(crate::TextEdit { range: start..end, new_text: String::new() });
+            }
+            previous_blank = true;
+            start = next_start;
+            continue;
+        }
+        previous_blank = false;
+
+        let line_end = tokens[next_token..]
+            .iter()
+            .position(|token| token.start >= next_start)
+            .map_or(tokens.len(), |index| next_token + index);
+        if !starts_verbatim {
+            line_indent = indent(&stack, tokens[next_token..line_end].first());
+            let leading = text.len() - text.trim_start().len();
+            let indentation = " ".repeat(line_indent * indent_width);
+            if text[..leading] != indentation {
+                edits
+                    .push(crate::TextEdit { range: start..start + leading, new_text: indentation });
+            }
+        }
+        let trimmed = start + text.trim_end().len();
+        if !ends_verbatim && trimmed < end {
+            edits.push(crate::TextEdit { range: trimmed..end, new_text: String::new() });
+        }
+
+        for index in next_token..line_end {
+            advance(&mut stack, tokens, index, line_indent);
+        }
+        next_token = line_end;
+        start = next_start;
+    }
+
+    if !is_verbatim(content_end) {
+        let newline = if content_end == 0 {
+            ""
+        } else if source.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        if source[content_end..] != *newline {
+            let range = content_end..source.len();
+            edits.push(crate::TextEdit { range, new_text: String::from(newline) });
+        }
+    }
+    edits
+}
+
+/// Returns the indentation level of a line starting with `first`, or without any tokens.
+fn indent(stack: &[Frame], first: Option<&crate::lexer::Token<'_>>) -> usize {
+    let Some(top) = stack.last() else {
+        return 0;
+    };
+    let is_closing = first.is_some_and(|first| {
+        first.kind == crate::lexer::TokenKind::Punct && matches!(first.text, ")" | "]" | "}")
+    });
+    if is_closing && stack.len() > 1 {
+        return top.outer_indent;
+    }
+    top.indent + usize::from(top.started)
+}
+
+/// Updates the open brackets with the token at `index`, which is on a line indented by
+/// `line_indent` levels.
+fn advance(
+    stack: &mut Vec<Frame>,
+    tokens: &[crate::lexer::Token<'_>],
+    index: usize,
+    line_indent: usize,
+) {
+    let token = &tokens[index];
+    let Some(top) = stack.last_mut() else {
+        return;
+    };
+    if token.kind != crate::lexer::TokenKind::Punct {
+        top.started = true;
+        return;
+    }
+    match token.text {
+        "(" | "[" | "{" => {
+            top.started = true;
+            let context = if token.text == "{" && !is_object(tokens, index) {
+                Context::Statements
+            } else {
+                Context::Items
+            };
+            stack.push(Frame {
+                context,
+                indent: line_indent + 1,
+                outer_indent: line_indent,
+                started: false,
+            });
+        }
+        ")" | "]" | "}" => {
+            if stack.len() > 1 {
+                stack.pop();
+            }
+        }
+        ";" => top.started = false,
+        "," if top.context == Context::Items => top.started = false,
+        _ => top.started = true,
+    }
+}
+
+/// Returns whether the `{` at `open` starts an object, as its first key is followed by a `:`,
+/// rather than a block of statements.
+fn is_object(tokens: &[crate::lexer::Token<'_>], open: usize) -> bool {
+    let is_key = tokens.get(open + 1).is_some_and(|key| {
+        matches!(
+            key.kind,
+            crate::lexer::TokenKind::Ident
+                | crate::lexer::TokenKind::String
+                | crate::lexer::TokenKind::Number
+        )
+    });
+    is_key && tokens.get(open + 2).is_some_and(|colon| colon.is_punct(":"))
+}
+
+/// Returns the strings, quoted identifiers and block comments of `source` that span several lines,
+/// whose text the formatter must leave as written.
+fn verbatim_ranges(
+    source: &str,
+    tokens: &[crate::lexer::Token<'_>],
+) -> Vec<std::ops::Range<usize>> {
+    let mut ranges: Vec<std::ops::Range<usize>> = tokens
+        .iter()
+        .filter(|token| token.text.contains('\n'))
+        .map(crate::lexer::Token::span)
+        .collect();
+    // Comments are only found between tokens.
+    let mut gap_start = 0;
+    for index in 0..=tokens.len() {
+        let gap_end = tokens.get(index).map_or(source.len(), |token| token.start);
+        let gap = &source[gap_start..gap_end];
+        let mut offset = 0;
+        while offset < gap.len() {
+            let rest = &gap[offset..];
+            if rest.starts_with("--") || rest.starts_with("//") || rest.starts_with('#') {
+                offset += rest.find('\n').unwrap_or(rest.len());
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                let len = comment.find("*/").map_or(rest.len(), |index| index + 4);
+                if rest[..len].contains('\n') {
+                    ranges.push(gap_start + offset..gap_start + offset + len);
+                }
+                offset += len;
+            } else {
+                offset += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        gap_start = tokens.get(index).map_or(gap_end, |token| token.end);
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    const FUNCTION_AND_QUERY: &str = "DEFINE FUNCTION fn::adult($age: int) {\n\
+                                      RETURN $age >= 18;\n\
+                                      };\n\n\n\n\
+                                      SELECT name,\n\
+                                      age\n\
+                                      FROM person\n\
+                                      WHERE fn::adult(age)   \n  \
+                                      AND active = true;";
+
+    const OBJECT: &str = "CREATE person CONTENT {\nname: 'Tobie',\ntags: [\n'a',\n'b'\n]\n};\n";
+
+    const COMMENTS_AND_STRINGS: &str = "SELECT * FROM person WHERE name = 'line one\n   \
+                                        line two'\n\
+                                        /* a block\n      \
+                                        comment */\n\
+                                        -- select from where\n\
+                                        AND age > 18;\n";
+
+    fn config(keyword_case: &str, indent_width: usize) -> crate::config::FormatConfig {
+        let content = format!(
+            "[format]\nkeyword-case = \"{}\"\nindent-width = {}\n",
+            keyword_case, indent_width
+        );
+        let (config, diagnostics) =
+            crate::config::parse(std::path::Path::new("/project/surrealql.toml"), &content);
+        assert!(diagnostics.is_empty());
+        config.unwrap().format
+    }
+
+    fn formatted(source: &str, config: &crate::config::FormatConfig) -> String {
+        let tree = crate::parser::parse(source, None).unwrap();
+        crate::TextEdit::apply(source, &super::format(source, &tree, config))
+    }
+
+    #[test]
+    fn indents_blocks_continuations_and_items() {
+        assert_eq!(
+            formatted(FUNCTION_AND_QUERY, &config("preserve", 4)),
+            "DEFINE FUNCTION fn::adult($age: int) {\n    RETURN $age >= 18;\n};\n\n\
+             SELECT name,\n    age\n    FROM person\n    WHERE fn::adult(age)\n    AND active = \
+             true;\n"
+        );
+        assert_eq!(
+            formatted(OBJECT, &config("preserve", 2)),
+            "CREATE person CONTENT {\n  name: 'Tobie',\n  tags: [\n    'a',\n    'b'\n  ]\n};\n"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_and_strings_as_written() {
+        assert_eq!(
+            formatted(COMMENTS_AND_STRINGS, &config("preserve", 4)),
+            "SELECT * FROM person WHERE name = 'line one\n   line two'\n    /* a block\n      \
+             comment */\n    -- select from where\n    AND age > 18;\n"
+        );
+    }
+
+    #[test]
+    fn formatting_again_changes_nothing() {
+        for source in [FUNCTION_AND_QUERY, OBJECT, COMMENTS_AND_STRINGS] {
+            for config in [config("preserve", 4), config("preserve", 2), config("upper", 4)] {
+                let once = formatted(source, &config);
+                assert_eq!(formatted(&once, &config), once, "{}", source);
+            }
+        }
+    }
+
+    #[test]
+    fn applies_the_keyword_case_of_the_config() {
+        let source = "-- select from\nselect 'from' FROM person;\n";
+        assert_eq!(
+            formatted(source, &config("upper", 4)),
+            "-- select from\nSELECT 'from' FROM person;\n"
+        );
+        assert_eq!(
+            formatted(source, &config("lower", 4)),
+            "-- select from\nselect 'from' from person;\n"
+        );
+        assert_eq!(formatted(source, &config("preserve", 4)), source);
+    }
+}
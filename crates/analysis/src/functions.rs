@@ -0,0 +1,361 @@
+//! Signatures of SurrealDB's built-in functions.
+
+/// Signatures in the form `name(argument, ...) -> type`, sorted by name.
+const SIGNATURES: &[&str] = &[
+    "array::add(array, value) -> array",
+    "array::all(array) -> bool",
+    "array::any(array) -> bool",
+    "array::append(array, value) -> array",
+    "array::at(array, index) -> any",
+    "array::boolean_and(lhs, rhs) -> array<bool>",
+    "array::boolean_not(array) -> array<bool>",
+    "array::boolean_or(lhs, rhs) -> array<bool>",
+    "array::boolean_xor(lhs, rhs) -> array<bool>",
+    "array::clump(array, size) -> array",
+    "array::combine(lhs, rhs) -> array",
+    "array::complement(lhs, rhs) -> array",
+    "array::concat(array, other) -> array",
+    "array::difference(lhs, rhs) -> array",
+    "array::distinct(array) -> array",
+    "array::filter(array, predicate) -> array",
+    "array::filter_index(array, value) -> array<int>",
+    "array::find(array, predicate) -> any",
+    "array::find_index(array, value) -> option<int>",
+    "array::first(array) -> any",
+    "array::flatten(array) -> array",
+    "array::fold(array, initial, closure) -> any",
+    "array::group(array) -> array",
+    "array::includes(array, value) -> bool",
+    "array::index_of(array, value) -> option<int>",
+    "array::insert(array, value, index) -> array",
+    "array::intersect(lhs, rhs) -> array",
+    "array::is_empty(array) -> bool",
+    "array::join(array, separator) -> string",
+    "array::last(array) -> any",
+    "array::len(array) -> int",
+    "array::logical_and(lhs, rhs) -> array",
+    "array::logical_or(lhs, rhs) -> array",
+    "array::logical_xor(lhs, rhs) -> array",
+    "array::map(array, closure) -> array",
+    "array::matches(array, value) -> array<bool>",
+    "array::max(array) -> any",
+    "array::min(array) -> any",
+    "array::pop(array) -> any",
+    "array::prepend(array, value) -> array",
+    "array::push(array, value) -> array",
+    "array::range(start, count) -> array<int>",
+    "array::reduce(array, closure) -> any",
+    "array::remove(array, index) -> array",
+    "array::repeat(value, count) -> array",
+    "array::reverse(array) -> array",
+    "array::shuffle(array) -> array",
+    "array::slice(array, start, len) -> array",
+    "array::sort(array, ascending) -> array",
+    "array::sort::asc(array) -> array",
+    "array::sort::desc(array) -> array",
+    "array::swap(array, from, to) -> array",
+    "array::transpose(array) -> array",
+    "array::union(lhs, rhs) -> array",
+    "array::windows(array, size) -> array",
+    "bytes::len(bytes) -> int",
+    "count(value) -> int",
+    "crypto::argon2::compare(hash, password) -> bool",
+    "crypto::argon2::generate(password) -> string",
+    "crypto::bcrypt::compare(hash, password) -> bool",
+    "crypto::bcrypt::generate(password) -> string",
+    "crypto::blake3(value) -> string",
+    "crypto::md5(value) -> string",
+    "crypto::pbkdf2::compare(hash, password) -> bool",
+    "crypto::pbkdf2::generate(password) -> string",
+    "crypto::scrypt::compare(hash, password) -> bool",
+    "crypto::scrypt::generate(password) -> string",
+    "crypto::sha1(value) -> string",
+    "crypto::sha256(value) -> string",
+    "crypto::sha512(value) -> string",
+    "duration::days(duration) -> int",
+    "duration::from::days(days) -> duration",
+    "duration::from::hours(hours) -> duration",
+    "duration::from::micros(micros) -> duration",
+    "duration::from::millis(millis) -> duration",
+    "duration::from::mins(mins) -> duration",
+    "duration::from::nanos(nanos) -> duration",
+    "duration::from::secs(secs) -> duration",
+    "duration::from::weeks(weeks) -> duration",
+    "duration::hours(duration) -> int",
+    "duration::micros(duration) -> int",
+    "duration::millis(duration) -> int",
+    "duration::mins(duration) -> int",
+    "duration::nanos(duration) -> int",
+    "duration::secs(duration) -> int",
+    "duration::weeks(duration) -> int",
+    "duration::years(duration) -> int",
+    "encoding::base64::decode(value) -> bytes",
+    "encoding::base64::encode(value) -> string",
+    "geo::area(geometry) -> float",
+    "geo::bearing(from, to) -> float",
+    "geo::centroid(geometry) -> geometry<point>",
+    "geo::distance(from, to) -> float",
+    "geo::hash::decode(hash) -> geometry<point>",
+    "geo::hash::encode(point, accuracy) -> string",
+    "http::delete(url, headers) -> any",
+    "http::get(url, headers) -> any",
+    "http::head(url, headers) -> none",
+    "http::patch(url, body, headers) -> any",
+    "http::post(url, body, headers) -> any",
+    "http::put(url, body, headers) -> any",
+    "math::abs(number) -> number",
+    "math::acos(number) -> float",
+    "math::asin(number) -> float",
+    "math::atan(number) -> float",
+    "math::bottom(array, count) -> array",
+    "math::ceil(number) -> number",
+    "math::clamp(number, min, max) -> number",
+    "math::cos(number) -> float",
+    "math::deg2rad(number) -> float",
+    "math::fixed(number, precision) -> number",
+    "math::floor(number) -> number",
+    "math::interquartile(array) -> float",
+    "math::lerp(from, to, factor) -> float",
+    "math::ln(number) -> float",
+    "math::log(number, base) -> float",
+    "math::log10(number) -> float",
+    "math::log2(number) -> float",
+    "math::max(array) -> number",
+    "math::mean(array) -> float",
+    "math::median(array) -> number",
+    "math::midhinge(array) -> float",
+    "math::min(array) -> number",
+    "math::mode(array) -> number",
+    "math::nearestrank(array, percentile) -> number",
+    "math::percentile(array, percentile) -> float",
+    "math::pow(number, exponent) -> number",
+    "math::product(array) -> number",
+    "math::rad2deg(number) -> float",
+    "math::round(number) -> number",
+    "math::sign(number) -> int",
+    "math::sin(number) -> float",
+    "math::spread(array) -> number",
+    "math::sqrt(number) -> float",
+    "math::stddev(array) -> float",
+    "math::sum(array) -> number",
+    "math::tan(number) -> float",
+    "math::top(array, count) -> array",
+    "math::trimean(array) -> float",
+    "math::variance(array) -> float",
+    "meta::id(record) -> any",
+    "meta::tb(record) -> string",
+    "not(value) -> bool",
+    "object::entries(object) -> array",
+    "object::from_entries(entries) -> object",
+    "object::keys(object) -> array<string>",
+    "object::len(object) -> int",
+    "object::values(object) -> array",
+    "parse::email::host(email) -> string",
+    "parse::email::user(email) -> string",
+    "parse::url::domain(url) -> string",
+    "parse::url::fragment(url) -> string",
+    "parse::url::host(url) -> string",
+    "parse::url::path(url) -> string",
+    "parse::url::port(url) -> int",
+    "parse::url::query(url) -> string",
+    "parse::url::scheme(url) -> string",
+    "rand() -> float",
+    "rand::bool() -> bool",
+    "rand::enum(value) -> any",
+    "rand::float(min, max) -> float",
+    "rand::guid(length) -> string",
+    "rand::int(min, max) -> int",
+    "rand::string(length) -> string",
+    "rand::time(min, max) -> datetime",
+    "rand::ulid() -> string",
+    "rand::uuid() -> uuid",
+    "rand::uuid::v4() -> uuid",
+    "rand::uuid::v7() -> uuid",
+    "record::exists(record) -> bool",
+    "record::id(record) -> any",
+    "record::tb(record) -> string",
+    "search::analyze(analyzer, string) -> array<string>",
+    "search::highlight(prefix, suffix, reference, partial) -> any",
+    "search::offsets(reference, partial) -> object",
+    "search::score(reference) -> number",
+    "session::db() -> string",
+    "session::id() -> string",
+    "session::ip() -> string",
+    "session::ns() -> string",
+    "session::origin() -> string",
+    "session::token() -> object",
+    "sleep(duration) -> none",
+    "string::concat(value) -> string",
+    "string::contains(string, predicate) -> bool",
+    "string::ends_with(string, suffix) -> bool",
+    "string::html::encode(string) -> string",
+    "string::html::sanitize(string) -> string",
+    "string::is::alpha(string) -> bool",
+    "string::is::alphanum(string) -> bool",
+    "string::is::ascii(string) -> bool",
+    "string::is::datetime(string, format) -> bool",
+    "string::is::domain(string) -> bool",
+    "string::is::email(string) -> bool",
+    "string::is::hexadecimal(string) -> bool",
+    "string::is::ip(string) -> bool",
+    "string::is::ipv4(string) -> bool",
+    "string::is::ipv6(string) -> bool",
+    "string::is::latitude(string) -> bool",
+    "string::is::longitude(string) -> bool",
+    "string::is::numeric(string) -> bool",
+    "string::is::record(string, table) -> bool",
+    "string::is::semver(string) -> bool",
+    "string::is::url(string) -> bool",
+    "string::is::uuid(string) -> bool",
+    "string::join(separator, value) -> string",
+    "string::len(string) -> int",
+    "string::lowercase(string) -> string",
+    "string::matches(string, pattern) -> bool",
+    "string::repeat(string, count) -> string",
+    "string::replace(string, pattern, replacement) -> string",
+    "string::reverse(string) -> string",
+    "string::semver::compare(lhs, rhs) -> int",
+    "string::semver::major(version) -> int",
+    "string::semver::minor(version) -> int",
+    "string::semver::patch(version) -> int",
+    "string::similarity::fuzzy(lhs, rhs) -> int",
+    "string::similarity::jaro(lhs, rhs) -> float",
+    "string::similarity::smithwaterman(lhs, rhs) -> int",
+    "string::slice(str, from, len) -> string",
+    "string::slug(string) -> string",
+    "string::split(string, separator) -> array<string>",
+    "string::starts_with(string, prefix) -> bool",
+    "string::trim(string) -> string",
+    "string::uppercase(string) -> string",
+    "string::words(string) -> array<string>",
+    "time::ceil(datetime, duration) -> datetime",
+    "time::day(datetime) -> int",
+    "time::floor(datetime, duration) -> datetime",
+    "time::format(datetime, format) -> string",
+    "time::from::micros(micros) -> datetime",
+    "time::from::millis(millis) -> datetime",
+    "time::from::nanos(nanos) -> datetime",
+    "time::from::secs(secs) -> datetime",
+    "time::from::unix(secs) -> datetime",
+    "time::group(datetime, interval) -> datetime",
+    "time::hour(datetime) -> int",
+    "time::max(array) -> datetime",
+    "time::micros(datetime) -> int",
+    "time::millis(datetime) -> int",
+    "time::min(array) -> datetime",
+    "time::minute(datetime) -> int",
+    "time::month(datetime) -> int",
+    "time::nano(datetime) -> int",
+    "time::now() -> datetime",
+    "time::round(datetime, duration) -> datetime",
+    "time::second(datetime) -> int",
+    "time::timezone() -> string",
+    "time::unix(datetime) -> int",
+    "time::wday(datetime) -> int",
+    "time::week(datetime) -> int",
+    "time::yday(datetime) -> int",
+    "time::year(datetime) -> int",
+    "type::bool(value) -> bool",
+    "type::datetime(value) -> datetime",
+    "type::decimal(value) -> decimal",
+    "type::duration(value) -> duration",
+    "type::field(field) -> any",
+    "type::fields(fields) -> array",
+    "type::float(value) -> float",
+    "type::int(value) -> int",
+    "type::is::array(value) -> bool",
+    "type::is::bool(value) -> bool",
+    "type::is::bytes(value) -> bool",
+    "type::is::datetime(value) -> bool",
+    "type::is::decimal(value) -> bool",
+    "type::is::duration(value) -> bool",
+    "type::is::float(value) -> bool",
+    "type::is::geometry(value) -> bool",
+    "type::is::int(value) -> bool",
+    "type::is::none(value) -> bool",
+    "type::is::null(value) -> bool",
+    "type::is::number(value) -> bool",
+    "type::is::object(value) -> bool",
+    "type::is::record(value, table) -> bool",
+    "type::is::string(value) -> bool",
+    "type::is::uuid(value) -> bool",
+    "type::number(value) -> number",
+    "type::point(value) -> geometry<point>",
+    "type::record(value, table) -> record",
+    "type::string(value) -> string",
+    "type::table(value) -> string",
+    "type::thing(table, id) -> record",
+    "type::uuid(value) -> uuid",
+    "value::diff(lhs, rhs) -> array",
+    "value::patch(value, patch) -> any",
+    "vector::add(lhs, rhs) -> array<number>",
+    "vector::angle(lhs, rhs) -> float",
+    "vector::cross(lhs, rhs) -> array<number>",
+    "vector::distance::chebyshev(lhs, rhs) -> float",
+    "vector::distance::euclidean(lhs, rhs) -> float",
+    "vector::distance::hamming(lhs, rhs) -> int",
+    "vector::distance::manhattan(lhs, rhs) -> float",
+    "vector::distance::minkowski(lhs, rhs, order) -> float",
+    "vector::divide(lhs, rhs) -> array<number>",
+    "vector::dot(lhs, rhs) -> number",
+    "vector::magnitude(vector) -> float",
+    "vector::multiply(lhs, rhs) -> array<number>",
+    "vector::normalize(vector) -> array<float>",
+    "vector::project(lhs, rhs) -> array<number>",
+    "vector::scale(vector, factor) -> array<number>",
+    "vector::similarity::cosine(lhs, rhs) -> float",
+    "vector::similarity::jaccard(lhs, rhs) -> float",
+    "vector::similarity::pearson(lhs, rhs) -> float",
+    "vector::subtract(lhs, rhs) -> array<number>",
+];
+
+/// Functions of SurrealDB 1.x that were renamed in 2.x, with their replacement.
+pub(crate) const RENAMED: &[(&str, &str)] = &[
+    ("meta::id", "record::id"),
+    ("meta::tb", "record::tb"),
+    ("string::endsWith", "string::ends_with"),
+    ("string::startsWith", "string::starts_with"),
+];
+
+#[derive(Clone, Debug)]
+pub(crate) struct Builtin {
+    /// The names of the positional parameters.
+    pub(crate) params: Vec<&'static str>,
+    /// The source text of the return type.
+    pub(crate) returns: &'static str,
+}
+
+fn parse(signature: &'static str) -> Builtin {
+    let (head, returns) = signature.split_once("->").unwrap_or((signature, "any"));
+    let params = head
+        .split_once('(')
+        .and_then(|(_, rest)| rest.rsplit_once(')'))
+        .map(|(params, _)| {
+            params.split(',').map(str::trim).filter(|param| !param.is_empty()).collect()
+        })
+        .unwrap_or_default();
+    Builtin { params, returns: returns.trim() }
+}
+
+/// Looks up a built-in function by its lowercase name.
+pub(crate) fn builtin(name: &str) -> Option<Builtin> {
+    SIGNATURES
+        .binary_search_by(|signature| {
+            let candidate = signature.split_once('(').map_or(*signature, |(name, _)| name);
+            candidate.cmp(name)
+        })
+        .ok()
+        .map(|index| parse(SIGNATURES[index]))
+}
+
+/// Returns the names of every built-in function.
+pub(crate) fn names() -> impl Iterator<Item = &'static str> {
+    SIGNATURES
+        .iter()
+        .map(|signature| signature.split_once('(').map_or(*signature, |(name, _)| name))
+}
+
+/// Returns whether `namespace` is the first segment of a built-in function, e.g. `string`.
+pub(crate) fn is_namespace(namespace: &str) -> bool {
+    names().any(|name| name.split_once("::").is_some_and(|(prefix, _)| prefix == namespace))
+}
@@ -0,0 +1,252 @@
+//! Inlay hints for inferred `LET` types, call argument names and record-link traversals.
+
+/// Computes the inlay hints of a document made of `tokens` that fall within `range`, leaving out
+/// those that `config` turns off.
+pub(crate) fn inlay_hints(
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+    config: &crate::config::HintsConfig,
+    range: crate::TextRange,
+) -> Vec<InlayHint> {
+    let mut hints = Vec::new();
+    if config.let_types {
+        hints.extend(let_hints(tokens, schema));
+    }
+    if config.parameter_names {
+        hints.extend(argument_hints(tokens, schema));
+    }
+    if config.record_links {
+        for statement in crate::query::statements(tokens) {
+            hints.extend(traversal_hints(&tokens[statement], schema));
+        }
+    }
+    hints.retain(|hint| (range.start..=range.end).contains(&hint.offset));
+    hints.sort_by_key(|hint| hint.offset);
+    hints.dedup_by(|a, b| a.offset == b.offset && a.label == b.label);
+    hints
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InlayHintKind {
+    /// Shown after the code it describes, e.g. the type of a `LET` binding.
+    Type,
+    /// Shown before a call argument, naming its parameter.
+    Parameter,
+}
+
+/// A label shown inline at `offset`.
+#[derive(Clone, Debug)]
+pub struct InlayHint {
+    pub offset: usize,
+    pub label: String,
+    pub kind: InlayHintKind,
+    pub tooltip: Option<String>,
+}
+
+/// Shows the inferred type after the parameter of `LET $x = ...` bindings without a declared type.
+fn let_hints(tokens: &[crate::lexer::Token<'_>], schema: &crate::schema::Schema) -> Vec<InlayHint> {
+    let mut hints = Vec::new();
+    for range in crate::query::statements(tokens) {
+        let statement = &tokens[range];
+        let [first, param, eq, value @ ..] = statement else {
+            continue;
+        };
+        if !first.is_keyword("LET")
+            || param.kind != crate::lexer::TokenKind::Param
+            || !eq.is_punct("=")
+        {
+            continue;
+        }
+        let Some(kind) = crate::types::infer(value, schema) else {
+            continue;
+        };
+        hints.push(InlayHint {
+            offset: param.end,
+            label: format!(": {}", kind),
+            kind: InlayHintKind::Type,
+            tooltip: None,
+        });
+    }
+    hints
+}
+
+/// Shows parameter names before the positional arguments of built-in and `fn::` calls.
+fn argument_hints(
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+) -> Vec<InlayHint> {
+    let mut hints = Vec::new();
+    for call in crate::query::calls(tokens) {
+        let params: Vec<String> = if call.name.starts_with("fn::") {
+            match schema.functions.get(&call.name) {
+                Some(function) => function.params.clone(),
+                None => continue,
+            }
+        } else {
+            match crate::functions::builtin(&call.name.to_lowercase()) {
+                Some(builtin) => builtin.params.iter().map(|param| param.to_string()).collect(),
+                None => continue,
+            }
+        };
+
+        let arguments = &tokens[call.open + 1..call.close];
+        for (part, param) in crate::lexer::split_top_level(arguments, ",").into_iter().zip(params) {
+            let argument = &arguments[part];
+            let Some(first) = argument.first() else {
+                continue;
+            };
+            // `string::len($string)` is already self-explanatory.
+            if argument.len() == 1 && first.ident().eq_ignore_ascii_case(&param) {
+                continue;
+            }
+            hints.push(InlayHint {
+                offset: first.start,
+                label: format!("{}:", param),
+                kind: InlayHintKind::Parameter,
+                tooltip: None,
+            });
+        }
+    }
+    hints
+}
+
+/// Shows the table a record link resolves to in field paths such as `author.name`.
+fn traversal_hints(
+    statement: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+) -> Vec<InlayHint> {
+    let mut hints = Vec::new();
+    let tables = crate::query::table_references(statement);
+    let [table] = tables.as_slice() else {
+        return hints;
+    };
+
+    for field in crate::query::field_references(statement) {
+        let Some(mut index) = statement.iter().position(|token| token.start == field.span.start)
+        else {
+            continue;
+        };
+        let mut table = table.name.clone();
+        let mut path = field.name;
+        while let Some(next) = statement.get(index + 1) {
+            if next.is_punct("[") {
+                let Some(close) = crate::lexer::matching_bracket(statement, index + 1) else {
+                    break;
+                };
+                path.push_str("[*]");
+                index = close;
+                continue;
+            }
+            let Some(segment) = statement.get(index + 2) else {
+                break;
+            };
+            if !next.is_punct(".") || segment.kind != crate::lexer::TokenKind::Ident {
+                break;
+            }
+            let kind = schema
+                .fields
+                .get(&table)
+                .and_then(|fields| fields.get(&path))
+                .and_then(|field| field.kind.as_ref());
+            match kind.and_then(linked_tables) {
+                Some(linked) => {
+                    let label = linked.join(" | ");
+                    hints.push(InlayHint {
+                        offset: statement[index].end,
+                        label: format!("→ {}", label),
+                        kind: InlayHintKind::Type,
+                        tooltip: Some(format!("`{}` links to `{}`", path, label)),
+                    });
+                    // The fields of a link to several tables cannot be resolved any further.
+                    let [linked] = linked else {
+                        break;
+                    };
+                    table = linked.clone();
+                    path = segment.ident().to_string();
+                }
+                None => {
+                    path.push('.');
+                    path.push_str(segment.ident());
+                }
+            }
+            index += 2;
+        }
+    }
+    hints
+}
+
+/// Returns the tables a field of `kind` links to, looking through options, arrays and sets.
+fn linked_tables(kind: &crate::types::Kind) -> Option<&[String]> {
+    match kind {
+        crate::types::Kind::Record(tables) if !tables.is_empty() => Some(tables),
+        crate::types::Kind::Option(kind) => linked_tables(kind),
+        kind => linked_tables(kind.element()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const SCHEMA: &str = "DEFINE FUNCTION fn::greet($name: string, $greeting: string) { \
+                          RETURN $greeting + ' ' + $name; };\n\
+                          DEFINE TABLE person SCHEMAFULL;\n\
+                          DEFINE FIELD name ON person TYPE string;\n\
+                          DEFINE TABLE post SCHEMAFULL;\n\
+                          DEFINE FIELD author ON post TYPE record<person>;\n";
+
+    const QUERY: &str =
+        "LET $count = 1;\nRETURN fn::greet('Ada', $hello);\nSELECT author.name FROM post;";
+
+    fn hints(config: &crate::config::HintsConfig) -> Vec<(usize, String, super::InlayHintKind)> {
+        let uri = url::Url::parse("file:///schema.surql").unwrap();
+        let schema = crate::schema::Schema::from_changes(
+            &crate::schema::changes(&uri, SCHEMA),
+            crate::config::Dialect::V2,
+        );
+        let tokens = crate::lexer::tokenize(QUERY);
+        super::inlay_hints(&tokens, &schema, config, 0..QUERY.len())
+            .into_iter()
+            .map(|hint| (hint.offset, hint.label, hint.kind))
+            .collect()
+    }
+
+    fn offset_after(text: &str) -> usize {
+        QUERY.find(text).unwrap() + text.len()
+    }
+
+    #[test]
+    fn shows_let_types_parameter_names_and_record_links() {
+        assert_eq!(
+            hints(&crate::config::HintsConfig::default()),
+            [
+                (offset_after("LET $count"), ": int".to_string(), super::InlayHintKind::Type),
+                (
+                    QUERY.find("'Ada'").unwrap(),
+                    "name:".to_string(),
+                    super::InlayHintKind::Parameter
+                ),
+                (
+                    QUERY.find("$hello").unwrap(),
+                    "greeting:".to_string(),
+                    super::InlayHintKind::Parameter
+                ),
+                (offset_after("author"), "→ person".to_string(), super::InlayHintKind::Type),
+            ]
+        );
+    }
+
+    #[test]
+    fn each_kind_of_hint_can_be_turned_off() {
+        let all = hints(&crate::config::HintsConfig::default());
+        let turned_off = [
+            crate::config::HintsConfig { let_types: false, ..Default::default() },
+            crate::config::HintsConfig { parameter_names: false, ..Default::default() },
+            crate::config::HintsConfig { record_links: false, ..Default::default() },
+        ];
+        let labels = [&[": int"][..], &["name:", "greeting:"], &["→ person"]];
+        for (config, labels) in turned_off.iter().zip(labels) {
+            let expected: Vec<_> =
+                all.iter().filter(|(_, label, _)| !labels.contains(&&**label)).cloned().collect();
+            assert_eq!(hints(config), expected);
+        }
+    }
+}
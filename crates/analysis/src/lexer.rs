@@ -0,0 +1,382 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// A bare or backtick/angle-bracket quoted identifier, including keywords.
+    Ident,
+    /// A `$param`.
+    Param,
+    Number,
+    Duration,
+    String,
+    Punct,
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Token<'a> {
+    pub(crate) kind: TokenKind,
+    pub(crate) text: &'a str,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl<'a> Token<'a> {
+    /// Returns whether the token is the keyword `keyword`, which must be given in uppercase.
+    pub(crate) fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Ident && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    pub(crate) fn is_punct(&self, punct: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text == punct
+    }
+
+    /// Returns the identifier without any surrounding quotes.
+    pub(crate) fn ident(&self) -> &'a str {
+        let text = self.text;
+        if let Some(inner) = text.strip_prefix('`').and_then(|text| text.strip_suffix('`')) {
+            return inner;
+        }
+        if let Some(inner) = text.strip_prefix('⟨').and_then(|text| text.strip_suffix('⟩')) {
+            return inner;
+        }
+        text.strip_prefix('$').unwrap_or(text)
+    }
+
+    pub(crate) fn span(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+}
+
+const PUNCTUATION: &[&str] = &[
+    "<->", "...", "->", "<-", "::", "+?=", "+=", "-=", "*=", "/=", "==", "!=", "?=", "<=", ">=",
+    "&&", "||", "??", "?:", "..", "**", "@@", "=~",
+];
+
+fn is_ident_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '_'
+}
+
+fn is_ident_continue(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// Returns the tokens of `source` at the leaves of its syntax `tree`, skipping comments.
+///
+/// Each leaf is read as the tokens it holds. A token that the grammar splits into several leaves,
+/// such as the `<` and `-` of `<-`, is read whole from its first leaf, and a leaf holding several
+/// tokens, such as one the parser skipped while recovering from an error, is split into them.
+pub(crate) fn tokens<'a>(source: &'a str, tree: &tree_sitter::Tree) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    let mut end = 0;
+    let mut cursor = tree.walk();
+    loop {
+        let leaf = cursor.node();
+        if cursor.goto_first_child() {
+            continue;
+        }
+        let from = leaf.start_byte().max(end);
+        if from < leaf.end_byte() && leaf.end_byte() <= source.len() {
+            end = scan(source, from, leaf.end_byte(), &mut tokens).max(leaf.end_byte());
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return tokens;
+            }
+        }
+    }
+}
+
+/// Returns the tokens of `source`, a document or fragment without a tree of its own, such as a
+/// statement introspected from a database or the text of a type.
+pub(crate) fn tokenize(source: &str) -> Vec<Token<'_>> {
+    crate::parser::parse(source, None).map(|tree| tokens(source, &tree)).unwrap_or_default()
+}
+
+/// Reads the tokens of `source` that start from `from` and before `until` into `tokens`, skipping
+/// whitespace and comments, and returns where it stopped reading. The last token or comment may
+/// end after `until`.
+fn scan<'a>(source: &'a str, from: usize, until: usize, tokens: &mut Vec<Token<'a>>) -> usize {
+    let mut chars = source[from..].char_indices().map(|(index, ch)| (from + index, ch)).peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        if start >= until {
+            return start;
+        }
+        let kind = match ch {
+            _ if ch.is_whitespace() => continue,
+            '-' if source[start..].starts_with("--") => {
+                skip_line(&mut chars);
+                continue;
+            }
+            '/' if source[start..].starts_with("//") => {
+                skip_line(&mut chars);
+                continue;
+            }
+            '#' => {
+                skip_line(&mut chars);
+                continue;
+            }
+            '/' if source[start..].starts_with("/*") => {
+                chars.next();
+                while let Some((index, _)) = chars.next() {
+                    if source[index..].starts_with("*/") {
+                        chars.next();
+                        break;
+                    }
+                }
+                continue;
+            }
+            '\'' | '"' => {
+                skip_string(&mut chars, ch);
+                TokenKind::String
+            }
+            '`' | '⟨' => {
+                let close = if ch == '`' { '`' } else { '⟩' };
+                for (_, next) in chars.by_ref() {
+                    if next == close {
+                        break;
+                    }
+                }
+                TokenKind::Ident
+            }
+            '$' => {
+                while chars.next_if(|&(_, next)| is_ident_continue(next)).is_some() {}
+                TokenKind::Param
+            }
+            _ if ch.is_ascii_digit() => {
+                while let Some(&(index, next)) = chars.peek() {
+                    let is_fraction = next == '.'
+                        && source[index + 1..].starts_with(|c: char| c.is_ascii_digit());
+                    if is_fraction || is_ident_continue(next) {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let end = chars.peek().map_or(source.len(), |&(index, _)| index);
+                let suffix = source[start..end]
+                    .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == '_');
+                let is_exponent =
+                    suffix.len() > 1 && suffix[1..].bytes().all(|b| b.is_ascii_digit());
+                match suffix {
+                    "" | "f" | "dec" => TokenKind::Number,
+                    _ if is_exponent && suffix.starts_with(['e', 'E']) => TokenKind::Number,
+                    _ => TokenKind::Duration,
+                }
+            }
+            _ if is_ident_start(ch) => {
+                // String prefixes such as `r"person:one"`, `d"2024-01-01"` and `u"..."`.
+                if matches!(ch, 'r' | 'd' | 'u' | 's') {
+                    if let Some(&(_, quote @ ('\'' | '"'))) = chars.peek() {
+                        chars.next();
+                        skip_string(&mut chars, quote);
+                        let end = chars.peek().map_or(source.len(), |&(index, _)| index);
+                        tokens.push(Token {
+                            kind: TokenKind::String,
+                            text: &source[start..end],
+                            start,
+                            end,
+                        });
+                        continue;
+                    }
+                }
+                while chars.next_if(|&(_, next)| is_ident_continue(next)).is_some() {}
+                TokenKind::Ident
+            }
+            _ => match PUNCTUATION.iter().find(|punct| source[start..].starts_with(*punct)) {
+                Some(punct) => {
+                    for _ in 1..punct.chars().count() {
+                        chars.next();
+                    }
+                    TokenKind::Punct
+                }
+                None if ch.is_ascii_punctuation() => TokenKind::Punct,
+                None => TokenKind::Unknown,
+            },
+        };
+        let end = chars.peek().map_or(source.len(), |&(index, _)| index);
+        tokens.push(Token { kind, text: &source[start..end], start, end });
+    }
+
+    source.len()
+}
+
+fn skip_line(chars: &mut std::iter::Peekable<impl Iterator<Item = (usize, char)>>) {
+    while chars.next_if(|&(_, ch)| ch != '\n').is_some() {}
+}
+
+fn skip_string(chars: &mut std::iter::Peekable<impl Iterator<Item = (usize, char)>>, quote: char) {
+    while let Some((_, ch)) = chars.next() {
+        if ch == '\\' {
+            chars.next();
+        } else if ch == quote {
+            break;
+        }
+    }
+}
+
+/// Splits `tokens` into statements on `;` tokens outside of any brackets.
+///
+/// The returned ranges index into `tokens` and exclude the terminating `;`.
+pub(crate) fn split_statements(tokens: &[Token<'_>]) -> Vec<std::ops::Range<usize>> {
+    let mut statements = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Punct {
+            continue;
+        }
+        match token.text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            ";" if depth == 0 => {
+                if start < index {
+                    statements.push(start..index);
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() {
+        statements.push(start..tokens.len());
+    }
+    statements
+}
+
+/// Returns the index of the token closing the bracket opened at `open`.
+pub(crate) fn matching_bracket(tokens: &[Token<'_>], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        if token.kind != TokenKind::Punct {
+            continue;
+        }
+        match token.text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits `tokens` on `separator` tokens outside of any brackets.
+pub(crate) fn split_top_level(
+    tokens: &[Token<'_>],
+    separator: &str,
+) -> Vec<std::ops::Range<usize>> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Punct {
+            continue;
+        }
+        match token.text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            text if text == separator && depth == 0 => {
+                parts.push(start..index);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() || !parts.is_empty() {
+        parts.push(start..tokens.len());
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    fn kinds(source: &str) -> Vec<(super::TokenKind, &str)> {
+        super::tokenize(source).iter().map(|token| (token.kind, token.text)).collect()
+    }
+
+    #[test]
+    fn skips_whitespace_and_comments() {
+        let source = "SELECT -- one\n* # two\nFROM // three\n/* four\n */ person";
+        let texts: Vec<&str> = super::tokenize(source).iter().map(|token| token.text).collect();
+        assert_eq!(texts, ["SELECT", "*", "FROM", "person"]);
+    }
+
+    #[test]
+    fn reads_the_same_tokens_from_an_edited_tree() {
+        let before = "SELECT name FROM person;\nSELECT * FROM pet;";
+        let after = "SELECT name, age FROM person;\nSELECT * FROM pet;";
+        let mut tree = crate::parser::parse(before, None).unwrap();
+        tree.edit(&tree_sitter::InputEdit {
+            start_byte: 11,
+            old_end_byte: 11,
+            new_end_byte: 16,
+            start_position: tree_sitter::Point::new(0, 11),
+            old_end_position: tree_sitter::Point::new(0, 11),
+            new_end_position: tree_sitter::Point::new(0, 16),
+        });
+        let edited = crate::parser::parse(after, Some(&tree)).unwrap();
+        let texts = |tokens: Vec<super::Token<'_>>| -> Vec<(usize, String)> {
+            tokens.iter().map(|token| (token.start, token.text.to_string())).collect()
+        };
+        assert_eq!(texts(super::tokens(after, &edited)), texts(super::tokenize(after)));
+    }
+
+    #[test]
+    fn recognises_literals() {
+        assert_eq!(
+            kinds(r#"$name 'it\'s' "x" r"person:one" 12 1.5 1e3 10dec 1h30m `a b` ⟨c⟩"#),
+            [
+                (super::TokenKind::Param, "$name"),
+                (super::TokenKind::String, r"'it\'s'"),
+                (super::TokenKind::String, r#""x""#),
+                (super::TokenKind::String, r#"r"person:one""#),
+                (super::TokenKind::Number, "12"),
+                (super::TokenKind::Number, "1.5"),
+                (super::TokenKind::Number, "1e3"),
+                (super::TokenKind::Number, "10dec"),
+                (super::TokenKind::Duration, "1h30m"),
+                (super::TokenKind::Ident, "`a b`"),
+                (super::TokenKind::Ident, "⟨c⟩"),
+            ]
+        );
+    }
+
+    #[test]
+    fn prefers_the_longest_punctuation() {
+        let texts: Vec<&str> =
+            super::tokenize("a<->b->c<-d+?=1..2::x").iter().map(|token| token.text).collect();
+        assert_eq!(
+            texts,
+            ["a", "<->", "b", "->", "c", "<-", "d", "+?=", "1", "..", "2", "::", "x"]
+        );
+    }
+
+    #[test]
+    fn idents_are_unquoted() {
+        let tokens = super::tokenize("`first name` ⟨last⟩ $age");
+        let idents: Vec<&str> = tokens.iter().map(super::Token::ident).collect();
+        assert_eq!(idents, ["first name", "last", "age"]);
+    }
+
+    #[test]
+    fn splits_statements_outside_of_brackets() {
+        let tokens = super::tokenize("LET $a = { x: 1; }; ; SELECT * FROM ($a; 1)");
+        let statements: Vec<String> = super::split_statements(&tokens)
+            .into_iter()
+            .map(|range| tokens[range].iter().map(|token| token.text).collect())
+            .collect();
+        assert_eq!(statements, ["LET$a={x:1;}", "SELECT*FROM($a;1)"]);
+    }
+
+    #[test]
+    fn finds_the_matching_bracket() {
+        let tokens = super::tokenize("f([1, (2)], 3)");
+        assert_eq!(super::matching_bracket(&tokens, 1), Some(tokens.len() - 1));
+        assert_eq!(super::matching_bracket(&tokens, 2), Some(8));
+        assert_eq!(super::matching_bracket(&super::tokenize("(()"), 0), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Analysis of SurrealQL documents, independent of any editor protocol.
+//!
+//! [`AnalysisHost`] holds the documents of a workspace and the schema built from its `DEFINE`
+//! statements, and hands out [`Analysis`] snapshots that answer queries about them, from any
+//! thread and without blocking edits. Every position is a byte offset into the text of a file, and
+//! files are identified by URL, so that the language server, the command line and any other tool
+//! can map them onto their own representation.
+
+mod analysis;
+mod casing;
+mod completion;
+pub mod config;
+mod diagnostics;
+mod diagram;
+mod format;
+mod functions;
+mod hints;
+mod keywords;
+mod lexer;
+mod migration;
+mod parser;
+mod position;
+mod query;
+mod refactor;
+mod runnables;
+mod schema;
+mod scope;
+mod snapshot;
+mod typegen;
+mod types;
+
+pub use crate::analysis::{
+    Analysis, AnalysisHost, CompletionItem, CompletionKind, FileDiagnostics, Hover,
+};
+pub use crate::diagnostics::{Diagnostic, Lint, Related, Severity, Tag};
+pub use crate::diagram::DiagramFormat;
+pub use crate::hints::{InlayHint, InlayHintKind};
+pub use crate::migration::{Migration, SchemaSource};
+pub use crate::runnables::{Query, Runnable, RunnableKind, Selection};
+pub use crate::schema::LIVE_SCHEME;
+pub use crate::snapshot::Snapshot;
+pub use crate::typegen::TypeLanguage;
+
+/// A byte range within the text of a file.
+pub type TextRange = std::ops::Range<usize>;
+
+/// A byte range within the text of a given file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileRange {
+    pub file: url::Url,
+    pub range: TextRange,
+}
+
+/// Replaces `range` with `new_text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// Returns `text` with `edits`, which must not overlap, applied. Text inserted where another
+    /// edit starts goes before it, and insertions at the same offset go in the order of `edits`.
+    pub fn apply(text: &str, edits: &[TextEdit]) -> String {
+        let mut edits: Vec<&TextEdit> = edits.iter().collect();
+        edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+        let mut text = text.to_string();
+        for edit in edits.into_iter().rev() {
+            text.replace_range(edit.range.clone(), &edit.new_text);
+        }
+        text
+    }
+}
+
+/// What a [`SourceChange`] does, so that editors can group and filter them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssistKind {
+    /// Fixes the problem reported by a diagnostic.
+    QuickFix,
+    /// Extracts the selection into a `LET` or a function.
+    Extract,
+    /// Inlines a `LET` binding.
+    Inline,
+    /// Upper-cases every keyword of a document.
+    UppercaseKeywords,
+    /// Lower-cases every keyword of a document.
+    LowercaseKeywords,
+}
+
+/// A labelled set of edits, such as a quick fix or a refactoring, which may span several files.
+#[derive(Clone, Debug)]
+pub struct SourceChange {
+    pub label: String,
+    pub kind: AssistKind,
+    pub edits: std::collections::BTreeMap<url::Url, Vec<TextEdit>>,
+    /// Whether the change is the obvious correction, e.g. the single closest name.
+    pub preferred: bool,
+}
+
+impl SourceChange {
+    fn new(label: String, kind: AssistKind, file: &url::Url, edits: Vec<TextEdit>) -> Self {
+        let edits = std::collections::BTreeMap::from([(file.clone(), edits)]);
+        Self { label, kind, edits, preferred: false }
+    }
+
+    fn preferred(mut self) -> Self {
+        self.preferred = true;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn edit(range: crate::TextRange, new_text: &str) -> crate::TextEdit {
+        crate::TextEdit { range, new_text: new_text.to_string() }
+    }
+
+    #[test]
+    fn applies_edits_from_the_end() {
+        let edits = [edit(0..3, "let"), edit(9..10, "2")];
+        assert_eq!(crate::TextEdit::apply("LET $a = 1;", &edits), "let $a = 2;");
+    }
+
+    #[test]
+    fn inserts_before_a_replacement_at_the_same_offset() {
+        let edits = [edit(0..0, "A;"), edit(0..3, "B"), edit(3..3, "C"), edit(3..3, "D")];
+        assert_eq!(crate::TextEdit::apply("xyz", &edits), "A;BCD");
+    }
+}
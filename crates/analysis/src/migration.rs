@@ -0,0 +1,382 @@
+//! Migrations between two schemas: the `REMOVE` and `DEFINE` statements that turn one into the
+//! other, and warnings for those that may lose data or fail on existing records.
+
+/// A schema that migrations are generated from or to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaSource {
+    /// The `DEFINE` and `REMOVE` statements of the files of the workspace, without any database or
+    /// snapshot.
+    Workspace,
+    /// The `DEFINE` statements of a document, e.g. as introspected from a database.
+    Statements(String),
+    /// The schema snapshot at a path.
+    Snapshot(std::path::PathBuf),
+}
+
+/// The statements migrating one schema to another.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Migration {
+    /// The statements in the order they are to be run, each ending with a `;`.
+    pub statements: Vec<String>,
+    /// The statements that may lose data or fail on existing records, e.g. dropping a field or
+    /// tightening its type, and the definitions that could not be migrated.
+    pub warnings: Vec<String>,
+}
+
+impl std::fmt::Display for Migration {
+    /// Writes the migration as a SurrealQL document, with the warnings as comments at the top.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for warning in &self.warnings {
+            writeln!(f, "-- WARNING: {}", warning)?;
+        }
+        if !self.warnings.is_empty() && !self.statements.is_empty() {
+            writeln!(f)?;
+        }
+        for statement in &self.statements {
+            writeln!(f, "{}", statement)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the migration from the schema `from` to the schema `to`. Definitions that only `from`
+/// has are removed first, then those that only `to` has are defined and those that differ are
+/// overwritten, tables before their fields and indexes.
+pub(crate) fn migration(
+    from: &crate::schema::Schema,
+    to: &crate::schema::Schema,
+    dialect: crate::config::Dialect,
+) -> Migration {
+    let mut migration = Migration::default();
+    let from_keys = keys(from);
+    let to_keys = keys(to);
+
+    for key in from_keys.iter().filter(|key| !to_keys.contains(key)) {
+        // Removing a table removes its fields and indexes along with it.
+        let table_removed = match key {
+            crate::schema::Key::Field(table, _) | crate::schema::Key::Index(table, _) => {
+                !to_keys.contains(&crate::schema::Key::Table(table.clone()))
+                    && from_keys.contains(&crate::schema::Key::Table(table.clone()))
+            }
+            _ => false,
+        };
+        if table_removed {
+            continue;
+        }
+        let statement = match key {
+            crate::schema::Key::Table(table) => {
+                migration
+                    .warnings
+                    .push(format!("`REMOVE TABLE {}` deletes the records of the table", table));
+                format!("REMOVE TABLE {};", table)
+            }
+            crate::schema::Key::Field(table, field) => {
+                migration.warnings.push(format!(
+                    "`REMOVE FIELD {} ON {}` drops the values of the field when records of a \
+                     SCHEMAFULL table are next updated",
+                    field, table
+                ));
+                format!("REMOVE FIELD {} ON {};", field, table)
+            }
+            crate::schema::Key::Index(table, index) => {
+                format!("REMOVE INDEX {} ON {};", index, table)
+            }
+            crate::schema::Key::Function(function) => format!("REMOVE FUNCTION {};", function),
+            crate::schema::Key::Param(param) => format!("REMOVE PARAM ${};", param),
+        };
+        migration.statements.push(statement);
+    }
+
+    for key in &to_keys {
+        let exists = from_keys.contains(key);
+        if exists && !is_changed(from, to, key) {
+            continue;
+        }
+        if exists {
+            migration.warnings.extend(tightening(from, to, key, dialect));
+        }
+        let Some(statement) = statement(to, key) else {
+            migration.warnings.push(format!(
+                "`DEFINE {} {}` cannot be generated, as the schema it comes from only holds its \
+                 name",
+                key.keyword(),
+                name(key)
+            ));
+            continue;
+        };
+        // SurrealDB 1.x replaces existing definitions without `OVERWRITE`, which it does not know.
+        let overwrite =
+            if exists && dialect != crate::config::Dialect::V1 { " OVERWRITE" } else { "" };
+        migration.statements.push(format!("DEFINE {}{} {};", key.keyword(), overwrite, statement));
+    }
+    migration
+}
+
+/// Returns the definitions of `schema` in the order they are defined: tables, fields, indexes,
+/// functions and then params.
+fn keys(schema: &crate::schema::Schema) -> Vec<crate::schema::Key> {
+    let tables = schema.tables.keys().map(|table| crate::schema::Key::Table(table.clone()));
+    let fields = schema.fields.iter().flat_map(|(table, fields)| {
+        fields.keys().map(|field| crate::schema::Key::Field(table.clone(), field.clone()))
+    });
+    let indexes = schema.indexes.iter().flat_map(|(table, indexes)| {
+        indexes.keys().map(|index| crate::schema::Key::Index(table.clone(), index.clone()))
+    });
+    let functions =
+        schema.functions.keys().map(|function| crate::schema::Key::Function(function.clone()));
+    let params = schema.params.keys().map(|param| crate::schema::Key::Param(param.clone()));
+    tables.chain(fields).chain(indexes).chain(functions).chain(params).collect()
+}
+
+/// Returns the name of the definition as written after its kind.
+fn name(key: &crate::schema::Key) -> String {
+    match key {
+        crate::schema::Key::Table(name) | crate::schema::Key::Function(name) => name.clone(),
+        crate::schema::Key::Field(table, name) | crate::schema::Key::Index(table, name) => {
+            format!("{} ON {}", name, table)
+        }
+        crate::schema::Key::Param(name) => format!("${}", name),
+    }
+}
+
+/// Returns whether the definition of `key` differs between the schemas. Statements are compared
+/// when both schemas know them, and otherwise what both know of the definition.
+fn is_changed(
+    from: &crate::schema::Schema,
+    to: &crate::schema::Schema,
+    key: &crate::schema::Key,
+) -> bool {
+    if let (Some(a), Some(b)) = (from.statements.get(key), to.statements.get(key)) {
+        return normalised(key, a) != normalised(key, b);
+    }
+    match key {
+        crate::schema::Key::Table(table) => match (from.tables.get(table), to.tables.get(table)) {
+            (Some(a), Some(b)) => a.schemafull != b.schemafull || a.kind != b.kind,
+            _ => false,
+        },
+        crate::schema::Key::Field(table, field) => field_kind(from, table, field)
+            .zip(field_kind(to, table, field))
+            .is_some_and(|(a, b)| a != b),
+        crate::schema::Key::Function(function) => {
+            match (from.functions.get(function), to.functions.get(function)) {
+                (Some(a), Some(b)) => a != b,
+                _ => false,
+            }
+        }
+        crate::schema::Key::Index(..) | crate::schema::Key::Param(_) => false,
+    }
+}
+
+/// Returns the tokens of `statement`, a definition of `key`, without what does not change its
+/// meaning: whitespace, comments, the quoting and case of identifiers, and the clauses that
+/// SurrealDB adds to the statements it reports, such as `TYPE ANY`, `SCHEMALESS` and the default
+/// `PERMISSIONS`.
+fn normalised(key: &crate::schema::Key, statement: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = crate::lexer::tokenize(statement)
+        .iter()
+        .map(|token| match token.kind {
+            crate::lexer::TokenKind::Ident => token.ident().to_uppercase(),
+            _ => token.text.to_string(),
+        })
+        .collect();
+    let default_permissions = match key {
+        crate::schema::Key::Table(_) => "NONE",
+        _ => "FULL",
+    };
+    let mut index = 0;
+    while index < tokens.len() {
+        let next = tokens.get(index + 1).map(String::as_str);
+        match (tokens[index].as_str(), next) {
+            ("ON", Some("TABLE")) => {
+                tokens.remove(index + 1);
+            }
+            ("TYPE", Some("ANY")) if matches!(key, crate::schema::Key::Table(_)) => {
+                tokens.drain(index..index + 2);
+                continue;
+            }
+            ("SCHEMALESS", _) if matches!(key, crate::schema::Key::Table(_)) => {
+                tokens.remove(index);
+                continue;
+            }
+            ("PERMISSIONS", Some(permissions)) if permissions == default_permissions => {
+                tokens.drain(index..index + 2);
+                continue;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    tokens
+}
+
+fn field_kind<'s>(
+    schema: &'s crate::schema::Schema,
+    table: &str,
+    field: &str,
+) -> Option<&'s Option<crate::types::Kind>> {
+    schema.fields.get(table)?.get(field).map(|field| &field.kind)
+}
+
+/// Returns a warning when the definition of `key` in `to` accepts less than it does in `from`,
+/// so that existing records may no longer be valid.
+fn tightening(
+    from: &crate::schema::Schema,
+    to: &crate::schema::Schema,
+    key: &crate::schema::Key,
+    dialect: crate::config::Dialect,
+) -> Option<String> {
+    match key {
+        crate::schema::Key::Table(table) => {
+            let (a, b) = (from.tables.get(table)?, to.tables.get(table)?);
+            if !a.schemafull && b.schemafull {
+                return Some(format!(
+                    "`{}` becomes SCHEMAFULL, which drops the values of the fields it does not \
+                     define when records are next updated",
+                    table
+                ));
+            }
+            (a.kind != b.kind && b.kind != crate::schema::TableKind::Any).then(|| {
+                format!(
+                    "The type of `{}` changes from {} to {}, which existing records may not match",
+                    table, a.kind, b.kind
+                )
+            })
+        }
+        crate::schema::Key::Field(table, field) => {
+            let a = field_kind(from, table, field)?.clone().unwrap_or(crate::types::Kind::Any);
+            let b = field_kind(to, table, field)?.clone().unwrap_or(crate::types::Kind::Any);
+            // Any value is assignable to `any` and from it, which says nothing about the records.
+            let is_tightened = b != crate::types::Kind::Any
+                && (a == crate::types::Kind::Any || !a.is_assignable_to(&b, dialect));
+            is_tightened.then(|| {
+                format!(
+                    "The type of `{}.{}` changes from `{}` to `{}`, which existing values may \
+                     not match",
+                    table, field, a, b
+                )
+            })
+        }
+        crate::schema::Key::Function(_)
+        | crate::schema::Key::Index(..)
+        | crate::schema::Key::Param(_) => None,
+    }
+}
+
+/// Returns the statement of the definition of `key` in `schema`, as written after `DEFINE kind`.
+/// Tables and fields without one, e.g. from a snapshot, are written from what is known of them.
+fn statement(schema: &crate::schema::Schema, key: &crate::schema::Key) -> Option<String> {
+    if let Some(statement) = schema.statements.get(key) {
+        return Some(statement.clone());
+    }
+    match key {
+        crate::schema::Key::Table(table) => {
+            let definition = schema.tables.get(table)?;
+            let mut statement = table.clone();
+            if definition.kind != crate::schema::TableKind::Any {
+                statement += &format!(" TYPE {}", definition.kind);
+            }
+            statement += if definition.schemafull { " SCHEMAFULL" } else { " SCHEMALESS" };
+            Some(statement)
+        }
+        crate::schema::Key::Field(table, field) => {
+            let kind = field_kind(schema, table, field)?;
+            Some(match kind {
+                Some(kind) => format!("{} ON {} TYPE {}", field, table, kind),
+                None => format!("{} ON {}", field, table),
+            })
+        }
+        crate::schema::Key::Function(_)
+        | crate::schema::Key::Index(..)
+        | crate::schema::Key::Param(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn schema(text: &str) -> crate::schema::Schema {
+        let uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        crate::schema::Schema::from_changes(
+            &crate::schema::changes(&uri, text),
+            crate::config::Dialect::V2,
+        )
+    }
+
+    fn migration(from: &str, to: &str) -> super::Migration {
+        super::migration(&schema(from), &schema(to), crate::config::Dialect::V2)
+    }
+
+    fn is_same(statement: &str, other: &str) -> bool {
+        let key = crate::schema::Key::Table(String::from("person"));
+        super::normalised(&key, statement) == super::normalised(&key, other)
+    }
+
+    #[test]
+    fn removing_a_table_or_field_warns() {
+        let migration = migration(
+            "DEFINE TABLE person; DEFINE FIELD name ON person;\n\
+             DEFINE TABLE log; DEFINE FIELD at ON log; DEFINE INDEX at ON log FIELDS at;",
+            "DEFINE TABLE person;",
+        );
+        // The fields and indexes of a removed table go with it.
+        assert_eq!(migration.statements, ["REMOVE TABLE log;", "REMOVE FIELD name ON person;"]);
+        assert_eq!(
+            migration.warnings,
+            [
+                "`REMOVE TABLE log` deletes the records of the table",
+                "`REMOVE FIELD name ON person` drops the values of the field when records of a \
+                 SCHEMAFULL table are next updated",
+            ]
+        );
+    }
+
+    #[test]
+    fn tightening_a_definition_warns() {
+        let migration = migration(
+            "DEFINE TABLE person SCHEMALESS; DEFINE TABLE likes TYPE NORMAL;\n\
+             DEFINE FIELD age ON person TYPE int; DEFINE FIELD name ON person TYPE option<string>;",
+            "DEFINE TABLE person SCHEMAFULL; DEFINE TABLE likes TYPE RELATION IN person OUT post;\n\
+             DEFINE FIELD age ON person TYPE number; DEFINE FIELD name ON person TYPE string;",
+        );
+        assert_eq!(
+            migration.warnings,
+            [
+                "The type of `likes` changes from NORMAL to RELATION IN person OUT post, which \
+                 existing records may not match",
+                "`person` becomes SCHEMAFULL, which drops the values of the fields it does not \
+                 define when records are next updated",
+                "The type of `person.name` changes from `option<string>` to `string`, which \
+                 existing values may not match",
+            ]
+        );
+        // Widening `age` to `number` is overwritten without a warning.
+        assert!(migration
+            .statements
+            .contains(&String::from("DEFINE FIELD OVERWRITE age ON person TYPE number;")));
+    }
+
+    #[test]
+    fn loosening_a_definition_does_not_warn() {
+        let migration = migration(
+            "DEFINE TABLE person SCHEMAFULL; DEFINE FIELD name ON person TYPE string;",
+            "DEFINE TABLE person SCHEMALESS; DEFINE FIELD name ON person TYPE option<string>;",
+        );
+        assert_eq!(migration.statements.len(), 2);
+        assert!(migration.warnings.is_empty());
+    }
+
+    #[test]
+    fn normalised_ignores_what_does_not_change_the_meaning() {
+        assert!(is_same("person SCHEMAFULL", "person\n  -- The people.\n  SCHEMAFULL"));
+        assert!(is_same("person SCHEMAFULL", "`person` schemafull"));
+        assert!(is_same("person", "person TYPE ANY SCHEMALESS PERMISSIONS NONE"));
+        assert!(!is_same("person", "person PERMISSIONS FULL"));
+        assert!(!is_same("person", "person SCHEMAFULL"));
+        assert!(!is_same("person", "people"));
+
+        let key = crate::schema::Key::Field(String::from("person"), String::from("name"));
+        assert_eq!(
+            super::normalised(&key, "name ON TABLE person TYPE string PERMISSIONS FULL"),
+            ["NAME", "ON", "PERSON", "TYPE", "STRING"]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+pub(crate) fn initialise_parser() -> tree_sitter::Parser {
+    let mut parser = tree_sitter::Parser::new();
+    if parser.set_language(tree_sitter_surrealql::language()).is_err() {
+        panic!("Failed to set parser language");
+    }
+    parser
+}
+
+thread_local! {
+    /// A parser per thread, as parsing needs a mutable parser but may happen on any thread that
+    /// answers a query.
+    static PARSER: std::cell::RefCell<tree_sitter::Parser> =
+        std::cell::RefCell::new(initialise_parser());
+}
+
+/// Parses `text` with the parser of the current thread, reusing `old_tree`, which must have been
+/// edited to match `text`.
+pub(crate) fn parse(text: &str, old_tree: Option<&tree_sitter::Tree>) -> Option<tree_sitter::Tree> {
+    PARSER.with(|parser| parser.borrow_mut().parse(text, old_tree))
+}
+
+/// The text of a document and its syntax tree, which is parsed on first use and shared by the
+/// queries about the document, so that they all run on the same tree.
+#[derive(Debug)]
+pub(crate) struct Parsed {
+    text: std::sync::Arc<str>,
+    tree: std::sync::OnceLock<Option<tree_sitter::Tree>>,
+    /// The tree of an earlier version of the document, edited to match `text`, whose unchanged
+    /// parts are reused when the document is parsed.
+    edited_tree: Option<tree_sitter::Tree>,
+}
+
+impl Parsed {
+    pub(crate) fn new(text: String, edited_tree: Option<tree_sitter::Tree>) -> Self {
+        Self { text: text.into(), tree: std::sync::OnceLock::new(), edited_tree }
+    }
+
+    pub(crate) fn text(&self) -> &std::sync::Arc<str> {
+        &self.text
+    }
+
+    /// Returns the syntax tree of the document, parsing it on first use.
+    pub(crate) fn tree(&self) -> Option<&tree_sitter::Tree> {
+        self.tree.get_or_init(|| parse(&self.text, self.edited_tree.as_ref())).as_ref()
+    }
+
+    /// Returns the last tree that was parsed, edited or not, without parsing the document.
+    pub(crate) fn last_tree(&self) -> Option<&tree_sitter::Tree> {
+        self.tree.get().and_then(Option::as_ref).or(self.edited_tree.as_ref())
+    }
+
+    /// Returns the tokens at the leaves of the syntax tree.
+    pub(crate) fn tokens(&self) -> Vec<crate::lexer::Token<'_>> {
+        self.tree().map(|tree| crate::lexer::tokens(&self.text, tree)).unwrap_or_default()
+    }
+}
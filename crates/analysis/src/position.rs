@@ -1,13 +1,9 @@
+/// Returns the keyword at `cursor` and its byte range.
 pub(crate) fn retrieve_keyword_at_position(
     document_content: &str,
-    parser: &mut tree_sitter::Parser,
-    syntax_tree: &mut Option<tree_sitter::Tree>,
-    cursor_line: usize,
-    cursor_character: usize,
-) -> Option<String> {
-    *syntax_tree = parser.parse(document_content, syntax_tree.as_ref());
-    let tree = syntax_tree.as_ref()?;
-
+    tree: &tree_sitter::Tree,
+    cursor: tree_sitter::Point,
+) -> Option<(String, crate::TextRange)> {
     let mut query_cursor = tree_sitter::QueryCursor::new();
     let document_bytes = document_content.as_bytes();
 
@@ -41,8 +37,8 @@ pub(crate) fn retrieve_keyword_at_position(
         &KEYWORD_QUERY,
         tree.root_node(),
         document_bytes,
-        cursor_line,
-        cursor_character,
+        cursor.row,
+        cursor.column,
     )
 }
 
@@ -53,7 +49,7 @@ fn find_keyword_at_position(
     document_bytes: &[u8],
     cursor_line: usize,
     cursor_character: usize,
-) -> Option<String> {
+) -> Option<(String, crate::TextRange)> {
     for match_ in query_cursor.matches(query, root_node, document_bytes) {
         for capture in match_.captures {
             let node = capture.node;
@@ -61,7 +57,8 @@ fn find_keyword_at_position(
             let end_position = node.end_position();
 
             if is_within_cursor_range(start_position, end_position, cursor_line, cursor_character) {
-                return node.utf8_text(document_bytes).ok().map(String::from);
+                let text = node.utf8_text(document_bytes).ok()?;
+                return Some((String::from(text), node.byte_range()));
             }
         }
     }
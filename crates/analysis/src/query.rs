@@ -0,0 +1,896 @@
+//! A structural view over the tokens of SurrealQL statements.
+//!
+//! This is deliberately forgiving: it recognises the clauses that semantic analysis cares about and
+//! ignores anything it doesn't understand, so that half-typed queries still produce results.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StatementKind {
+    Select,
+    Create,
+    Update,
+    Upsert,
+    Delete,
+    Insert,
+    Relate,
+    Define,
+    Remove,
+    Let,
+    Other,
+}
+
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "FROM", "WHERE", "SPLIT", "GROUP", "ORDER", "LIMIT", "START", "FETCH", "TIMEOUT", "PARALLEL",
+    "EXPLAIN", "WITH", "SET", "UNSET", "CONTENT", "MERGE", "PATCH", "REPLACE", "RETURN", "VALUES",
+    "OMIT",
+];
+
+/// Keywords that are values rather than field names when they appear in an expression.
+const VALUE_KEYWORDS: &[&str] = &["NONE", "NULL", "TRUE", "FALSE", "AND", "OR", "NOT", "IS", "IN"];
+
+/// A reference to a table by name, e.g. the `person` in `SELECT * FROM person:tobie`.
+#[derive(Clone, Debug)]
+pub(crate) struct TableReference {
+    pub(crate) name: String,
+    pub(crate) span: std::ops::Range<usize>,
+}
+
+/// A reference to a top-level field of the table(s) a statement operates on.
+#[derive(Clone, Debug)]
+pub(crate) struct FieldReference {
+    pub(crate) name: String,
+    pub(crate) span: std::ops::Range<usize>,
+}
+
+/// A `field = value` style assignment from a `SET` clause or a `CONTENT`/`MERGE` object.
+#[derive(Clone, Debug)]
+pub(crate) struct Assignment {
+    /// The full path of the assigned field, e.g. `address.city`.
+    pub(crate) field: String,
+    pub(crate) field_span: std::ops::Range<usize>,
+    pub(crate) operator: String,
+    /// The token indices of the assigned value, relative to the statement.
+    pub(crate) value: std::ops::Range<usize>,
+}
+
+/// A call of a built-in or custom function, e.g. `string::len($name)`.
+#[derive(Clone, Debug)]
+pub(crate) struct Call {
+    /// The name as written, e.g. `fn::greet`.
+    pub(crate) name: String,
+    pub(crate) span: std::ops::Range<usize>,
+    /// The token indices of the opening and closing parentheses.
+    pub(crate) open: usize,
+    pub(crate) close: usize,
+}
+
+/// Returns the token ranges of every statement in `tokens`, including statements nested in blocks
+/// and subqueries. Nested statements come after the statement containing them.
+pub(crate) fn statements(tokens: &[crate::lexer::Token<'_>]) -> Vec<std::ops::Range<usize>> {
+    let mut statements = Vec::new();
+    collect_statements(tokens, 0, &mut statements);
+    statements
+}
+
+fn collect_statements(
+    tokens: &[crate::lexer::Token<'_>],
+    offset: usize,
+    statements: &mut Vec<std::ops::Range<usize>>,
+) {
+    for range in crate::lexer::split_statements(tokens) {
+        statements.push(offset + range.start..offset + range.end);
+        let statement = &tokens[range.clone()];
+        let mut index = 0;
+        while index < statement.len() {
+            let token = &statement[index];
+            let nested = if token.is_punct("{") {
+                true
+            } else if token.is_punct("(") {
+                statement.get(index + 1).is_some_and(|next| statement_kind(&[*next]).is_some())
+            } else {
+                false
+            };
+            if nested {
+                if let Some(close) = crate::lexer::matching_bracket(statement, index) {
+                    collect_statements(
+                        &statement[index + 1..close],
+                        offset + range.start + index + 1,
+                        statements,
+                    );
+                    index = close;
+                }
+            }
+            index += 1;
+        }
+    }
+}
+
+fn statement_kind(tokens: &[crate::lexer::Token<'_>]) -> Option<StatementKind> {
+    let first = tokens.first()?;
+    if first.kind != crate::lexer::TokenKind::Ident {
+        return None;
+    }
+    let kind = match first.text.to_ascii_uppercase().as_str() {
+        "SELECT" => StatementKind::Select,
+        "CREATE" => StatementKind::Create,
+        "UPDATE" => StatementKind::Update,
+        "UPSERT" => StatementKind::Upsert,
+        "DELETE" => StatementKind::Delete,
+        "INSERT" => StatementKind::Insert,
+        "RELATE" => StatementKind::Relate,
+        "DEFINE" => StatementKind::Define,
+        "REMOVE" => StatementKind::Remove,
+        "LET" => StatementKind::Let,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Returns the kind of the statement, looking through `RETURN` and `LET $x =` prefixes.
+pub(crate) fn kind(statement: &[crate::lexer::Token<'_>]) -> StatementKind {
+    statement_kind(body(statement)).unwrap_or(StatementKind::Other)
+}
+
+/// Strips `RETURN` and `LET $x =` prefixes from a statement.
+pub(crate) fn body<'t, 'a>(
+    statement: &'t [crate::lexer::Token<'a>],
+) -> &'t [crate::lexer::Token<'a>] {
+    match statement {
+        [first, rest @ ..] if first.is_keyword("RETURN") => body(rest),
+        [first, param, rest @ ..]
+            if first.is_keyword("LET") && param.kind == crate::lexer::TokenKind::Param =>
+        {
+            let rest = match rest {
+                [colon, _, rest @ ..] if colon.is_punct(":") => rest,
+                _ => rest,
+            };
+            match rest {
+                [equals, rest @ ..] if equals.is_punct("=") && statement_kind(rest).is_some() => {
+                    body(rest)
+                }
+                _ => statement,
+            }
+        }
+        _ => statement,
+    }
+}
+
+fn is_clause_keyword(tokens: &[crate::lexer::Token<'_>], index: usize) -> bool {
+    let token = &tokens[index];
+    if !CLAUSE_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword)) {
+        return false;
+    }
+    if index > 0 && tokens[index - 1].is_punct(".") {
+        return false;
+    }
+    // A keyword followed by an operator is a field name, e.g. `SET start = 1`.
+    !tokens.get(index + 1).is_some_and(|next| {
+        next.kind == crate::lexer::TokenKind::Punct
+            && !matches!(next.text, "{" | "(" | "[" | "*" | "$")
+    })
+}
+
+/// Returns the clauses of a statement as `(keyword, token range)` pairs, where the range excludes
+/// the keyword. The first entry has an empty keyword and covers everything before the first
+/// clause.
+pub(crate) fn clauses(
+    statement: &[crate::lexer::Token<'_>],
+) -> Vec<(String, std::ops::Range<usize>)> {
+    let mut clauses = Vec::new();
+    let mut keyword = String::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    // The projections of a `SELECT` may contain field names that look like clause keywords.
+    let mut seen_from = kind(statement) != StatementKind::Select;
+    for index in 0..statement.len() {
+        let token = &statement[index];
+        if token.kind == crate::lexer::TokenKind::Punct {
+            match token.text {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            continue;
+        }
+        if depth > 0 || !is_clause_keyword(statement, index) {
+            continue;
+        }
+        if !seen_from && !token.is_keyword("FROM") {
+            continue;
+        }
+        seen_from = true;
+        clauses.push((keyword, start..index));
+        keyword = token.text.to_ascii_uppercase();
+        start = index + 1;
+    }
+    clauses.push((keyword, start..statement.len()));
+    clauses
+}
+
+fn clause<'c>(
+    clauses: &'c [(String, std::ops::Range<usize>)],
+    keyword: &str,
+) -> Option<&'c std::ops::Range<usize>> {
+    clauses.iter().find(|(name, _)| name == keyword).map(|(_, range)| range)
+}
+
+/// Returns the tables a statement reads from or writes to.
+pub(crate) fn table_references(statement: &[crate::lexer::Token<'_>]) -> Vec<TableReference> {
+    let statement = body(statement);
+    let clauses = clauses(statement);
+    let targets = match statement_kind(statement) {
+        Some(StatementKind::Select) => clause(&clauses, "FROM").cloned(),
+        Some(StatementKind::Create | StatementKind::Update | StatementKind::Upsert) => {
+            clauses.first().map(|(_, range)| range.start + 1..range.end)
+        }
+        Some(StatementKind::Delete) => clause(&clauses, "FROM")
+            .cloned()
+            .or_else(|| clauses.first().map(|(_, range)| range.start + 1..range.end)),
+        Some(StatementKind::Insert) => {
+            let into = statement.iter().position(|token| token.is_keyword("INTO"));
+            into.map(|into| into + 1..(into + 2).min(statement.len()))
+        }
+        Some(StatementKind::Relate) => {
+            return relate_edge(statement).into_iter().collect();
+        }
+        _ => None,
+    };
+    let Some(targets) = targets else {
+        return Vec::new();
+    };
+
+    let mut references = Vec::new();
+    let targets = &statement[targets];
+    for part in crate::lexer::split_top_level(targets, ",") {
+        let mut target = &targets[part];
+        if target.first().is_some_and(|token| token.is_keyword("ONLY")) {
+            target = &target[1..];
+        }
+        if let Some(reference) = table_reference(target) {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+/// Recognises a bare table name or a record id such as `person:tobie`.
+fn table_reference(target: &[crate::lexer::Token<'_>]) -> Option<TableReference> {
+    let first = target.first()?;
+    if first.kind != crate::lexer::TokenKind::Ident {
+        return None;
+    }
+    let is_table = match target.get(1) {
+        None => true,
+        Some(next) => next.is_punct(":"),
+    };
+    is_table.then(|| TableReference { name: first.ident().to_string(), span: first.span() })
+}
+
+/// Returns the tables a `RELATE` statement relates, as its `in` table, its edge table and its `out`
+/// table. The ends are `None` unless they are record ids, e.g. when they are params or subqueries.
+pub(crate) fn relation(
+    statement: &[crate::lexer::Token<'_>],
+) -> Option<(Option<TableReference>, TableReference, Option<TableReference>)> {
+    let statement = body(statement);
+    if statement_kind(statement) != Some(StatementKind::Relate) {
+        return None;
+    }
+    let edge = relate_edge(statement)?;
+    let first = statement.iter().position(|token| token.is_punct("->") || token.is_punct("<-"))?;
+    let left = table_reference(&statement[1..first]);
+    let right = table_reference(&statement[first + 3..]);
+    // `RELATE out<-edge<-in` relates the same records as `RELATE in->edge->out`.
+    if statement[first].is_punct("<-") {
+        Some((right, edge, left))
+    } else {
+        Some((left, edge, right))
+    }
+}
+
+fn relate_edge(statement: &[crate::lexer::Token<'_>]) -> Option<TableReference> {
+    let arrows: Vec<usize> = statement
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| token.is_punct("->") || token.is_punct("<-"))
+        .map(|(index, _)| index)
+        .collect();
+    match arrows.as_slice() {
+        [first, second, ..] if second - first == 2 => {
+            table_reference(&statement[first + 1..*second])
+        }
+        _ => None,
+    }
+}
+
+/// The direction of a step of a graph traversal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Direction {
+    /// `->`, from the `in` of an edge to its `out`.
+    Out,
+    /// `<-`, from the `out` of an edge to its `in`.
+    In,
+    /// `<->`, either way.
+    Both,
+}
+
+/// A step of a graph traversal, e.g. the `->purchased` of `->purchased->product`.
+#[derive(Clone, Debug)]
+pub(crate) struct Step {
+    pub(crate) direction: Direction,
+    /// The token index of the arrow, relative to the statement.
+    pub(crate) arrow: usize,
+    /// The table stepped to, or `None` for `?` and steps that name no table yet.
+    pub(crate) table: Option<TableReference>,
+}
+
+/// A graph traversal such as `->purchased->product`, whose steps alternate between edges and the
+/// tables at their other end.
+#[derive(Clone, Debug)]
+pub(crate) struct Traversal {
+    /// The table the traversal starts from: that of a record id such as `user:tobie`, or the
+    /// single target of the statement for a traversal on its own, e.g. in `SELECT ->purchased
+    /// FROM user`.
+    pub(crate) start: Option<String>,
+    pub(crate) steps: Vec<Step>,
+}
+
+/// Keywords after which a traversal starts from the records of the statement.
+const TRAVERSAL_KEYWORDS: &[&str] =
+    &["SELECT", "WHERE", "AND", "OR", "NOT", "VALUE", "RETURN", "BY", "THEN", "ELSE", "IF"];
+
+/// Returns the graph traversals of a statement, excluding those of its nested statements.
+pub(crate) fn traversals(statement: &[crate::lexer::Token<'_>]) -> Vec<Traversal> {
+    if matches!(
+        kind(statement),
+        StatementKind::Define | StatementKind::Remove | StatementKind::Relate
+    ) {
+        return Vec::new();
+    }
+    let targets = table_references(statement);
+    let target = match targets.as_slice() {
+        [target] => Some(target.name.clone()),
+        _ => None,
+    };
+
+    let mut traversals = Vec::new();
+    let mut index = 0;
+    while index < statement.len() {
+        let token = &statement[index];
+        let is_nested = token.is_punct("{")
+            || (token.is_punct("(")
+                && statement.get(index + 1).is_some_and(|next| statement_kind(&[*next]).is_some()));
+        if is_nested {
+            index = crate::lexer::matching_bracket(statement, index).unwrap_or(statement.len()) + 1;
+            continue;
+        }
+        if direction(token).is_none() {
+            index += 1;
+            continue;
+        }
+        let start = match &statement[..index] {
+            [.., table, colon, id] if colon.is_punct(":") && is_record_key(id) => {
+                Some(table.ident().to_string())
+            }
+            [] => target.clone(),
+            [.., previous] => {
+                let is_standalone = (previous.kind == crate::lexer::TokenKind::Punct
+                    && !matches!(previous.text, ")" | "]" | "}"))
+                    || TRAVERSAL_KEYWORDS.iter().any(|keyword| previous.is_keyword(keyword));
+                if is_standalone {
+                    target.clone()
+                } else {
+                    None
+                }
+            }
+        };
+        let (steps, end) = steps(statement, index);
+        traversals.push(Traversal { start, steps });
+        index = end;
+    }
+    traversals
+}
+
+fn direction(token: &crate::lexer::Token<'_>) -> Option<Direction> {
+    match token.text {
+        "->" if token.kind == crate::lexer::TokenKind::Punct => Some(Direction::Out),
+        "<-" if token.kind == crate::lexer::TokenKind::Punct => Some(Direction::In),
+        "<->" if token.kind == crate::lexer::TokenKind::Punct => Some(Direction::Both),
+        _ => None,
+    }
+}
+
+fn is_record_key(token: &crate::lexer::Token<'_>) -> bool {
+    matches!(token.kind, crate::lexer::TokenKind::Ident | crate::lexer::TokenKind::Number)
+}
+
+/// Returns the steps of the traversal whose first arrow is at `index`, and the index after them.
+fn steps(statement: &[crate::lexer::Token<'_>], mut index: usize) -> (Vec<Step>, usize) {
+    let mut steps = Vec::new();
+    while let Some(direction) = statement.get(index).and_then(direction) {
+        let arrow = index;
+        index += 1;
+        let mut table = None;
+        match statement.get(index) {
+            // A step with a condition or alias, e.g. `->(purchased WHERE quantity > 1)`.
+            Some(open) if open.is_punct("(") => {
+                table = statement.get(index + 1).and_then(step_table);
+                index = crate::lexer::matching_bracket(statement, index)
+                    .map_or(statement.len(), |close| close + 1);
+            }
+            Some(token) if token.is_punct("?") => index += 1,
+            Some(token) => {
+                table = step_table(token);
+                if table.is_some() {
+                    index += 1;
+                }
+            }
+            None => {}
+        }
+        steps.push(Step { direction, arrow, table });
+    }
+    (steps, index)
+}
+
+fn step_table(token: &crate::lexer::Token<'_>) -> Option<TableReference> {
+    let is_keyword = CLAUSE_KEYWORDS.iter().chain(&["AS"]).any(|keyword| token.is_keyword(keyword));
+    (token.kind == crate::lexer::TokenKind::Ident && !is_keyword)
+        .then(|| TableReference { name: token.ident().to_string(), span: token.span() })
+}
+
+/// Returns the field assignments of a statement's `SET` clause and `CONTENT`, `MERGE` or
+/// `REPLACE` object.
+pub(crate) fn assignments(statement: &[crate::lexer::Token<'_>]) -> Vec<Assignment> {
+    let offset = statement.len() - body(statement).len();
+    let statement = body(statement);
+    let clauses = clauses(statement);
+    let mut assignments = Vec::new();
+
+    if let Some(set) = clause(&clauses, "SET") {
+        let tokens = &statement[set.clone()];
+        for part in crate::lexer::split_top_level(tokens, ",") {
+            let start = offset + set.start + part.start;
+            let part = &tokens[part];
+            let Some(operator) = part.iter().position(|token| {
+                token.kind == crate::lexer::TokenKind::Punct
+                    && matches!(token.text, "=" | "+=" | "-=" | "+?=")
+            }) else {
+                continue;
+            };
+            let Some((field, field_span)) = field_path(&part[..operator]) else {
+                continue;
+            };
+            assignments.push(Assignment {
+                field,
+                field_span,
+                operator: part[operator].text.to_string(),
+                value: start + operator + 1..start + part.len(),
+            });
+        }
+    }
+
+    for keyword in ["CONTENT", "MERGE", "REPLACE"] {
+        if let Some(range) = clause(&clauses, keyword) {
+            if statement.get(range.start).is_some_and(|token| token.is_punct("{")) {
+                assignments.extend(object_assignments(statement, range.start, offset));
+            }
+        }
+    }
+
+    if statement_kind(statement) == Some(StatementKind::Insert) {
+        if let Some(into) = statement.iter().position(|token| token.is_keyword("INTO")) {
+            let mut index = into + 2;
+            if statement.get(index).is_some_and(|token| token.is_punct("[")) {
+                index += 1;
+            }
+            while statement.get(index).is_some_and(|token| token.is_punct("{")) {
+                assignments.extend(object_assignments(statement, index, offset));
+                let Some(close) = crate::lexer::matching_bracket(statement, index) else {
+                    break;
+                };
+                index = close + 1;
+                if statement.get(index).is_some_and(|token| token.is_punct(",")) {
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    assignments
+}
+
+/// Returns the `key: value` pairs of the object literal opened at `open`.
+fn object_assignments(
+    statement: &[crate::lexer::Token<'_>],
+    open: usize,
+    offset: usize,
+) -> Vec<Assignment> {
+    let Some(close) = crate::lexer::matching_bracket(statement, open) else {
+        return Vec::new();
+    };
+    let inner = &statement[open + 1..close];
+    let mut assignments = Vec::new();
+    for part in crate::lexer::split_top_level(inner, ",") {
+        let start = offset + open + 1 + part.start;
+        let tokens = &inner[part];
+        let [key, colon, ..] = tokens else {
+            continue;
+        };
+        if !colon.is_punct(":") {
+            continue;
+        }
+        let field = match key.kind {
+            crate::lexer::TokenKind::Ident => key.ident().to_string(),
+            crate::lexer::TokenKind::String => key.text.trim_matches(['\'', '"']).to_string(),
+            _ => continue,
+        };
+        assignments.push(Assignment {
+            field,
+            field_span: key.span(),
+            operator: String::from(":"),
+            value: start + 2..start + tokens.len(),
+        });
+    }
+    assignments
+}
+
+/// Parses a field path such as `address.city` or `tags[0]`, returning it with its span.
+pub(crate) fn field_path(
+    tokens: &[crate::lexer::Token<'_>],
+) -> Option<(String, std::ops::Range<usize>)> {
+    let first = tokens.first()?;
+    if first.kind != crate::lexer::TokenKind::Ident {
+        return None;
+    }
+    let mut path = first.ident().to_string();
+    let mut index = 1;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        if token.is_punct(".") {
+            let next = tokens.get(index + 1)?;
+            path.push('.');
+            path.push_str(if next.is_punct("*") { "*" } else { next.ident() });
+            index += 2;
+        } else if token.is_punct("[") {
+            let close = crate::lexer::matching_bracket(tokens, index)?;
+            path.push_str("[*]");
+            index = close + 1;
+        } else {
+            return None;
+        }
+    }
+    Some((path, first.start..tokens[tokens.len() - 1].end))
+}
+
+/// Returns references to top-level fields in the projections, conditions and ordering of a
+/// `SELECT`, the `SET`/`UNSET` clauses of writes, and the columns of an `INSERT`.
+pub(crate) fn field_references(statement: &[crate::lexer::Token<'_>]) -> Vec<FieldReference> {
+    let statement = body(statement);
+    let clauses = clauses(statement);
+    let mut references = Vec::new();
+    let reference = |token: &crate::lexer::Token<'_>| FieldReference {
+        name: token.ident().to_string(),
+        span: token.span(),
+    };
+
+    let kind = statement_kind(statement);
+    let mut aliases = std::collections::HashSet::new();
+    if kind == Some(StatementKind::Select) {
+        if let Some((_, projections)) = clauses.first() {
+            let mut start = projections.start + 1;
+            if statement.get(start).is_some_and(|token| token.is_keyword("VALUE")) {
+                start += 1;
+            }
+            let tokens = &statement[start.min(projections.end)..projections.end];
+            for part in crate::lexer::split_top_level(tokens, ",") {
+                let part = &tokens[part];
+                if let Some(alias) = part.iter().position(|token| token.is_keyword("AS")) {
+                    if let Some(name) = part.get(alias + 1) {
+                        aliases.insert(name.ident().to_ascii_lowercase());
+                    }
+                }
+                let Some(first) = part.first() else {
+                    continue;
+                };
+                let is_field = match part.get(1) {
+                    None => true,
+                    Some(next) => next.is_punct(".") || next.is_punct("[") || next.is_keyword("AS"),
+                };
+                if is_field && is_field_name(first) {
+                    references.push(reference(first));
+                }
+            }
+        }
+    }
+
+    for assignment in assignments(statement).iter().filter(|a| a.operator != ":") {
+        references.extend(first_segment(statement, assignment));
+    }
+
+    for keyword in ["UNSET", "GROUP", "ORDER", "SPLIT"] {
+        let Some(range) = clause(&clauses, keyword) else {
+            continue;
+        };
+        let mut tokens = &statement[range.clone()];
+        if tokens.first().is_some_and(|token| token.is_keyword("BY") || token.is_keyword("ON")) {
+            tokens = &tokens[1..];
+        }
+        for part in crate::lexer::split_top_level(tokens, ",") {
+            let part = &tokens[part];
+            let Some(first) = part.first() else {
+                continue;
+            };
+            if is_field_name(first) && !aliases.contains(&first.ident().to_ascii_lowercase()) {
+                let is_path = match part.get(1) {
+                    None => true,
+                    Some(next) => {
+                        next.is_punct(".")
+                            || next.is_punct("[")
+                            || ["ASC", "DESC", "COLLATE", "NUMERIC"]
+                                .iter()
+                                .any(|keyword| next.is_keyword(keyword))
+                    }
+                };
+                if is_path {
+                    references.push(reference(first));
+                }
+            }
+        }
+    }
+
+    if let Some(condition) = clause(&clauses, "WHERE") {
+        let tokens = &statement[condition.clone()];
+        for (index, token) in tokens.iter().enumerate() {
+            let follows_connective = index == 0
+                || ["AND", "OR", "NOT", "&&", "||", "("].iter().any(|word| {
+                    tokens[index - 1].is_keyword(word) || tokens[index - 1].is_punct(word)
+                });
+            let precedes_operator = tokens.get(index + 1).is_some_and(|next| {
+                (next.kind == crate::lexer::TokenKind::Punct
+                    && !matches!(next.text, "(" | "::" | ":" | "," | "->" | "<-" | "<->"))
+                    || ["CONTAINS", "CONTAINSNOT", "CONTAINSALL", "CONTAINSANY", "CONTAINSNONE"]
+                        .iter()
+                        .chain(
+                            ["INSIDE", "NOTINSIDE", "ALLINSIDE", "ANYINSIDE", "NONEINSIDE"].iter(),
+                        )
+                        .chain(["IN", "IS", "OUTSIDE", "INTERSECTS"].iter())
+                        .any(|keyword| next.is_keyword(keyword))
+            });
+            if follows_connective && precedes_operator && is_field_name(token) {
+                references.push(reference(token));
+            }
+        }
+    }
+
+    if kind == Some(StatementKind::Insert) {
+        if let Some(into) = statement.iter().position(|token| token.is_keyword("INTO")) {
+            let open = into + 2;
+            if statement.get(open).is_some_and(|token| token.is_punct("(")) {
+                if let Some(close) = crate::lexer::matching_bracket(statement, open) {
+                    references.extend(
+                        statement[open + 1..close]
+                            .iter()
+                            .filter(|token| is_field_name(token))
+                            .map(reference),
+                    );
+                }
+            }
+        }
+        for assignment in assignments(statement) {
+            references.extend(first_segment(statement, &assignment));
+        }
+    } else if clause(&clauses, "CONTENT").or(clause(&clauses, "MERGE")).is_some() {
+        for assignment in assignments(statement).into_iter().filter(|a| a.operator == ":") {
+            references.push(FieldReference { name: assignment.field, span: assignment.field_span });
+        }
+    }
+
+    references
+}
+
+/// Returns a reference to the top-level field an assignment writes to.
+fn first_segment(
+    statement: &[crate::lexer::Token<'_>],
+    assignment: &Assignment,
+) -> Option<FieldReference> {
+    let token = statement.iter().find(|token| token.start == assignment.field_span.start)?;
+    let name = match token.kind {
+        crate::lexer::TokenKind::String => token.text.trim_matches(['\'', '"']),
+        _ => token.ident(),
+    };
+    Some(FieldReference { name: name.to_string(), span: token.span() })
+}
+
+fn is_field_name(token: &crate::lexer::Token<'_>) -> bool {
+    token.kind == crate::lexer::TokenKind::Ident
+        && !VALUE_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword))
+}
+
+/// Returns the function calls in `tokens`, skipping the signatures of `DEFINE FUNCTION` and
+/// method calls such as `$name.len()`.
+pub(crate) fn calls(tokens: &[crate::lexer::Token<'_>]) -> Vec<Call> {
+    let mut calls = Vec::new();
+    for (open, token) in tokens.iter().enumerate() {
+        if !token.is_punct("(") || open == 0 {
+            continue;
+        }
+        let mut start = open - 1;
+        if tokens[start].kind != crate::lexer::TokenKind::Ident {
+            continue;
+        }
+        while start >= 2
+            && tokens[start - 1].is_punct("::")
+            && tokens[start - 2].kind == crate::lexer::TokenKind::Ident
+        {
+            start -= 2;
+        }
+        let before = start.checked_sub(1).map(|index| &tokens[index]);
+        if before.is_some_and(|token| token.is_keyword("FUNCTION") || token.is_punct(".")) {
+            continue;
+        }
+        let Some(close) = crate::lexer::matching_bracket(tokens, open) else {
+            continue;
+        };
+        calls.push(Call {
+            name: tokens[start..open].iter().map(|token| token.text).collect(),
+            span: tokens[start].start..tokens[open - 1].end,
+            open,
+            close,
+        });
+    }
+    calls
+}
+
+#[cfg(test)]
+mod tests {
+    fn tables(source: &str) -> Vec<String> {
+        let tokens = crate::lexer::tokenize(source);
+        super::table_references(&tokens).into_iter().map(|table| table.name).collect()
+    }
+
+    fn fields(source: &str) -> Vec<String> {
+        let tokens = crate::lexer::tokenize(source);
+        super::field_references(&tokens).into_iter().map(|field| field.name).collect()
+    }
+
+    #[test]
+    fn finds_the_tables_of_each_kind_of_statement() {
+        assert_eq!(tables("SELECT * FROM person, post:1, $table"), ["person", "post"]);
+        assert_eq!(tables("CREATE ONLY person:tobie SET name = 'Tobie'"), ["person"]);
+        assert_eq!(tables("UPDATE person WHERE age > 18"), ["person"]);
+        assert_eq!(tables("DELETE FROM person"), ["person"]);
+        assert_eq!(tables("DELETE person:1"), ["person"]);
+        assert_eq!(tables("INSERT INTO person { name: 'Tobie' }"), ["person"]);
+        assert_eq!(tables("RELATE person:1->likes->post:2"), ["likes"]);
+        assert_eq!(tables("LET $people = SELECT * FROM person"), ["person"]);
+        assert!(tables("SELECT * FROM (SELECT * FROM person)").is_empty());
+    }
+
+    #[test]
+    fn finds_the_fields_a_statement_references() {
+        assert_eq!(
+            fields("SELECT name, address.city AS city FROM person WHERE age > 18 ORDER BY city"),
+            ["name", "address", "age"]
+        );
+        assert_eq!(
+            fields("UPDATE person SET name = 'x', tags += 'y' UNSET email"),
+            ["name", "tags", "email"]
+        );
+        assert_eq!(fields("CREATE person CONTENT { name: 'x', 'age': 3 }"), ["name", "age"]);
+        assert_eq!(fields("INSERT INTO person (name, age) VALUES ('x', 3)"), ["name", "age"]);
+        assert!(fields("SELECT * FROM person WHERE true AND $x = NONE").is_empty());
+    }
+
+    #[test]
+    fn finds_assignments_with_their_values() {
+        let tokens = crate::lexer::tokenize("UPDATE person SET address.city = 'London', age += 1");
+        let assignments: Vec<(String, String, String)> = super::assignments(&tokens)
+            .into_iter()
+            .map(|assignment| {
+                let value = tokens[assignment.value].iter().map(|token| token.text).collect();
+                (assignment.field, assignment.operator, value)
+            })
+            .collect();
+        assert_eq!(
+            assignments,
+            [
+                (String::from("address.city"), String::from("="), String::from("'London'")),
+                (String::from("age"), String::from("+="), String::from("1")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_field_paths() {
+        let path =
+            |source| super::field_path(&crate::lexer::tokenize(source)).map(|(path, _)| path);
+        assert_eq!(path("address.city").as_deref(), Some("address.city"));
+        assert_eq!(path("tags[0].name").as_deref(), Some("tags[*].name"));
+        assert_eq!(path("emails.*").as_deref(), Some("emails.*"));
+        assert_eq!(path("a b"), None);
+    }
+
+    #[test]
+    fn nested_statements_follow_their_parent() {
+        let tokens = crate::lexer::tokenize("SELECT * FROM (SELECT * FROM person); CREATE post");
+        let kinds: Vec<super::StatementKind> = super::statements(&tokens)
+            .into_iter()
+            .map(|range| super::kind(&tokens[range]))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                super::StatementKind::Select,
+                super::StatementKind::Select,
+                super::StatementKind::Create
+            ]
+        );
+    }
+
+    /// The direction and table of each step of a traversal.
+    type Steps = Vec<(super::Direction, Option<String>)>;
+
+    /// Returns the start and the steps of every traversal of `source`.
+    fn traversals(source: &str) -> Vec<(Option<String>, Steps)> {
+        let tokens = crate::lexer::tokenize(source);
+        super::traversals(&tokens)
+            .into_iter()
+            .map(|traversal| {
+                let steps = traversal
+                    .steps
+                    .into_iter()
+                    .map(|step| (step.direction, step.table.map(|table| table.name)))
+                    .collect();
+                (traversal.start, steps)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_steps_of_traversals_in_each_direction() {
+        let person = || Some(String::from("person"));
+        let table = |name: &str| Some(String::from(name));
+        assert_eq!(
+            traversals(
+                "SELECT ->purchased->product, <-(knows WHERE since > 1)<-?, <->knows<->person \
+                 FROM person"
+            ),
+            [
+                (
+                    person(),
+                    vec![
+                        (super::Direction::Out, table("purchased")),
+                        (super::Direction::Out, table("product")),
+                    ]
+                ),
+                (
+                    person(),
+                    vec![(super::Direction::In, table("knows")), (super::Direction::In, None)]
+                ),
+                (
+                    person(),
+                    vec![
+                        (super::Direction::Both, table("knows")),
+                        (super::Direction::Both, person()),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(
+            traversals("RETURN product:1<-purchased<-person"),
+            [(
+                table("product"),
+                vec![(super::Direction::In, table("purchased")), (super::Direction::In, person())]
+            )]
+        );
+        assert!(traversals("RELATE person:1->purchased->product:1").is_empty());
+    }
+
+    #[test]
+    fn finds_calls_but_not_signatures_or_methods() {
+        let tokens = crate::lexer::tokenize(
+            "DEFINE FUNCTION fn::greet($name: string) { \
+             RETURN string::concat('Hi ', $name.trim()) }",
+        );
+        let calls: Vec<String> = super::calls(&tokens).into_iter().map(|call| call.name).collect();
+        assert_eq!(calls, ["string::concat"]);
+    }
+}
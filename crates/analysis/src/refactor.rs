@@ -0,0 +1,755 @@
+//! Refactoring code actions: extracting expressions and statements, and inlining `LET` bindings.
+
+/// Returns the refactorings available for the selection `range` of `source`, the text of `uri`,
+/// whose syntax tree is `tree`.
+pub(crate) fn refactorings(
+    uri: &url::Url,
+    source: &str,
+    tree: &tree_sitter::Tree,
+    schema: &crate::schema::Schema,
+    range: crate::TextRange,
+) -> Vec<crate::SourceChange> {
+    let tokens = crate::lexer::tokens(source, tree);
+    let refactoring = Refactoring { source, tree, tokens: &tokens };
+
+    let mut edits = Vec::new();
+    if let Some(selection) = refactoring.selection(range.start, range.end) {
+        edits.extend(refactoring.extract_to_let(selection.clone()));
+        edits.extend(refactoring.extract_to_function(selection, schema));
+    }
+    edits.extend(refactoring.inline_let(range.start));
+
+    edits
+        .into_iter()
+        .map(|(title, kind, edits)| {
+            let edits = edits
+                .into_iter()
+                .map(|(span, text)| crate::TextEdit { range: span, new_text: text })
+                .collect();
+            crate::SourceChange::new(title, kind, uri, edits)
+        })
+        .collect()
+}
+
+/// The clauses whose expressions are evaluated once per record, against that record, e.g. the
+/// condition of a `WHERE`. The projections of a `SELECT` are too.
+const PER_RECORD_CLAUSES: &[&str] = &[
+    "WHERE", "SPLIT", "GROUP", "ORDER", "FETCH", "OMIT", "SET", "UNSET", "CONTENT", "MERGE",
+    "PATCH", "REPLACE", "RETURN",
+];
+
+/// The parameters bound to the current record, or to its value before or after a change.
+const RECORD_PARAMS: &[&str] = &["this", "value", "before", "after", "input"];
+
+/// Keywords that are operators or values rather than field names in an expression.
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "AND",
+    "OR",
+    "NOT",
+    "IS",
+    "IN",
+    "CONTAINS",
+    "CONTAINSNOT",
+    "CONTAINSALL",
+    "CONTAINSANY",
+    "CONTAINSNONE",
+    "INSIDE",
+    "NOTINSIDE",
+    "ALLINSIDE",
+    "ANYINSIDE",
+    "NONEINSIDE",
+    "OUTSIDE",
+    "INTERSECTS",
+    "NONE",
+    "NULL",
+    "TRUE",
+    "FALSE",
+    "IF",
+    "THEN",
+    "ELSE",
+    "END",
+];
+
+/// A refactoring as its title, kind and the `(span, replacement)` edits to the document.
+type Edits = (String, crate::AssistKind, Vec<(std::ops::Range<usize>, String)>);
+
+/// The document being refactored, as its syntax tree and the tokens at the tree's leaves.
+struct Refactoring<'s, 't> {
+    source: &'s str,
+    tree: &'t tree_sitter::Tree,
+    tokens: &'t [crate::lexer::Token<'s>],
+}
+
+impl Refactoring<'_, '_> {
+    /// Returns the node of the tree starting at the token at `index`.
+    fn node(&self, index: usize) -> Option<tree_sitter::Node<'_>> {
+        let token = &self.tokens[index];
+        self.tree.root_node().descendant_for_byte_range(token.start, token.start)
+    }
+
+    /// Returns whether `offset` lies within a leaf of the tree rather than between two.
+    fn splits_leaf(&self, offset: usize) -> bool {
+        self.tree.root_node().descendant_for_byte_range(offset, offset).is_some_and(|node| {
+            node.child_count() == 0 && node.start_byte() < offset && offset < node.end_byte()
+        })
+    }
+
+    /// Returns the indices of the tokens covered by the byte range `start..end`, provided that it
+    /// neither splits a leaf of the tree or a token nor is empty.
+    fn selection(&self, start: usize, end: usize) -> Option<std::ops::Range<usize>> {
+        if start >= end || self.splits_leaf(start) || self.splits_leaf(end) {
+            return None;
+        }
+        if self.tokens.iter().any(|token| {
+            (token.start < start && start < token.end) || (token.start < end && end < token.end)
+        }) {
+            return None;
+        }
+        let first = self.tokens.iter().position(|token| token.start >= start)?;
+        let last = self.tokens.iter().rposition(|token| token.end <= end)?;
+        (first <= last).then_some(first..last + 1)
+    }
+
+    /// Returns the innermost statement containing the tokens of `selection`.
+    fn enclosing_statement(
+        &self,
+        selection: &std::ops::Range<usize>,
+    ) -> Option<std::ops::Range<usize>> {
+        crate::query::statements(self.tokens)
+            .into_iter()
+            .filter(|statement| {
+                statement.start <= selection.start && selection.end <= statement.end
+            })
+            .min_by_key(|statement| statement.len())
+    }
+
+    /// Returns a parameter name based on `base` that is not used anywhere in the document.
+    fn unique_param(&self, base: &str) -> String {
+        let is_used = |name: &str| {
+            crate::scope::BUILTIN_PARAMS.contains(&name)
+                || self.tokens.iter().any(|token| {
+                    token.kind == crate::lexer::TokenKind::Param && token.ident() == name
+                })
+        };
+        let mut name = base.to_string();
+        let mut suffix = 1;
+        while is_used(&name) {
+            suffix += 1;
+            name = format!("{}{}", base, suffix);
+        }
+        name
+    }
+
+    /// Returns the whitespace indenting the line of the token at `index`, if the token is the
+    /// first on its line.
+    fn indentation(&self, index: usize) -> Option<&str> {
+        let start = self.tokens[index].start;
+        let indentation = &self.source[start - self.column(index)..start];
+        indentation.chars().all(char::is_whitespace).then_some(indentation)
+    }
+
+    /// Returns whether the tokens of `selection` lie in a clause of `statement` that is evaluated
+    /// once per record.
+    fn is_per_record(
+        &self,
+        statement: &std::ops::Range<usize>,
+        selection: &std::ops::Range<usize>,
+    ) -> bool {
+        let tokens = &self.tokens[statement.clone()];
+        let start = selection.start - statement.start;
+        let clauses = crate::query::clauses(tokens);
+        let Some((keyword, _)) = clauses.iter().find(|(_, range)| range.contains(&start)) else {
+            return false;
+        };
+        if keyword.is_empty() {
+            return crate::query::kind(tokens) == crate::query::StatementKind::Select;
+        }
+        PER_RECORD_CLAUSES.contains(&keyword.as_str())
+    }
+
+    fn text(&self, tokens: std::ops::Range<usize>) -> &str {
+        &self.source[self.tokens[tokens.start].start..self.tokens[tokens.end - 1].end]
+    }
+
+    /// Extracts a selected expression into a `LET` binding before its statement.
+    fn extract_to_let(&self, selection: std::ops::Range<usize>) -> Option<Edits> {
+        let selected = &self.tokens[selection.clone()];
+        if !is_expression(selected) {
+            return None;
+        }
+        let statement = self.enclosing_statement(&selection)?;
+        if statement == selection
+            || crate::query::kind(&self.tokens[statement.clone()])
+                == crate::query::StatementKind::Define
+        {
+            return None;
+        }
+        // The left-hand side of an assignment or object key is not an expression.
+        let next = self.tokens.get(selection.end);
+        if next.is_some_and(|next| {
+            matches!(next.text, "=" | "+=" | "-=" | "+?=" | ":")
+                && next.kind == crate::lexer::TokenKind::Punct
+        }) {
+            return None;
+        }
+        // Parameters bound inside the statement, e.g. closure arguments, are not in scope before
+        // it.
+        let bound = bound_params(&self.tokens[statement.start..selection.start]);
+        if selected.iter().any(|token| {
+            token.kind == crate::lexer::TokenKind::Param && bound.contains(token.ident())
+        }) {
+            return None;
+        }
+        // Fields and `$this` are evaluated against the current record, which a `LET` before the
+        // statement has none of, and `$parent` against that of an enclosing statement.
+        let is_parent = |token: &crate::lexer::Token<'_>| {
+            token.kind == crate::lexer::TokenKind::Param && token.ident() == "parent"
+        };
+        if selected.iter().any(is_parent)
+            || (self.is_per_record(&statement, &selection) && depends_on_record(selected))
+        {
+            return None;
+        }
+
+        let name = self.unique_param("extracted");
+        let separator = match self.indentation(statement.start) {
+            Some(indentation) => format!("\n{}", indentation),
+            None => String::from(" "),
+        };
+        let statement_start = self.tokens[statement.start].start;
+        let span = self.tokens[selection.start].start..self.tokens[selection.end - 1].end;
+        Some((
+            format!("Extract to `LET ${}`", name),
+            crate::AssistKind::Extract,
+            vec![
+                (
+                    statement_start..statement_start,
+                    format!("LET ${} = {};{}", name, self.text(selection), separator),
+                ),
+                (span, format!("${}", name)),
+            ],
+        ))
+    }
+
+    /// Extracts selected statements into a `DEFINE FUNCTION`, passing their free parameters as
+    /// typed arguments.
+    fn extract_to_function(
+        &self,
+        mut selection: std::ops::Range<usize>,
+        schema: &crate::schema::Schema,
+    ) -> Option<Edits> {
+        let terminated = self.tokens[selection.end - 1].is_punct(";");
+        if terminated {
+            selection.end -= 1;
+        }
+        let selected = &self.tokens[selection.clone()];
+        if selected.is_empty() || !is_balanced(selected) {
+            return None;
+        }
+        let statements: Vec<std::ops::Range<usize>> = crate::lexer::split_statements(selected)
+            .into_iter()
+            .map(|range| selection.start + range.start..selection.start + range.end)
+            .collect();
+        let all = crate::query::statements(self.tokens);
+        let is_statement = |range: &std::ops::Range<usize>| all.contains(range);
+        if statements.first()?.start != selection.start
+            || statements.last()?.end != selection.end
+            || !statements.iter().all(is_statement)
+            || statements.iter().any(|statement| {
+                crate::query::kind(&self.tokens[statement.clone()])
+                    == crate::query::StatementKind::Define
+            })
+        {
+            return None;
+        }
+
+        // `LET` bindings made by the selection must not be used after it.
+        let bound = bound_params(selected);
+        let escapes = self.tokens[selection.end..].iter().any(|token| {
+            token.kind == crate::lexer::TokenKind::Param && bound.contains(token.ident())
+        });
+        if escapes {
+            return None;
+        }
+
+        let mut arguments: Vec<&str> = Vec::new();
+        for token in selected {
+            let name = token.ident();
+            if token.kind == crate::lexer::TokenKind::Param
+                && !bound.contains(name)
+                && !crate::scope::BUILTIN_PARAMS.contains(&name)
+                && !schema.params.contains_key(name)
+                && !arguments.contains(&name)
+            {
+                arguments.push(name);
+            }
+        }
+        let parameters: Vec<String> = arguments
+            .iter()
+            .map(|name| {
+                let kind = self.param_kind(name, selection.start, schema);
+                format!(
+                    "${}: {}",
+                    name,
+                    kind.map_or(crate::types::Kind::Any, |kind| kind.widened())
+                )
+            })
+            .collect();
+
+        let mut name = String::from("fn::extracted");
+        let mut suffix = 1;
+        while schema.functions.contains_key(&name) || self.source.contains(&format!("{}(", name)) {
+            suffix += 1;
+            name = format!("fn::extracted{}", suffix);
+        }
+
+        // Insert the function before the top-level statement containing the selection.
+        let top_level =
+            crate::lexer::split_statements(self.tokens).into_iter().find(|statement| {
+                statement.start <= selection.start && selection.end <= statement.end
+            })?;
+        let insert_at = self.tokens[top_level.start].start;
+        let body = indent(self.text(selection.clone()), self.column(selection.start));
+        let definition =
+            format!("DEFINE FUNCTION {}({}) {{\n{};\n}};\n\n", name, parameters.join(", "), body);
+        let call = format!(
+            "{}({}){}",
+            name,
+            arguments.iter().map(|name| format!("${}", name)).collect::<Vec<_>>().join(", "),
+            if terminated { ";" } else { "" }
+        );
+        let end = if terminated { selection.end } else { selection.end - 1 };
+        let span = self.tokens[selection.start].start..self.tokens[end].end;
+        Some((
+            format!("Extract to `DEFINE FUNCTION {}`", name),
+            crate::AssistKind::Extract,
+            vec![(insert_at..insert_at, definition), (span, call)],
+        ))
+    }
+
+    /// Returns the column of the token at `index`, in bytes, from the position of its node.
+    fn column(&self, index: usize) -> usize {
+        match self.node(index) {
+            Some(node) if node.start_byte() == self.tokens[index].start => {
+                node.start_position().column
+            }
+            _ => {
+                let start = self.tokens[index].start;
+                start - self.source[..start].rfind('\n').map_or(0, |newline| newline + 1)
+            }
+        }
+    }
+
+    /// Returns the kind of `$name` at the token `before`, from the last `LET` binding it or from
+    /// the typed argument of an enclosing `DEFINE FUNCTION`.
+    fn param_kind(
+        &self,
+        name: &str,
+        before: usize,
+        schema: &crate::schema::Schema,
+    ) -> Option<crate::types::Kind> {
+        let tokens = &self.tokens[..before];
+        let binding = (0..tokens.len()).rev().find(|&index| {
+            let token = &tokens[index];
+            token.kind == crate::lexer::TokenKind::Param
+                && token.ident() == name
+                && tokens
+                    .get(index + 1)
+                    .is_some_and(|next| next.is_punct(":") || next.is_punct("="))
+        })?;
+        if tokens[binding + 1].is_punct(":") {
+            let end = tokens[binding + 2..]
+                .iter()
+                .position(|token| token.is_punct(",") || token.is_punct(")") || token.is_punct("="))
+                .map_or(tokens.len(), |end| binding + 2 + end);
+            let text: Vec<&str> = tokens[binding + 2..end].iter().map(|token| token.text).collect();
+            return crate::types::Kind::parse(&text.join(" "));
+        }
+        if binding == 0 || !tokens[binding - 1].is_keyword("LET") {
+            return None;
+        }
+        let value = &self.tokens[binding + 2..];
+        let end = crate::lexer::split_statements(value).first()?.end;
+        crate::types::infer(&value[..end], schema)
+    }
+
+    /// Inlines the single use of the `LET` binding at or used at `offset`.
+    fn inline_let(&self, offset: usize) -> Option<Edits> {
+        let at =
+            self.tokens.iter().position(|token| token.start <= offset && offset <= token.end)?;
+        let statements = crate::query::statements(self.tokens);
+        let is_let = |statement: &std::ops::Range<usize>| {
+            let tokens = &self.tokens[statement.clone()];
+            tokens.len() > 3
+                && tokens[0].is_keyword("LET")
+                && tokens[1].kind == crate::lexer::TokenKind::Param
+                && tokens[2].is_punct("=")
+        };
+        // Either the cursor is on `LET $name` or on a use of `$name`.
+        let statement = match statements.iter().find(|statement| {
+            is_let(statement) && (statement.start..statement.start + 2).contains(&at)
+        }) {
+            Some(statement) => statement.clone(),
+            None => {
+                let param = &self.tokens[at];
+                if param.kind != crate::lexer::TokenKind::Param {
+                    return None;
+                }
+                statements
+                    .iter()
+                    .filter(|statement| {
+                        is_let(statement)
+                            && statement.end <= at
+                            && self.tokens[statement.start + 1].ident() == param.ident()
+                    })
+                    .max_by_key(|statement| statement.start)?
+                    .clone()
+            }
+        };
+        let name = self.tokens[statement.start + 1].ident();
+        let value = statement.start + 3..statement.end;
+        let value_tokens = &self.tokens[value.clone()];
+        if !matches!(
+            crate::query::kind(value_tokens),
+            crate::query::StatementKind::Select | crate::query::StatementKind::Other
+        ) {
+            // Moving a write changes when, and how often, it runs.
+            return None;
+        }
+
+        // Find the uses up to the end of the enclosing block or the next binding of `$name`.
+        let mut uses = Vec::new();
+        let mut depth = 0usize;
+        let mut index = statement.end;
+        while let Some(token) = self.tokens.get(index) {
+            match token.text {
+                "(" | "[" | "{" if token.kind == crate::lexer::TokenKind::Punct => depth += 1,
+                ")" | "]" | "}" if token.kind == crate::lexer::TokenKind::Punct => {
+                    let Some(outer) = depth.checked_sub(1) else {
+                        break;
+                    };
+                    depth = outer;
+                }
+                _ => {}
+            }
+            if token.kind == crate::lexer::TokenKind::Param && token.ident() == name {
+                let rebinds = index > 0
+                    && (self.tokens[index - 1].is_keyword("LET")
+                        || self.tokens[index - 1].is_keyword("FOR"));
+                if rebinds {
+                    break;
+                }
+                uses.push(index);
+            }
+            index += 1;
+        }
+        let [usage] = uses.as_slice() else {
+            return None;
+        };
+
+        // The parameters of the value must still mean the same thing at the use.
+        let rebound = bound_params(&self.tokens[statement.end..*usage]);
+        if value_tokens.iter().any(|token| {
+            token.kind == crate::lexer::TokenKind::Param && rebound.contains(token.ident())
+        }) {
+            return None;
+        }
+
+        let text = self.text(value.clone());
+        let is_atomic = value_tokens.len() == 1
+            || crate::lexer::matching_bracket(value_tokens, 0) == Some(value_tokens.len() - 1)
+            || crate::query::calls(value_tokens).first().is_some_and(|call| {
+                call.span.start == value_tokens[0].start && call.close == value_tokens.len() - 1
+            });
+        let replacement = if is_atomic { text.to_string() } else { format!("({})", text) };
+
+        // Remove the statement with its `;` and the rest of its line when that is blank.
+        let start = self.tokens[statement.start].start;
+        let mut end = self.tokens[statement.end - 1].end;
+        if self.tokens.get(statement.end).is_some_and(|token| token.is_punct(";")) {
+            end = self.tokens[statement.end].end;
+        }
+        let rest = &self.source[end..];
+        let line_end = rest.find('\n').map_or(rest.len(), |newline| newline + 1);
+        let indentation = self.indentation(statement.start);
+        let start = match indentation {
+            Some(indentation) if rest[..line_end].trim().is_empty() => {
+                end += line_end;
+                start - indentation.len()
+            }
+            _ => start,
+        };
+        Some((
+            format!("Inline `${}`", name),
+            crate::AssistKind::Inline,
+            vec![(start..end, String::new()), (self.tokens[*usage].span(), replacement)],
+        ))
+    }
+}
+
+/// Returns the names of the parameters bound in `tokens` by `LET`, `FOR` and closure arguments.
+fn bound_params<'a>(tokens: &[crate::lexer::Token<'a>]) -> std::collections::HashSet<&'a str> {
+    let mut bound = std::collections::HashSet::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind != crate::lexer::TokenKind::Param || index == 0 {
+            continue;
+        }
+        let previous = &tokens[index - 1];
+        let next = tokens.get(index + 1);
+        if previous.is_keyword("LET")
+            || previous.is_keyword("FOR")
+            || previous.is_punct("|")
+            || next.is_some_and(|next| next.is_punct("|"))
+        {
+            bound.insert(token.ident());
+        }
+    }
+    bound
+}
+
+/// Returns whether the expression of `tokens` reads the current record: a bare field name, a record
+/// parameter such as `$this`, or a graph traversal that starts at the record, e.g. `->likes`.
+/// Subqueries select records of their own.
+fn depends_on_record(tokens: &[crate::lexer::Token<'_>]) -> bool {
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        let previous = index.checked_sub(1).map(|index| &tokens[index]);
+        let next = tokens.get(index + 1);
+        let is_subquery = token.is_punct("(")
+            && next.is_some_and(|next| {
+                crate::query::kind(std::slice::from_ref(next)) != crate::query::StatementKind::Other
+            });
+        let skip_to = if is_subquery {
+            crate::lexer::matching_bracket(tokens, index)
+        } else {
+            crate::types::cast_end(tokens, index)
+        };
+        if let Some(close) = skip_to {
+            index = close + 1;
+            continue;
+        }
+        let depends = match token.kind {
+            crate::lexer::TokenKind::Param => RECORD_PARAMS.contains(&token.ident()),
+            crate::lexer::TokenKind::Punct if matches!(token.text, "->" | "<-" | "<->") => {
+                match previous {
+                    None => true,
+                    Some(previous) => {
+                        previous.kind == crate::lexer::TokenKind::Punct
+                            && !matches!(previous.text, ")" | "]" | "}" | "?")
+                    }
+                }
+            }
+            crate::lexer::TokenKind::Ident => {
+                let is_name = previous.is_some_and(|previous| {
+                    matches!(previous.text, "." | "::" | "->" | "<-" | "<->")
+                        && previous.kind == crate::lexer::TokenKind::Punct
+                });
+                // The key of a record id, e.g. the `tobie` of `person:tobie`.
+                let is_record_key = previous.is_some_and(|colon| {
+                    index >= 2
+                        && colon.is_punct(":")
+                        && colon.start == tokens[index - 2].end
+                        && colon.end == token.start
+                });
+                let is_call_or_key = next.is_some_and(|next| {
+                    next.kind == crate::lexer::TokenKind::Punct
+                        && matches!(next.text, "(" | "::" | ":")
+                });
+                !is_name
+                    && !is_record_key
+                    && !is_call_or_key
+                    && !EXPRESSION_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword))
+            }
+            _ => false,
+        };
+        if depends {
+            return true;
+        }
+        index += 1;
+    }
+    false
+}
+
+/// Returns whether every bracket in `tokens` is closed within them.
+fn is_balanced(tokens: &[crate::lexer::Token<'_>]) -> bool {
+    let mut depth = 0usize;
+    for token in tokens.iter().filter(|token| token.kind == crate::lexer::TokenKind::Punct) {
+        match token.text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => match depth.checked_sub(1) {
+                Some(outer) => depth = outer,
+                None => return false,
+            },
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+/// Returns whether `tokens` looks like a complete expression rather than part of one.
+fn is_expression(tokens: &[crate::lexer::Token<'_>]) -> bool {
+    let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+        return false;
+    };
+    let starts = first.kind != crate::lexer::TokenKind::Punct
+        || matches!(first.text, "(" | "[" | "{" | "<" | "-" | "!");
+    let ends = last.kind != crate::lexer::TokenKind::Punct || matches!(last.text, ")" | "]" | "}");
+    let separated = crate::lexer::split_statements(tokens).len() > 1
+        || crate::lexer::split_top_level(tokens, ",").len() > 1;
+    starts && ends && !separated && is_balanced(tokens)
+}
+
+/// Re-indents `text`, which starts at `column`, by four spaces.
+fn indent(text: &str, column: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let common = lines
+        .iter()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .chain([column])
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let line =
+                if index == 0 { line } else { line.get(common..).unwrap_or(line.trim_start()) };
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("    {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    /// Returns the document after each refactoring of the selection between the `|` markers of
+    /// `marked`, or at the single `|` marker, keyed by title.
+    fn refactor(schema: &str, marked: &str) -> Vec<(String, String)> {
+        let start = marked.find('|').unwrap();
+        let end = marked.rfind('|').unwrap();
+        let source = marked.replace('|', "");
+        let range = if start == end { start..start } else { start..end - 1 };
+        let schema_uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        let schema = crate::schema::Schema::from_changes(
+            &crate::schema::changes(&schema_uri, schema),
+            crate::config::Dialect::V2,
+        );
+        let uri = url::Url::parse("file:///project/query.surql").unwrap();
+        let tree = crate::parser::parse(&source, None).unwrap();
+        super::refactorings(&uri, &source, &tree, &schema, range)
+            .into_iter()
+            .map(|change| {
+                let edits = &change.edits[&uri];
+                (change.label, crate::TextEdit::apply(&source, edits))
+            })
+            .collect()
+    }
+
+    fn extracted(marked: &str) -> Option<String> {
+        refactor("", marked)
+            .into_iter()
+            .find(|(title, _)| title.starts_with("Extract to `LET"))
+            .map(|(_, text)| text)
+    }
+
+    #[test]
+    fn extracts_an_expression_into_a_let_before_its_statement() {
+        assert_eq!(
+            extracted("SELECT * FROM person WHERE age > |18 + 3|;").as_deref(),
+            Some("LET $extracted = 18 + 3;\nSELECT * FROM person WHERE age > $extracted;")
+        );
+        assert_eq!(
+            extracted("RETURN 1; RETURN |2 * 3|;").as_deref(),
+            Some("RETURN 1; LET $extracted = 2 * 3; RETURN $extracted;")
+        );
+        assert_eq!(
+            extracted("LET $extracted = 1;\n    CREATE person SET born = |time::now()|;")
+                .as_deref(),
+            Some(
+                "LET $extracted = 1;\n    LET $extracted2 = time::now();\n    CREATE person SET \
+                 born = $extracted2;"
+            )
+        );
+    }
+
+    #[test]
+    fn does_not_extract_what_depends_on_the_current_record() {
+        for marked in [
+            "SELECT * FROM person WHERE |age > 18|;",
+            "SELECT |name.first| FROM person;",
+            "UPDATE person SET age = |age + 1|;",
+            "SELECT * FROM person ORDER BY |string::lowercase(name)|;",
+            "SELECT * FROM person WHERE |$this.age| > 18;",
+            "SELECT |->likes->post| FROM person;",
+            "SELECT * FROM person WHERE friends CONTAINS |$parent.id|;",
+        ] {
+            assert_eq!(extracted(marked), None, "{}", marked);
+        }
+    }
+
+    #[test]
+    fn extracts_record_independent_expressions_from_per_record_clauses() {
+        for marked in [
+            "SELECT * FROM person WHERE age > |<int> $min + 1|;",
+            "SELECT * FROM person WHERE id = |person:tobie|;",
+            "SELECT * FROM person WHERE id IN |(SELECT VALUE author FROM post)|;",
+            "UPDATE person SET tags = |{ kind: 'x', at: time::now() }|;",
+            "SELECT * FROM person WHERE friend = |person:1->knows->person|;",
+            "SELECT * FROM |person| LIMIT 1;",
+        ] {
+            assert!(extracted(marked).is_some(), "{}", marked);
+        }
+        assert!(extracted("SELECT * FROM person LIMIT |limit|;").is_some());
+    }
+
+    #[test]
+    fn does_not_extract_assignment_targets_or_closure_arguments() {
+        assert_eq!(extracted("UPDATE person:1 SET |age| = 3;"), None);
+        assert_eq!(extracted("RETURN [1].map(|$n| |$n * 2|);"), None);
+        assert_eq!(extracted("SELECT |*| FROM person;"), None);
+    }
+
+    #[test]
+    fn extracts_statements_into_a_function() {
+        let schema = "DEFINE TABLE person SCHEMAFULL; DEFINE FIELD age ON person TYPE int;";
+        let changes =
+            refactor(schema, "LET $min = 18;\n|SELECT * FROM person WHERE age > $min;|\nRETURN 1;");
+        let (title, text) = changes
+            .into_iter()
+            .find(|(title, _)| title.starts_with("Extract to `DEFINE FUNCTION"))
+            .unwrap();
+        assert_eq!(title, "Extract to `DEFINE FUNCTION fn::extracted`");
+        assert_eq!(
+            text,
+            "LET $min = 18;\nDEFINE FUNCTION fn::extracted($min: int) {\n    SELECT * FROM person \
+             WHERE age > $min;\n};\n\nfn::extracted($min);\nRETURN 1;"
+        );
+    }
+
+    #[test]
+    fn does_not_extract_bindings_that_are_used_later() {
+        let changes = refactor("", "|LET $a = 1;| RETURN $a;");
+        assert!(changes.iter().all(|(title, _)| !title.contains("FUNCTION")));
+    }
+
+    #[test]
+    fn inlines_a_let_used_once() {
+        let changes = refactor("", "LET $n = 1 + 2;\nRETURN |$n * 3;");
+        assert_eq!(changes, [(String::from("Inline `$n`"), String::from("RETURN (1 + 2) * 3;"))]);
+        let changes = refactor("", "|LET $now = time::now();\nRETURN $now;");
+        assert_eq!(changes[0].1, "RETURN time::now();");
+    }
+
+    #[test]
+    fn does_not_inline_writes_or_bindings_used_twice() {
+        assert!(refactor("", "|LET $p = CREATE person;\nRETURN $p;").is_empty());
+        assert!(refactor("", "|LET $n = 1;\nRETURN $n + $n;").is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! The statements and transactions of a document that can be run against a database.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunnableKind {
+    /// Runs a single statement.
+    Statement,
+    /// Runs a `SELECT` with an `EXPLAIN` clause, to show its query plan.
+    Explain,
+    /// Runs a `BEGIN` statement through its `COMMIT` or `CANCEL`.
+    Transaction,
+}
+
+/// Something that can be run, shown at `range`.
+#[derive(Clone, Debug)]
+pub struct Runnable {
+    pub range: crate::TextRange,
+    /// The statements to run, for use as a [`Selection::Range`].
+    pub statements: crate::TextRange,
+    pub kind: RunnableKind,
+}
+
+/// Which statements of a document to run.
+#[derive(Clone, Debug)]
+pub enum Selection {
+    /// Every statement.
+    All,
+    /// The statement under the offset, or the closest one before it.
+    Offset(usize),
+    /// The statements that start within the range.
+    Range(crate::TextRange),
+}
+
+/// The statements of a document to execute.
+#[derive(Clone, Debug)]
+pub struct Query {
+    pub text: String,
+    /// The statements that SurrealDB reports a result for, which excludes transaction statements.
+    pub ranges: Vec<crate::TextRange>,
+}
+
+/// Returns a runnable for every top-level statement of a document made of `tokens`, an explainable
+/// one for every `SELECT` and a transaction for every `BEGIN`.
+pub(crate) fn runnables(tokens: &[crate::lexer::Token<'_>]) -> Vec<Runnable> {
+    let statements = crate::lexer::split_statements(tokens);
+
+    let mut runnables = Vec::new();
+    for (index, statement) in statements.iter().enumerate() {
+        let statement = &tokens[statement.clone()];
+        let (Some(first), Some(last)) = (statement.first(), statement.last()) else {
+            continue;
+        };
+        let range = first.start..last.end;
+        if first.is_keyword("BEGIN") {
+            let Some(end) = statements[index + 1..].iter().find(|statement| {
+                let first = &tokens[statement.start];
+                first.is_keyword("COMMIT") || first.is_keyword("CANCEL")
+            }) else {
+                continue;
+            };
+            let statements = first.start..tokens[end.end - 1].end;
+            runnables.push(Runnable { range, statements, kind: RunnableKind::Transaction });
+            continue;
+        }
+        if is_transaction_statement(statement) {
+            continue;
+        }
+        let statements = range.clone();
+        runnables.push(Runnable {
+            range: range.clone(),
+            statements: statements.clone(),
+            kind: RunnableKind::Statement,
+        });
+        if is_explainable(statement) {
+            runnables.push(Runnable { range, statements, kind: RunnableKind::Explain });
+        }
+    }
+    runnables
+}
+
+/// Returns the statements of `source`, made of `tokens`, that `selection` selects, with an
+/// `EXPLAIN` clause when `explain` is set.
+pub(crate) fn query(
+    source: &str,
+    tokens: &[crate::lexer::Token<'_>],
+    selection: &Selection,
+    explain: bool,
+) -> Option<Query> {
+    let mut statements = crate::lexer::split_statements(tokens);
+    let starts_before =
+        |statement: &std::ops::Range<usize>, offset| tokens[statement.start].start <= offset;
+    match selection {
+        Selection::All => {}
+        Selection::Range(range) => statements.retain(|statement| {
+            tokens[statement.start].start >= range.start && starts_before(statement, range.end)
+        }),
+        Selection::Offset(offset) => {
+            let under_cursor = statements
+                .iter()
+                .take_while(|statement| starts_before(statement, *offset))
+                .last()
+                .or(statements.first())
+                .cloned();
+            statements = under_cursor.into_iter().collect();
+        }
+    }
+    if statements.is_empty() {
+        return None;
+    }
+
+    let mut query = Query { text: String::new(), ranges: Vec::new() };
+    for statement in statements {
+        let span = tokens[statement.start].start..tokens[statement.end - 1].end;
+        query.text.push_str(&source[span.clone()]);
+        if explain && is_explainable(&tokens[statement.clone()]) {
+            query.text.push_str(" EXPLAIN");
+        }
+        query.text.push_str(";\n");
+        if !is_transaction_statement(&tokens[statement]) {
+            query.ranges.push(span);
+        }
+    }
+    Some(query)
+}
+
+/// Returns whether `statement` is a `SELECT` that an `EXPLAIN` clause can be appended to.
+fn is_explainable(statement: &[crate::lexer::Token<'_>]) -> bool {
+    statement.first().is_some_and(|first| first.is_keyword("SELECT"))
+        && !statement.iter().any(|token| token.is_keyword("EXPLAIN"))
+}
+
+/// Returns whether `statement` begins, commits or cancels a transaction.
+fn is_transaction_statement(statement: &[crate::lexer::Token<'_>]) -> bool {
+    statement.first().is_some_and(|first| {
+        ["BEGIN", "COMMIT", "CANCEL"].iter().any(|keyword| first.is_keyword(keyword))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    const DOCUMENT: &str = "CREATE person:tobie SET name = 'Tobie';\n\
+                            SELECT * FROM person;\n\
+                            BEGIN;\n\
+                            UPDATE person:tobie SET age = 30;\n\
+                            COMMIT;\n";
+
+    #[test]
+    fn finds_statements_selects_and_transactions() {
+        let runnables: Vec<(&str, &str, super::RunnableKind)> =
+            super::runnables(&crate::lexer::tokenize(DOCUMENT))
+                .into_iter()
+                .map(|runnable| {
+                    (&DOCUMENT[runnable.range], &DOCUMENT[runnable.statements], runnable.kind)
+                })
+                .collect();
+        assert_eq!(
+            runnables,
+            [
+                (
+                    "CREATE person:tobie SET name = 'Tobie'",
+                    "CREATE person:tobie SET name = 'Tobie'",
+                    super::RunnableKind::Statement
+                ),
+                ("SELECT * FROM person", "SELECT * FROM person", super::RunnableKind::Statement),
+                ("SELECT * FROM person", "SELECT * FROM person", super::RunnableKind::Explain),
+                (
+                    "BEGIN",
+                    "BEGIN;\nUPDATE person:tobie SET age = 30;\nCOMMIT",
+                    super::RunnableKind::Transaction
+                ),
+                (
+                    "UPDATE person:tobie SET age = 30",
+                    "UPDATE person:tobie SET age = 30",
+                    super::RunnableKind::Statement
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn queries_the_selected_statements() {
+        let tokens = crate::lexer::tokenize(DOCUMENT);
+        let query = |selection, explain| super::query(DOCUMENT, &tokens, &selection, explain);
+
+        let select = DOCUMENT.find("SELECT").unwrap();
+        let explained = query(super::Selection::Offset(select + 3), true).unwrap();
+        assert_eq!(explained.text, "SELECT * FROM person EXPLAIN;\n");
+
+        let transaction = DOCUMENT.find("BEGIN").unwrap()..DOCUMENT.len();
+        let transaction = query(super::Selection::Range(transaction), false).unwrap();
+        assert_eq!(transaction.text, "BEGIN;\nUPDATE person:tobie SET age = 30;\nCOMMIT;\n");
+        let reported: Vec<&str> =
+            transaction.ranges.into_iter().map(|range| &DOCUMENT[range]).collect();
+        assert_eq!(reported, ["UPDATE person:tobie SET age = 30"]);
+
+        assert_eq!(query(super::Selection::All, false).unwrap().ranges.len(), 3);
+    }
+}
@@ -0,0 +1,1186 @@
+//! The workspace schema, built by replaying the `DEFINE` and `REMOVE` statements of every indexed
+//! file, and of any introspected database, in the order they are applied.
+
+/// The URI scheme of the definitions read from a live database, which cannot be edited.
+pub const LIVE_SCHEME: &str = "surrealdb";
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TableKind {
+    Any,
+    Normal,
+    Relation { from: Vec<String>, to: Vec<String> },
+}
+
+impl std::fmt::Display for TableKind {
+    /// Writes the kind as after `TYPE`, e.g. `RELATION IN user OUT product`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableKind::Any => write!(f, "ANY"),
+            TableKind::Normal => write!(f, "NORMAL"),
+            TableKind::Relation { from, to } => {
+                write!(f, "RELATION")?;
+                if !from.is_empty() {
+                    write!(f, " IN {}", from.join(" | "))?;
+                }
+                if !to.is_empty() {
+                    write!(f, " OUT {}", to.join(" | "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl TableKind {
+    /// Returns the tables that a traversal in `direction` may step onto a relation from, and the
+    /// tables it leads to, either empty when any table may be. `None` unless this is a relation.
+    pub(crate) fn ends(
+        &self,
+        direction: crate::query::Direction,
+    ) -> Option<(Vec<String>, Vec<String>)> {
+        let TableKind::Relation { from, to } = self else {
+            return None;
+        };
+        Some(match direction {
+            crate::query::Direction::Out => (from.clone(), to.clone()),
+            crate::query::Direction::In => (to.clone(), from.clone()),
+            crate::query::Direction::Both if from.is_empty() || to.is_empty() => {
+                (Vec::new(), Vec::new())
+            }
+            crate::query::Direction::Both => {
+                let mut both: Vec<String> = from.iter().chain(to).cloned().collect();
+                both.sort();
+                both.dedup();
+                (both.clone(), both)
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TableDefinition {
+    pub(crate) name: String,
+    pub(crate) schemafull: bool,
+    pub(crate) kind: TableKind,
+    pub(crate) location: crate::FileRange,
+    /// The end of the `DEFINE TABLE` statement, before any `;`. `None` for tables that are not
+    /// defined in a SurrealQL document, and so cannot be edited.
+    pub(crate) end: Option<crate::FileRange>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FieldDefinition {
+    pub(crate) table: String,
+    /// The full path of the field, e.g. `address.city` or `tags[*]`.
+    pub(crate) name: String,
+    /// The declared `TYPE`, if any.
+    pub(crate) kind: Option<crate::types::Kind>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FunctionDefinition {
+    /// The full name of the function, including the `fn::` prefix.
+    pub(crate) name: String,
+    /// The names of the arguments, excluding the `$`.
+    pub(crate) params: Vec<String>,
+    pub(crate) returns: Option<crate::types::Kind>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct IndexDefinition {
+    pub(crate) table: String,
+    pub(crate) name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ParamDefinition {
+    /// The name of the parameter, excluding the `$`.
+    pub(crate) name: String,
+    pub(crate) location: crate::FileRange,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Definition {
+    Table(TableDefinition),
+    Field(FieldDefinition),
+    Function(FunctionDefinition),
+    Index(IndexDefinition),
+    Param(ParamDefinition),
+}
+
+impl Definition {
+    fn key(&self) -> Key {
+        match self {
+            Definition::Table(table) => Key::Table(table.name.clone()),
+            Definition::Field(field) => Key::Field(field.table.clone(), field.name.clone()),
+            Definition::Function(function) => Key::Function(function.name.clone()),
+            Definition::Index(index) => Key::Index(index.table.clone(), index.name.clone()),
+            Definition::Param(param) => Key::Param(param.name.clone()),
+        }
+    }
+}
+
+/// Identifies a definition, which later statements of the same kind and name replace or remove.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Key {
+    Table(String),
+    /// A field, by table and path.
+    Field(String, String),
+    Function(String),
+    /// An index, by table and name.
+    Index(String, String),
+    Param(String),
+}
+
+impl Key {
+    /// Returns the kind of the definition as written after `DEFINE` and `REMOVE`.
+    pub(crate) fn keyword(&self) -> &'static str {
+        match self {
+            Key::Table(_) => "TABLE",
+            Key::Field(..) => "FIELD",
+            Key::Function(_) => "FUNCTION",
+            Key::Index(..) => "INDEX",
+            Key::Param(_) => "PARAM",
+        }
+    }
+
+    /// Returns the table of a field or index.
+    fn table(&self) -> Option<&str> {
+        match self {
+            Key::Field(table, _) | Key::Index(table, _) => Some(table),
+            Key::Table(_) | Key::Function(_) | Key::Param(_) => None,
+        }
+    }
+}
+
+/// How a `DEFINE` statement treats an existing definition of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum DefineMode {
+    /// Fails, leaving the existing definition in place. The 1.x dialect replaces it instead.
+    #[default]
+    Define,
+    /// `OVERWRITE` replaces the existing definition.
+    Overwrite,
+    /// `IF NOT EXISTS` leaves the existing definition in place without failing.
+    IfNotExists,
+}
+
+/// A `REMOVE` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Removal {
+    /// Removes the table along with its fields and indexes.
+    Table(String),
+    Field {
+        table: String,
+        name: String,
+    },
+    Function(String),
+    Index {
+        table: String,
+        name: String,
+    },
+    Param(String),
+}
+
+/// A statement that changes the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Change {
+    Define {
+        definition: Box<Definition>,
+        mode: DefineMode,
+        /// The statement as written after `DEFINE kind` and its modifiers, e.g.
+        /// `email ON person TYPE string`, or `None` when it is not known, as for a snapshot.
+        statement: Option<String>,
+    },
+    Remove(Removal),
+}
+
+/// Clause keywords that end a `DEFINE FIELD ... TYPE` clause.
+const FIELD_CLAUSES: &[&str] =
+    &["FLEXIBLE", "DEFAULT", "VALUE", "ASSERT", "READONLY", "PERMISSIONS", "COMMENT", "REFERENCE"];
+
+/// Extracts the `DEFINE` and `REMOVE` statements of `source`, the text of `uri` without a tree of
+/// its own, in order.
+pub(crate) fn changes(uri: &url::Url, source: &str) -> Vec<Change> {
+    changes_of(uri, source, &crate::lexer::tokenize(source))
+}
+
+/// Extracts the `DEFINE` and `REMOVE` statements of `source`, the text of `uri` made of `tokens`,
+/// in order.
+fn changes_of(uri: &url::Url, source: &str, tokens: &[crate::lexer::Token<'_>]) -> Vec<Change> {
+    let location =
+        |span: std::ops::Range<usize>| crate::FileRange { file: uri.clone(), range: span };
+
+    crate::query::statements(tokens)
+        .into_iter()
+        .filter_map(|range| {
+            let tokens = &tokens[range];
+            if tokens.first().is_some_and(|token| token.is_keyword("REMOVE")) {
+                return removal(tokens).map(Change::Remove);
+            }
+            let definition = definition(tokens, &location)?;
+            let start = tokens.get(skip_define_modifiers(tokens, 2))?.start;
+            let end = tokens.last()?.end;
+            Some(Change::Define {
+                definition: Box::new(definition),
+                mode: define_mode(tokens),
+                statement: Some(source[start..end].to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Returns how the `DEFINE` statement of `tokens` treats an existing definition.
+fn define_mode(tokens: &[crate::lexer::Token<'_>]) -> DefineMode {
+    match tokens.get(2) {
+        Some(token) if token.is_keyword("OVERWRITE") => DefineMode::Overwrite,
+        Some(token) if token.is_keyword("IF") => DefineMode::IfNotExists,
+        _ => DefineMode::Define,
+    }
+}
+
+/// Skips `OVERWRITE` and `IF NOT EXISTS` after the kind of a `DEFINE` statement.
+pub(crate) fn skip_define_modifiers(tokens: &[crate::lexer::Token<'_>], mut index: usize) -> usize {
+    if tokens.get(index).is_some_and(|token| token.is_keyword("OVERWRITE")) {
+        index += 1;
+    }
+    if tokens.get(index).is_some_and(|token| token.is_keyword("IF"))
+        && tokens.get(index + 1).is_some_and(|token| token.is_keyword("NOT"))
+        && tokens.get(index + 2).is_some_and(|token| token.is_keyword("EXISTS"))
+    {
+        index += 3;
+    }
+    index
+}
+
+fn definition(
+    tokens: &[crate::lexer::Token<'_>],
+    location: &dyn Fn(std::ops::Range<usize>) -> crate::FileRange,
+) -> Option<Definition> {
+    let [define, kind, ..] = tokens else {
+        return None;
+    };
+    if !define.is_keyword("DEFINE") {
+        return None;
+    }
+    let start = skip_define_modifiers(tokens, 2);
+    let name = tokens.get(start)?;
+
+    match kind.text.to_ascii_uppercase().as_str() {
+        "TABLE" => Some(Definition::Table(table_definition(tokens, start, location))),
+        "FIELD" => field_definition(tokens, start).map(Definition::Field),
+        "FUNCTION" => function_definition(tokens, start).map(Definition::Function),
+        "INDEX" => on_table(tokens, start).map(|table| {
+            Definition::Index(IndexDefinition {
+                table: tokens[table].ident().to_string(),
+                name: name.ident().to_string(),
+            })
+        }),
+        "PARAM" if name.kind == crate::lexer::TokenKind::Param => {
+            Some(Definition::Param(ParamDefinition {
+                name: name.ident().to_string(),
+                location: location(name.span()),
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Parses `REMOVE kind [IF EXISTS] name [ON [TABLE] table]`.
+fn removal(tokens: &[crate::lexer::Token<'_>]) -> Option<Removal> {
+    let kind = tokens.get(1)?;
+    let if_exists = tokens.get(2).is_some_and(|token| token.is_keyword("IF"))
+        && tokens.get(3).is_some_and(|token| token.is_keyword("EXISTS"));
+    let start = if if_exists { 4 } else { 2 };
+    let name = tokens.get(start)?;
+    let table = || on_table(tokens, start).map(|table| tokens[table].ident().to_string());
+
+    match kind.text.to_ascii_uppercase().as_str() {
+        "TABLE" => Some(Removal::Table(name.ident().to_string())),
+        "FIELD" => {
+            let on = start + tokens[start..].iter().position(|token| token.is_keyword("ON"))?;
+            let (name, _) = crate::query::field_path(&tokens[start..on])?;
+            Some(Removal::Field { table: table()?, name })
+        }
+        "FUNCTION" => {
+            let end = tokens[start..]
+                .iter()
+                .position(|token| token.is_punct("("))
+                .map_or(tokens.len(), |end| start + end);
+            Some(Removal::Function(tokens[start..end].iter().map(|token| token.text).collect()))
+        }
+        "INDEX" => Some(Removal::Index { table: table()?, name: name.ident().to_string() }),
+        "PARAM" if name.kind == crate::lexer::TokenKind::Param => {
+            Some(Removal::Param(name.ident().to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the index of the table name in an `ON [TABLE] name` clause.
+fn on_table(tokens: &[crate::lexer::Token<'_>], start: usize) -> Option<usize> {
+    let on = start + tokens[start..].iter().position(|token| token.is_keyword("ON"))?;
+    let table = if tokens.get(on + 1).is_some_and(|token| token.is_keyword("TABLE")) {
+        on + 2
+    } else {
+        on + 1
+    };
+    tokens.get(table).filter(|token| token.kind == crate::lexer::TokenKind::Ident)?;
+    Some(table)
+}
+
+fn table_definition(
+    tokens: &[crate::lexer::Token<'_>],
+    start: usize,
+    location: &dyn Fn(std::ops::Range<usize>) -> crate::FileRange,
+) -> TableDefinition {
+    let name = &tokens[start];
+    let mut schemafull = false;
+    let mut kind = TableKind::Any;
+    let mut index = start + 1;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        if token.is_keyword("SCHEMAFULL") {
+            schemafull = true;
+        } else if token.is_keyword("SCHEMALESS") {
+            schemafull = false;
+        } else if token.is_keyword("AS") || token.is_keyword("PERMISSIONS") {
+            // Views and permissions contain arbitrary expressions.
+            break;
+        } else if token.is_keyword("TYPE") {
+            let Some(next) = tokens.get(index + 1) else {
+                break;
+            };
+            index += 1;
+            if next.is_keyword("NORMAL") {
+                kind = TableKind::Normal;
+            } else if next.is_keyword("RELATION") {
+                let mut from = Vec::new();
+                let mut to = Vec::new();
+                while let Some(token) = tokens.get(index + 1) {
+                    let side = if token.is_keyword("IN") || token.is_keyword("FROM") {
+                        &mut from
+                    } else if token.is_keyword("OUT") || token.is_keyword("TO") {
+                        &mut to
+                    } else {
+                        break;
+                    };
+                    index += 2;
+                    while let Some(table) = tokens.get(index) {
+                        side.push(table.ident().to_string());
+                        if !tokens.get(index + 1).is_some_and(|token| token.is_punct("|")) {
+                            break;
+                        }
+                        index += 2;
+                    }
+                }
+                kind = TableKind::Relation { from, to };
+            }
+        }
+        index += 1;
+    }
+
+    TableDefinition {
+        name: name.ident().to_string(),
+        schemafull,
+        kind,
+        location: location(name.span()),
+        end: Some(location(tokens[tokens.len() - 1].end..tokens[tokens.len() - 1].end)),
+    }
+}
+
+fn field_definition(tokens: &[crate::lexer::Token<'_>], start: usize) -> Option<FieldDefinition> {
+    let on = start + tokens[start..].iter().position(|token| token.is_keyword("ON"))?;
+    let (name, _) = crate::query::field_path(&tokens[start..on])?;
+    let table = on_table(tokens, start)?;
+
+    let mut kind = None;
+    let mut index = table + 1;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        if token.is_keyword("TYPE") {
+            let end = type_end(tokens, index + 1, FIELD_CLAUSES);
+            kind = type_kind(&tokens[index + 1..end]);
+            index = end;
+            continue;
+        } else if FIELD_CLAUSES.iter().any(|clause| token.is_keyword(clause)) {
+            // Skip over the expression of the clause.
+            index = type_end(tokens, index + 1, FIELD_CLAUSES);
+            continue;
+        }
+        index += 1;
+    }
+
+    Some(FieldDefinition { table: tokens[table].ident().to_string(), name, kind })
+}
+
+fn function_definition(
+    tokens: &[crate::lexer::Token<'_>],
+    start: usize,
+) -> Option<FunctionDefinition> {
+    let open = start + tokens[start..].iter().position(|token| token.is_punct("("))?;
+    let name: String = tokens[start..open].iter().map(|token| token.text).collect();
+    let close = crate::lexer::matching_bracket(tokens, open)?;
+
+    let arguments = &tokens[open + 1..close];
+    let params = crate::lexer::split_top_level(arguments, ",")
+        .into_iter()
+        .filter_map(|part| arguments[part].first().copied())
+        .filter(|token| token.kind == crate::lexer::TokenKind::Param)
+        .map(|token| token.ident().to_string())
+        .collect();
+
+    let returns = match tokens.get(close + 1) {
+        Some(arrow) if arrow.is_punct("->") => {
+            let end = tokens[close + 2..]
+                .iter()
+                .position(|token| token.is_punct("{"))
+                .map_or(tokens.len(), |end| close + 2 + end);
+            type_kind(&tokens[close + 2..end])
+        }
+        _ => None,
+    };
+
+    Some(FunctionDefinition { name, params, returns })
+}
+
+/// Returns the index of the first token after a type that starts at `start`, stopping at any of
+/// `clauses` outside of brackets.
+fn type_end(tokens: &[crate::lexer::Token<'_>], start: usize, clauses: &[&str]) -> usize {
+    let mut depth = 0usize;
+    for (index, token) in tokens.iter().enumerate().skip(start) {
+        match token.text {
+            "<" | "(" | "[" | "{" => depth += 1,
+            ">" | ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            _ if depth == 0 && clauses.iter().any(|clause| token.is_keyword(clause)) => {
+                return index;
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+fn type_kind(tokens: &[crate::lexer::Token<'_>]) -> Option<crate::types::Kind> {
+    let text: Vec<&str> = tokens.iter().map(|token| token.text).collect();
+    crate::types::Kind::parse(&text.join(" "))
+}
+
+/// The effective definitions after replaying the statements of every indexed file.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Schema {
+    pub(crate) tables: std::collections::BTreeMap<String, TableDefinition>,
+    /// Fields keyed by table name and then by field path.
+    pub(crate) fields:
+        std::collections::BTreeMap<String, std::collections::BTreeMap<String, FieldDefinition>>,
+    pub(crate) functions: std::collections::BTreeMap<String, FunctionDefinition>,
+    /// Indexes keyed by table name and then by index name.
+    pub(crate) indexes:
+        std::collections::BTreeMap<String, std::collections::BTreeMap<String, IndexDefinition>>,
+    pub(crate) params: std::collections::BTreeMap<String, ParamDefinition>,
+    /// The statements of the definitions, as written after `DEFINE kind` and its modifiers.
+    pub(crate) statements: std::collections::BTreeMap<Key, String>,
+    /// The definitions made by earlier layers, which a plain `DEFINE` of the current layer
+    /// replaces rather than fails on.
+    inherited: std::collections::BTreeSet<Key>,
+}
+
+impl Schema {
+    /// Returns the schema made by applying `changes` to an empty one, as `dialect` would.
+    pub(crate) fn from_changes<'c>(
+        changes: impl IntoIterator<Item = &'c Change>,
+        dialect: crate::config::Dialect,
+    ) -> Self {
+        let mut schema = Schema::default();
+        for change in changes {
+            schema.apply(change, dialect);
+        }
+        schema
+    }
+
+    /// Starts a new layer of statements, e.g. the files of the workspace after the schema of a
+    /// database, whose `DEFINE` statements replace what the earlier layers defined.
+    fn begin_layer(&mut self) {
+        let tables = self.tables.keys().map(|table| Key::Table(table.clone()));
+        let fields = self.fields.iter().flat_map(|(table, fields)| {
+            fields.keys().map(|field| Key::Field(table.clone(), field.clone()))
+        });
+        let functions = self.functions.keys().map(|function| Key::Function(function.clone()));
+        let indexes = self.indexes.iter().flat_map(|(table, indexes)| {
+            indexes.keys().map(|index| Key::Index(table.clone(), index.clone()))
+        });
+        let params = self.params.keys().map(|param| Key::Param(param.clone()));
+        self.inherited =
+            tables.chain(fields).chain(functions).chain(indexes).chain(params).collect();
+    }
+
+    /// Applies `change` as SurrealDB would: a `DEFINE` of an existing name fails unless it is an
+    /// `OVERWRITE`, and `IF NOT EXISTS` leaves the existing definition in place. With the 1.x
+    /// `dialect`, which has no `OVERWRITE`, a plain `DEFINE` replaces the existing definition.
+    fn apply(&mut self, change: &Change, dialect: crate::config::Dialect) {
+        match change {
+            Change::Define { definition, mode, statement } => {
+                let key = definition.key();
+                let inherited = self.inherited.remove(&key);
+                let overwrites = match mode {
+                    DefineMode::Overwrite => true,
+                    DefineMode::Define => dialect == crate::config::Dialect::V1,
+                    DefineMode::IfNotExists => false,
+                };
+                if overwrites || inherited || !self.contains(&key) {
+                    self.define(definition);
+                    match statement {
+                        Some(statement) => self.statements.insert(key, statement.clone()),
+                        None => self.statements.remove(&key),
+                    };
+                }
+            }
+            Change::Remove(removal) => self.remove(removal),
+        }
+    }
+
+    fn contains(&self, key: &Key) -> bool {
+        match key {
+            Key::Table(table) => self.tables.contains_key(table),
+            Key::Field(table, field) => {
+                self.fields.get(table).is_some_and(|fields| fields.contains_key(field))
+            }
+            Key::Function(function) => self.functions.contains_key(function),
+            Key::Index(table, index) => {
+                self.indexes.get(table).is_some_and(|indexes| indexes.contains_key(index))
+            }
+            Key::Param(param) => self.params.contains_key(param),
+        }
+    }
+
+    fn remove(&mut self, removal: &Removal) {
+        let key = match removal {
+            Removal::Table(table) => Key::Table(table.clone()),
+            Removal::Field { table, name } => Key::Field(table.clone(), name.clone()),
+            Removal::Function(function) => Key::Function(function.clone()),
+            Removal::Index { table, name } => Key::Index(table.clone(), name.clone()),
+            Removal::Param(param) => Key::Param(param.clone()),
+        };
+        self.statements.remove(&key);
+        match removal {
+            Removal::Table(table) => {
+                self.tables.remove(table);
+                self.fields.remove(table);
+                self.indexes.remove(table);
+                self.statements.retain(|key, _| key.table() != Some(table));
+            }
+            Removal::Field { table, name } => {
+                if let Some(fields) = self.fields.get_mut(table) {
+                    fields.remove(name);
+                }
+            }
+            Removal::Function(function) => {
+                self.functions.remove(function);
+            }
+            Removal::Index { table, name } => {
+                if let Some(indexes) = self.indexes.get_mut(table) {
+                    indexes.remove(name);
+                }
+            }
+            Removal::Param(param) => {
+                self.params.remove(param);
+            }
+        }
+    }
+
+    fn define(&mut self, definition: &Definition) {
+        match definition {
+            Definition::Table(table) => {
+                self.tables.insert(table.name.clone(), table.clone());
+            }
+            Definition::Field(field) => {
+                self.fields
+                    .entry(field.table.clone())
+                    .or_default()
+                    .insert(field.name.clone(), field.clone());
+            }
+            Definition::Function(function) => {
+                self.functions.insert(function.name.clone(), function.clone());
+            }
+            Definition::Index(index) => {
+                self.indexes
+                    .entry(index.table.clone())
+                    .or_default()
+                    .insert(index.name.clone(), index.clone());
+            }
+            Definition::Param(param) => {
+                self.params.insert(param.name.clone(), param.clone());
+            }
+        }
+    }
+
+    /// Returns the field definitions of `table`.
+    pub(crate) fn fields_of(&self, table: &str) -> impl Iterator<Item = &FieldDefinition> + '_ {
+        self.fields.get(table).into_iter().flat_map(|fields| fields.values())
+    }
+
+    /// Returns whether `field` is a top-level field of `table`, including the implicit `id` field
+    /// and the `in`/`out` fields of relation tables.
+    pub(crate) fn has_field(&self, table: &str, field: &str) -> bool {
+        if field == "id" {
+            return true;
+        }
+        let is_relation = self
+            .tables
+            .get(table)
+            .is_some_and(|table| matches!(table.kind, TableKind::Relation { .. }));
+        if is_relation && matches!(field, "in" | "out") {
+            return true;
+        }
+        self.fields_of(table).any(|definition| {
+            definition.name == field
+                || definition
+                    .name
+                    .strip_prefix(field)
+                    .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+        })
+    }
+}
+
+/// The text and tree of an indexed document and its schema changes, which are extracted on demand.
+#[derive(Debug)]
+struct Source {
+    uri: url::Url,
+    parsed: std::sync::Arc<crate::parser::Parsed>,
+    changes: std::sync::OnceLock<Changes>,
+    /// The changes of an earlier version of the document, which are reused when they are the
+    /// same, so that the schema built from them is still up to date.
+    previous: Option<Changes>,
+}
+
+type Changes = std::sync::Arc<Vec<Change>>;
+
+impl Source {
+    fn new(
+        uri: &url::Url,
+        parsed: std::sync::Arc<crate::parser::Parsed>,
+        previous: Option<&Source>,
+    ) -> Self {
+        let previous = previous
+            .and_then(|previous| previous.changes.get().or(previous.previous.as_ref()))
+            .cloned();
+        Self { uri: uri.clone(), parsed, changes: std::sync::OnceLock::new(), previous }
+    }
+
+    fn changes(&self) -> &Changes {
+        self.changes.get_or_init(|| {
+            let changes = changes_of(&self.uri, self.parsed.text(), &self.parsed.tokens());
+            match &self.previous {
+                Some(previous) if **previous == changes => previous.clone(),
+                _ => std::sync::Arc::new(changes),
+            }
+        })
+    }
+}
+
+/// The workspace schema and the changes it was replayed from.
+#[derive(Debug)]
+struct MergedSchema {
+    /// The dialect the changes were applied as.
+    dialect: crate::config::Dialect,
+    /// The changes of every database and snapshot, each a layer of its own.
+    layers: Vec<Changes>,
+    /// The schema after the layers, to which the files are applied as the last layer.
+    base: std::sync::Arc<Schema>,
+    /// The files in the order they were applied.
+    files: Vec<ReplayedFile>,
+    schema: std::sync::Arc<Schema>,
+    /// The schema as of each migration, after its statements have been applied.
+    at: std::collections::HashMap<url::Url, std::sync::Arc<Schema>>,
+}
+
+/// A file applied to a [`MergedSchema`].
+#[derive(Clone, Debug)]
+struct ReplayedFile {
+    uri: url::Url,
+    changes: Changes,
+    /// The schema after the file, kept for migrations only.
+    at: Option<std::sync::Arc<Schema>>,
+}
+
+/// Schema changes indexed per file, from which the workspace [`Schema`] is derived.
+///
+/// The index is cheap to clone for a snapshot of the workspace, as the files are shared. The
+/// changes of a file are only extracted once it has been edited and the schema is asked for, and
+/// the schema is only replayed again when the changes of a file have changed, not merely its other
+/// statements, and then only from the last migration before that file.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SchemaIndex {
+    files: std::collections::BTreeMap<url::Url, std::sync::Arc<Source>>,
+    /// The schema files and then the migrations of every project, in the order they are applied.
+    /// The other files are applied after them, in the order of their URIs.
+    order: Vec<url::Url>,
+    /// The migrations among `order`, each of which is checked against the schema as of itself.
+    migrations: std::collections::BTreeSet<url::Url>,
+    /// Changes loaded from schema snapshots, keyed by the URI of the snapshot.
+    snapshots: std::collections::BTreeMap<url::Url, Changes>,
+    /// Changes introspected from live databases, keyed by their [`LIVE_SCHEME`] URI. They are
+    /// kept when the files are re-indexed, until the database is introspected again.
+    live: std::collections::BTreeMap<url::Url, std::sync::Arc<Source>>,
+    /// The dialect whose rules the changes are applied with.
+    dialect: crate::config::Dialect,
+    /// The last schema replayed by this index or any of its clones. The lock is only held to
+    /// take or replace it, not while replaying.
+    merged: std::sync::Arc<std::sync::Mutex<Option<std::sync::Arc<MergedSchema>>>>,
+}
+
+impl SchemaIndex {
+    /// Replaces the changes of the file at `uri` with those of `text`.
+    pub(crate) fn update(&mut self, uri: &url::Url, text: &str) {
+        let parsed = crate::parser::Parsed::new(text.to_string(), None);
+        self.update_parsed(uri, std::sync::Arc::new(parsed));
+    }
+
+    /// Replaces the changes of the file at `uri` with those of `parsed`, whose tree is shared with
+    /// the open document.
+    pub(crate) fn update_parsed(
+        &mut self,
+        uri: &url::Url,
+        parsed: std::sync::Arc<crate::parser::Parsed>,
+    ) {
+        let source = Source::new(uri, parsed, self.files.get(uri).map(|source| &**source));
+        self.files.insert(uri.clone(), std::sync::Arc::new(source));
+    }
+
+    /// Forgets the changes of the file at `uri`.
+    pub(crate) fn remove(&mut self, uri: &url::Url) {
+        self.files.remove(uri);
+    }
+
+    /// Reads and indexes the file at `path`, returning whether it could be read.
+    pub(crate) fn update_from_disk(&mut self, path: &std::path::Path) -> bool {
+        let (Ok(uri), Ok(content)) =
+            (url::Url::from_file_path(path), std::fs::read_to_string(path))
+        else {
+            return false;
+        };
+        self.update(&uri, &content);
+        true
+    }
+
+    /// Reads the snapshot at `path`, returning why it could not be.
+    pub(crate) fn update_from_snapshot(&mut self, path: &std::path::Path) -> Result<(), String> {
+        let uri = url::Url::from_file_path(path)
+            .map_err(|_| format!("Invalid path {}", path.display()))?;
+        let snapshot = crate::snapshot::Snapshot::load(path)?;
+        self.snapshots.insert(uri.clone(), std::sync::Arc::new(snapshot.changes(&uri)));
+        Ok(())
+    }
+
+    /// Sets the order the files at `order` are applied in, ahead of the other files. The
+    /// `migrations` among them are checked against the schema as of themselves.
+    pub(crate) fn set_order(
+        &mut self,
+        order: Vec<url::Url>,
+        migrations: std::collections::BTreeSet<url::Url>,
+    ) {
+        self.order = order;
+        self.migrations = migrations;
+    }
+
+    /// Sets the dialect whose rules the changes are applied with.
+    pub(crate) fn set_dialect(&mut self, dialect: crate::config::Dialect) {
+        self.dialect = dialect;
+    }
+
+    /// Forgets every snapshot except those at `uris`.
+    pub(crate) fn retain_snapshots(&mut self, uris: &[url::Url]) {
+        self.snapshots.retain(|uri, _| uris.contains(uri));
+    }
+
+    /// Replaces the changes introspected from the database at `uri`.
+    pub(crate) fn update_live(&mut self, uri: &url::Url, text: String) {
+        let parsed = std::sync::Arc::new(crate::parser::Parsed::new(text, None));
+        let mut changes = changes_of(uri, parsed.text(), &parsed.tokens());
+        for change in &mut changes {
+            if let Change::Define { definition, .. } = change {
+                if let Definition::Table(table) = &mut **definition {
+                    table.end = None;
+                }
+            }
+        }
+        let changes = std::sync::OnceLock::from(std::sync::Arc::new(changes));
+        let source = Source { uri: uri.clone(), parsed, changes, previous: None };
+        self.live.insert(uri.clone(), std::sync::Arc::new(source));
+    }
+
+    /// Forgets the changes of every database except those at `uris`.
+    pub(crate) fn retain_live(&mut self, uris: &[url::Url]) {
+        self.live.retain(|uri, _| uris.contains(uri));
+    }
+
+    /// Returns the URLs of the indexed files.
+    pub(crate) fn files(&self) -> impl Iterator<Item = &url::Url> + '_ {
+        self.files.keys()
+    }
+
+    /// Returns the text of the indexed file or database at `uri`.
+    pub(crate) fn text(&self, uri: &url::Url) -> Option<&str> {
+        self.files.get(uri).or_else(|| self.live.get(uri)).map(|source| &**source.parsed.text())
+    }
+
+    /// Returns the text of the indexed file at `uri`, which is shared rather than copied until
+    /// the file changes, so that what is computed from it can be kept for as long as it is the
+    /// same text.
+    pub(crate) fn shared_text(&self, uri: &url::Url) -> Option<&std::sync::Arc<str>> {
+        self.files.get(uri).map(|source| source.parsed.text())
+    }
+
+    /// Returns the text and tree of the indexed file at `uri`.
+    pub(crate) fn parsed(&self, uri: &url::Url) -> Option<&crate::parser::Parsed> {
+        self.files.get(uri).map(|source| &*source.parsed)
+    }
+
+    /// Returns the schema after replaying every database, every snapshot and then every indexed
+    /// file, so that the files being edited take precedence over what is already deployed.
+    pub(crate) fn schema(&self) -> std::sync::Arc<Schema> {
+        self.replay(None)
+    }
+
+    /// Returns the schema that `file` is checked against: the schema as of the migration it is,
+    /// or else the schema after every file.
+    pub(crate) fn schema_at(&self, file: &url::Url) -> std::sync::Arc<Schema> {
+        self.replay(Some(file))
+    }
+
+    /// Returns the schema made by the files alone, without any database or snapshot.
+    pub(crate) fn workspace_schema(&self) -> Schema {
+        let files = self.ordered_files();
+        let changes = files.iter().flat_map(|uri| self.files[*uri].changes().iter());
+        Schema::from_changes(changes, self.dialect)
+    }
+
+    /// Returns the indexed files in the order they are applied.
+    fn ordered_files(&self) -> Vec<&url::Url> {
+        let ordered: std::collections::HashSet<&url::Url> = self.order.iter().collect();
+        let rest = self.files.keys().filter(|uri| !ordered.contains(uri));
+        self.order.iter().filter(|uri| self.files.contains_key(*uri)).chain(rest).collect()
+    }
+
+    /// Returns the schema as of `file` if it is a migration, or else the schema after every file.
+    fn replay(&self, file: Option<&url::Url>) -> std::sync::Arc<Schema> {
+        let merged = self.merged();
+        match file.and_then(|file| merged.at.get(file)) {
+            Some(schema) => schema.clone(),
+            None => merged.schema.clone(),
+        }
+    }
+
+    /// Returns the schema replayed last while the changes are the same. Otherwise the files are
+    /// replayed again from the last migration before the first file whose changes differ, or from
+    /// the start when a database or snapshot has changed.
+    fn merged(&self) -> std::sync::Arc<MergedSchema> {
+        let live = self.live.values().map(|source| source.changes());
+        let layers: Vec<Changes> = live.chain(self.snapshots.values()).cloned().collect();
+        let files: Vec<(&url::Url, &Changes, bool)> = self
+            .ordered_files()
+            .into_iter()
+            .map(|uri| (uri, self.files[uri].changes(), self.migrations.contains(uri)))
+            .collect();
+
+        let last = self.merged.lock().unwrap_or_else(std::sync::PoisonError::into_inner).clone();
+        let last = last.filter(|last| {
+            last.dialect == self.dialect
+                && last.layers.len() == layers.len()
+                && last.layers.iter().zip(&layers).all(|(a, b)| std::sync::Arc::ptr_eq(a, b))
+        });
+        let unchanged = last.as_ref().map_or(0, |last| {
+            let same = last.files.iter().zip(&files).take_while(
+                |(replayed, (uri, changes, is_migration))| {
+                    replayed.uri == **uri
+                        && std::sync::Arc::ptr_eq(&replayed.changes, changes)
+                        && replayed.at.is_some() == *is_migration
+                },
+            );
+            same.count()
+        });
+
+        let (base, mut replayed, mut schema) = match &last {
+            Some(last) if unchanged == last.files.len() && unchanged == files.len() => {
+                return last.clone();
+            }
+            Some(last) => {
+                let resume = last.files[..unchanged].iter().rposition(|file| file.at.is_some());
+                let resumed = resume.and_then(|index| last.files[index].at.as_ref());
+                let schema = Schema::clone(resumed.unwrap_or(&last.base));
+                let kept = resume.map_or(0, |index| index + 1);
+                (last.base.clone(), last.files[..kept].to_vec(), schema)
+            }
+            None => {
+                let mut schema = Schema::default();
+                for layer in &layers {
+                    schema.begin_layer();
+                    for change in layer.iter() {
+                        schema.apply(change, self.dialect);
+                    }
+                }
+                schema.begin_layer();
+                (std::sync::Arc::new(schema.clone()), Vec::new(), schema)
+            }
+        };
+        for (uri, changes, is_migration) in &files[replayed.len()..] {
+            for change in changes.iter() {
+                schema.apply(change, self.dialect);
+            }
+            replayed.push(ReplayedFile {
+                uri: (*uri).clone(),
+                changes: (*changes).clone(),
+                at: is_migration.then(|| std::sync::Arc::new(schema.clone())),
+            });
+        }
+        let at =
+            replayed.iter().filter_map(|file| Some((file.uri.clone(), file.at.clone()?))).collect();
+        let merged = std::sync::Arc::new(MergedSchema {
+            dialect: self.dialect,
+            layers,
+            base,
+            files: replayed,
+            schema: std::sync::Arc::new(schema),
+            at,
+        });
+        *self.merged.lock().unwrap_or_else(std::sync::PoisonError::into_inner) =
+            Some(merged.clone());
+        merged
+    }
+}
+
+/// Returns the candidate closest to `name` by edit distance, if it is close enough to be a likely
+/// typo.
+pub(crate) fn suggest<'c>(
+    name: &str,
+    candidates: impl Iterator<Item = &'c str>,
+) -> Option<&'c str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns the number of insertions, deletions, substitutions and transpositions of adjacent
+/// characters that turn `a` into `b`, ignoring case.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut before_previous: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            let mut distance = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            if i > 0 && j > 0 && *ca == b[j - 1] && a[i - 1] == *cb {
+                distance = distance.min(before_previous[j - 1] + 1);
+            }
+            current.push(distance);
+        }
+        before_previous = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    fn uri(name: &str) -> url::Url {
+        url::Url::parse(&format!("file:///project/{}.surql", name)).unwrap()
+    }
+
+    /// Indexes `files` in their order, of which those named `migration*` are migrations.
+    fn index(files: &[(&str, &str)]) -> super::SchemaIndex {
+        let mut index = super::SchemaIndex::default();
+        for (name, text) in files {
+            index.update(&uri(name), text);
+        }
+        let order = files.iter().map(|(name, _)| uri(name)).collect();
+        let migrations = files
+            .iter()
+            .filter(|(name, _)| name.starts_with("migration"))
+            .map(|(name, _)| uri(name))
+            .collect();
+        index.set_order(order, migrations);
+        index
+    }
+
+    fn tables(schema: &super::Schema) -> Vec<&str> {
+        schema.tables.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn replays_from_the_migration_before_the_edited_file() {
+        let mut index = index(&[
+            ("migration1", "DEFINE TABLE a;"),
+            ("migration2", "DEFINE TABLE b;"),
+            ("migration3", "DEFINE TABLE c;"),
+            ("queries", "SELECT * FROM a;"),
+        ]);
+        let before: Vec<_> =
+            (1..=3).map(|n| index.schema_at(&uri(&format!("migration{}", n)))).collect();
+        assert_eq!(tables(&before[1]), ["a", "b"]);
+
+        index.update(&uri("migration2"), "DEFINE TABLE b2;");
+        let after: Vec<_> =
+            (1..=3).map(|n| index.schema_at(&uri(&format!("migration{}", n)))).collect();
+        assert!(std::sync::Arc::ptr_eq(&before[0], &after[0]));
+        assert_eq!(tables(&after[1]), ["a", "b2"]);
+        assert_eq!(tables(&after[2]), ["a", "b2", "c"]);
+        assert_eq!(tables(&index.schema_at(&uri("queries"))), ["a", "b2", "c"]);
+    }
+
+    #[test]
+    fn edits_that_keep_the_definitions_keep_the_schema() {
+        let mut index = index(&[("migration1", "DEFINE TABLE a;"), ("queries", "SELECT 1;")]);
+        let schema = index.schema();
+        index.update(&uri("migration1"), "DEFINE TABLE a; -- The first table");
+        index.update(&uri("queries"), "SELECT * FROM a;");
+        assert!(std::sync::Arc::ptr_eq(&schema, &index.schema()));
+
+        index.remove(&uri("queries"));
+        index.update(&uri("other"), "DEFINE TABLE z;");
+        assert_eq!(tables(&index.schema()), ["a", "z"]);
+        assert_eq!(tables(&index.schema_at(&uri("migration1"))), ["a"]);
+    }
+
+    /// Returns the declared type of `field` of `table`, or `"-"` when the field is not defined.
+    fn field(schema: &super::Schema, table: &str, field: &str) -> String {
+        match schema.fields.get(table).and_then(|fields| fields.get(field)) {
+            Some(field) => field.kind.as_ref().map_or(String::from("any"), ToString::to_string),
+            None => String::from("-"),
+        }
+    }
+
+    fn is_schemafull(schema: &super::Schema, table: &str) -> bool {
+        schema.tables[table].schemafull
+    }
+
+    #[test]
+    fn a_define_of_an_existing_name_is_ignored_unless_it_overwrites() {
+        let index = index(&[
+            (
+                "migration1",
+                "DEFINE TABLE person SCHEMALESS; DEFINE FIELD name ON person TYPE string;",
+            ),
+            ("migration2", "DEFINE TABLE person SCHEMAFULL; DEFINE FIELD name ON person TYPE int;"),
+            (
+                "migration3",
+                "DEFINE FIELD IF NOT EXISTS name ON person TYPE bool;\n\
+                 DEFINE FIELD IF NOT EXISTS age ON person TYPE int;",
+            ),
+            (
+                "migration4",
+                "DEFINE TABLE OVERWRITE person SCHEMAFULL;\n\
+                 DEFINE FIELD OVERWRITE name ON person TYPE option<string>;",
+            ),
+        ]);
+        let at = |n| index.schema_at(&uri(&format!("migration{}", n)));
+        for n in 1..=3 {
+            assert!(!is_schemafull(&at(n), "person"));
+            assert_eq!(field(&at(n), "person", "name"), "string");
+        }
+        assert_eq!(field(&at(2), "person", "age"), "-");
+        assert_eq!(field(&at(3), "person", "age"), "int");
+        assert!(is_schemafull(&at(4), "person"));
+        assert_eq!(field(&at(4), "person", "name"), "option<string>");
+    }
+
+    #[test]
+    fn a_define_of_an_existing_name_replaces_it_in_the_1x_dialect() {
+        let files = [
+            (
+                "migration1",
+                "DEFINE TABLE person SCHEMALESS; DEFINE FIELD name ON person TYPE string;",
+            ),
+            ("migration2", "DEFINE TABLE person SCHEMAFULL; DEFINE FIELD name ON person TYPE int;"),
+            ("migration3", "DEFINE FIELD IF NOT EXISTS name ON person TYPE bool;"),
+        ];
+        let mut index = index(&files);
+        let v2 = index.schema();
+        assert!(!is_schemafull(&v2, "person"));
+        assert_eq!(field(&v2, "person", "name"), "string");
+
+        index.set_dialect(crate::config::Dialect::V1);
+        let v1 = index.schema();
+        assert!(is_schemafull(&v1, "person"));
+        assert_eq!(field(&v1, "person", "name"), "int");
+        assert!(!is_schemafull(&index.schema_at(&uri("migration1")), "person"));
+        assert_eq!(field(&index.workspace_schema(), "person", "name"), "int");
+    }
+
+    #[test]
+    fn removals_apply_in_order() {
+        let mut index = index(&[
+            (
+                "migration1",
+                "DEFINE TABLE person; DEFINE FIELD email ON person TYPE string;\n\
+                 DEFINE INDEX email ON person FIELDS email UNIQUE;",
+            ),
+            ("migration2", "REMOVE FIELD email ON person; DEFINE FIELD email ON person TYPE int;"),
+            ("migration3", "REMOVE TABLE person;"),
+            ("migration4", "DEFINE TABLE person;"),
+        ]);
+        let at = |index: &super::SchemaIndex, n| index.schema_at(&uri(&format!("migration{}", n)));
+        assert_eq!(field(&at(&index, 2), "person", "email"), "int");
+        assert!(at(&index, 2).indexes["person"].contains_key("email"));
+        assert!(!at(&index, 3).tables.contains_key("person"));
+        assert_eq!(field(&at(&index, 4), "person", "email"), "-");
+        assert!(!at(&index, 4).indexes.contains_key("person"));
+
+        // Removing the table before anything is defined leaves every later definition in place.
+        let order = [3, 1, 4, 2].map(|n| uri(&format!("migration{}", n))).to_vec();
+        let migrations = order.iter().cloned().collect();
+        index.set_order(order, migrations);
+        let schema = index.schema();
+        assert_eq!(tables(&schema), ["person"]);
+        assert_eq!(field(&schema, "person", "email"), "int");
+        assert!(schema.indexes["person"].contains_key("email"));
+    }
+
+    #[test]
+    fn the_workspace_replaces_what_a_database_defines() {
+        let mut index = index(&[
+            (
+                "migration1",
+                "DEFINE TABLE person SCHEMALESS;\n\
+                 DEFINE FIELD IF NOT EXISTS age ON person TYPE int;",
+            ),
+            ("migration2", "DEFINE TABLE person SCHEMAFULL;"),
+        ]);
+        let database = url::Url::parse("surrealdb://localhost:8000/test/app").unwrap();
+        index.update_live(
+            &database,
+            String::from(
+                "DEFINE TABLE person SCHEMAFULL; DEFINE FIELD age ON person TYPE string;\n\
+                 DEFINE TABLE log SCHEMALESS;",
+            ),
+        );
+        let schema = index.schema();
+        assert_eq!(tables(&schema), ["log", "person"]);
+        // The first definition in the workspace replaces that of the database, even with
+        // `IF NOT EXISTS`, but the next one does not replace the first.
+        assert!(!is_schemafull(&schema, "person"));
+        assert_eq!(field(&schema, "person", "age"), "int");
+    }
+
+    #[test]
+    fn suggests_names_within_a_third_of_the_length() {
+        let tables = ["person", "post", "product"];
+        assert_eq!(super::suggest("persn", tables.into_iter()), Some("person"));
+        assert_eq!(super::suggest("PERSON", tables.into_iter()), Some("person"));
+        assert_eq!(super::suggest("prodcut", tables.into_iter()), Some("product"));
+        assert_eq!(super::suggest("invoice", tables.into_iter()), None);
+    }
+
+    #[test]
+    fn transpositions_count_as_one_edit() {
+        assert_eq!(super::edit_distance("name", "nmae"), 1);
+        assert_eq!(super::edit_distance("title", "titel"), 1);
+        assert_eq!(super::edit_distance("kitten", "sitting"), 3);
+        assert_eq!(super::edit_distance("", "abc"), 3);
+    }
+}
@@ -0,0 +1,304 @@
+//! Scope analysis of `$param` bindings and uses.
+
+/// Parameters that SurrealDB binds implicitly in some context. `$scope` is the 1.x name of
+/// `$access`.
+pub(crate) const BUILTIN_PARAMS: &[&str] = &[
+    "this", "parent", "value", "before", "after", "event", "auth", "access", "scope", "session",
+    "token", "input",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BindingKind {
+    Let,
+    FunctionArgument,
+    LoopVariable,
+    ClosureArgument,
+}
+
+#[derive(Debug)]
+struct Binding {
+    name: String,
+    span: std::ops::Range<usize>,
+    kind: BindingKind,
+    used: bool,
+}
+
+/// What the binding shadowed by a `LET` is.
+#[derive(Clone, Debug)]
+pub(crate) enum Shadowed {
+    Builtin,
+    /// A `DEFINE PARAM`, which may live in another file.
+    Defined(crate::FileRange),
+    /// A binding in an enclosing scope of the same document.
+    Local(std::ops::Range<usize>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Finding {
+    Undefined { name: String, span: std::ops::Range<usize> },
+    Unused { name: String, span: std::ops::Range<usize> },
+    Shadowing { name: String, span: std::ops::Range<usize>, shadowed: Shadowed },
+}
+
+struct Analyser<'s> {
+    schema: &'s crate::schema::Schema,
+    scopes: Vec<Vec<Binding>>,
+    findings: Vec<Finding>,
+}
+
+/// Resolves every `$param` of `tokens`, reporting undefined uses, unused `LET` bindings and
+/// `LET` bindings that shadow another parameter.
+pub(crate) fn analyse(
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+) -> Vec<Finding> {
+    let mut analyser = Analyser { schema, scopes: Vec::new(), findings: Vec::new() };
+    analyser.block(tokens);
+    analyser.findings
+}
+
+impl Analyser<'_> {
+    fn block(&mut self, tokens: &[crate::lexer::Token<'_>]) {
+        self.scopes.push(Vec::new());
+        for range in crate::lexer::split_statements(tokens) {
+            self.statement(&tokens[range]);
+        }
+        self.pop_scope();
+    }
+
+    fn pop_scope(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        for binding in scope {
+            if binding.kind == BindingKind::Let && !binding.used {
+                self.findings.push(Finding::Unused { name: binding.name, span: binding.span });
+            }
+        }
+    }
+
+    fn statement(&mut self, tokens: &[crate::lexer::Token<'_>]) {
+        match tokens {
+            [first, param, rest @ ..]
+                if first.is_keyword("LET") && param.kind == crate::lexer::TokenKind::Param =>
+            {
+                let value = rest
+                    .iter()
+                    .position(|token| token.is_punct("="))
+                    .map_or(rest, |eq| &rest[eq + 1..]);
+                self.expression(value);
+                self.declare_let(param);
+            }
+            [first, param, rest @ ..]
+                if first.is_keyword("FOR") && param.kind == crate::lexer::TokenKind::Param =>
+            {
+                let Some(open) = rest.iter().position(|token| token.is_punct("{")) else {
+                    return self.expression(rest);
+                };
+                self.expression(&rest[..open]);
+                self.scopes.push(vec![binding(param, BindingKind::LoopVariable)]);
+                self.expression(&rest[open..]);
+                self.pop_scope();
+            }
+            [first, kind, ..] if first.is_keyword("DEFINE") && kind.is_keyword("FUNCTION") => {
+                let Some(open) = tokens.iter().position(|token| token.is_punct("(")) else {
+                    return;
+                };
+                let Some(close) = crate::lexer::matching_bracket(tokens, open) else {
+                    return;
+                };
+                let arguments = tokens[open + 1..close]
+                    .iter()
+                    .filter(|token| token.kind == crate::lexer::TokenKind::Param)
+                    .map(|token| binding(token, BindingKind::FunctionArgument))
+                    .collect();
+                self.scopes.push(arguments);
+                self.expression(&tokens[close + 1..]);
+                self.pop_scope();
+            }
+            [first, kind, _, rest @ ..]
+                if first.is_keyword("DEFINE") && kind.is_keyword("PARAM") =>
+            {
+                self.expression(rest);
+            }
+            _ => self.expression(tokens),
+        }
+    }
+
+    fn expression(&mut self, tokens: &[crate::lexer::Token<'_>]) {
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = &tokens[index];
+            if token.kind == crate::lexer::TokenKind::Param {
+                self.resolve(token);
+            } else if token.is_punct("{") {
+                if let Some(close) = crate::lexer::matching_bracket(tokens, index) {
+                    self.block(&tokens[index + 1..close]);
+                    index = close;
+                }
+            } else if token.is_punct("|") && is_closure_start(tokens, index) {
+                // Closure arguments are in scope for the rest of the expression.
+                let close = tokens[index + 1..].iter().position(|token| token.is_punct("|"));
+                if let Some(close) = close.map(|close| index + 1 + close) {
+                    let arguments = tokens[index + 1..close]
+                        .iter()
+                        .filter(|token| token.kind == crate::lexer::TokenKind::Param)
+                        .map(|token| binding(token, BindingKind::ClosureArgument))
+                        .collect();
+                    self.scopes.push(arguments);
+                    self.expression(&tokens[close + 1..]);
+                    self.pop_scope();
+                    return;
+                }
+            }
+            index += 1;
+        }
+    }
+
+    fn declare_let(&mut self, param: &crate::lexer::Token<'_>) {
+        let name = param.ident();
+        let enclosing = self.scopes.len().saturating_sub(1);
+        let local = self.scopes[..enclosing]
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|binding| binding.name == name)
+            .map(|binding| Shadowed::Local(binding.span.clone()));
+        let shadowed = local
+            .or_else(|| {
+                self.schema.params.get(name).map(|param| Shadowed::Defined(param.location.clone()))
+            })
+            .or_else(|| BUILTIN_PARAMS.contains(&name).then_some(Shadowed::Builtin));
+        if let Some(shadowed) = shadowed {
+            self.findings.push(Finding::Shadowing {
+                name: name.to_string(),
+                span: param.span(),
+                shadowed,
+            });
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(binding(param, BindingKind::Let));
+        }
+    }
+
+    fn resolve(&mut self, param: &crate::lexer::Token<'_>) {
+        let name = param.ident();
+        let binding = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|binding| binding.name == name);
+        if let Some(binding) = binding {
+            binding.used = true;
+        } else if !BUILTIN_PARAMS.contains(&name) && !self.schema.params.contains_key(name) {
+            self.findings.push(Finding::Undefined { name: name.to_string(), span: param.span() });
+        }
+    }
+}
+
+fn binding(token: &crate::lexer::Token<'_>, kind: BindingKind) -> Binding {
+    Binding { name: token.ident().to_string(), span: token.span(), kind, used: false }
+}
+
+/// A `|` starts a closure when it is followed by `$arg` or `|` rather than being an operator.
+fn is_closure_start(tokens: &[crate::lexer::Token<'_>], index: usize) -> bool {
+    let follows_operand = index > 0
+        && matches!(
+            tokens[index - 1].kind,
+            crate::lexer::TokenKind::Param
+                | crate::lexer::TokenKind::Number
+                | crate::lexer::TokenKind::String
+        );
+    let next = tokens.get(index + 1);
+    !follows_operand
+        && next
+            .is_some_and(|next| next.is_punct("|") || next.kind == crate::lexer::TokenKind::Param)
+}
+
+#[cfg(test)]
+mod tests {
+    /// Returns each finding as `undefined $x`, `unused $x` or `shadows $x` with the text it
+    /// shadows, in the order they were found.
+    fn findings(schema: &str, source: &str) -> Vec<String> {
+        let schema_uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        let changes = crate::schema::changes(&schema_uri, schema);
+        let schema = crate::schema::Schema::from_changes(&changes, crate::config::Dialect::V2);
+        let tokens = crate::lexer::tokenize(source);
+        super::analyse(&tokens, &schema)
+            .into_iter()
+            .map(|finding| match finding {
+                super::Finding::Undefined { name, .. } => format!("undefined ${}", name),
+                super::Finding::Unused { name, .. } => format!("unused ${}", name),
+                super::Finding::Shadowing { name, shadowed, .. } => {
+                    let shadowed = match shadowed {
+                        super::Shadowed::Builtin => String::from("builtin"),
+                        super::Shadowed::Defined(location) => {
+                            format!("defined at {}", location.range.start)
+                        }
+                        super::Shadowed::Local(span) => format!("local at {}", span.start),
+                    };
+                    format!("shadows ${} ({})", name, shadowed)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_undefined_params() {
+        assert_eq!(findings("", "SELECT * FROM person WHERE age > $min"), ["undefined $min"]);
+    }
+
+    #[test]
+    fn let_bindings_are_visible_to_later_statements() {
+        assert!(findings("", "LET $min = 18; SELECT * FROM person WHERE age > $min").is_empty());
+        assert_eq!(
+            findings("", "SELECT * FROM person WHERE age > $min; LET $min = 18; RETURN $min"),
+            ["undefined $min"]
+        );
+    }
+
+    #[test]
+    fn builtin_and_defined_params_are_known() {
+        let schema = "DEFINE PARAM $endpoint VALUE 'https://example.com';";
+        let source = "SELECT * FROM person WHERE id = $auth.id AND $this.age > 1; RETURN $endpoint";
+        assert!(findings(schema, source).is_empty());
+    }
+
+    #[test]
+    fn reports_unused_let_bindings() {
+        assert_eq!(
+            findings("", "LET $unused = 1; LET $used = 2; RETURN $used"),
+            ["unused $unused"]
+        );
+    }
+
+    #[test]
+    fn function_arguments_loop_variables_and_closures_are_bound() {
+        let source = "DEFINE FUNCTION fn::add($a: int, $b: int) { RETURN $a + $b; };
+            FOR $person IN (SELECT * FROM person) { UPDATE $person SET seen = true; };
+            RETURN [1, 2].map(|$n| $n * 2);";
+        assert!(findings("", source).is_empty());
+        assert_eq!(findings("", "FOR $x IN [1] { RETURN $x; }; RETURN $x"), ["undefined $x"]);
+    }
+
+    #[test]
+    fn blocks_scope_their_bindings() {
+        let source = "IF true { LET $inner = 1; RETURN $inner; }; RETURN $inner";
+        assert_eq!(findings("", source), ["undefined $inner"]);
+    }
+
+    #[test]
+    fn reports_shadowing_let_bindings() {
+        assert_eq!(findings("", "LET $value = 1; RETURN $value"), ["shadows $value (builtin)"]);
+
+        let schema = "DEFINE PARAM $limit VALUE 10;";
+        assert_eq!(
+            findings(schema, "LET $limit = 5; RETURN $limit"),
+            ["shadows $limit (defined at 13)"]
+        );
+
+        let source = "LET $x = 1; IF true { LET $x = 2; RETURN $x; }; RETURN $x";
+        assert_eq!(findings("", source), ["shadows $x (local at 4)"]);
+    }
+}
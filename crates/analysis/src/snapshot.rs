@@ -0,0 +1,228 @@
+//! JSON snapshots of the effective schema of a workspace, which can be loaded as a schema source
+//! where the `DEFINE` statements live elsewhere, e.g. in another repository.
+
+/// The version of the snapshot format, bumped on incompatible changes.
+const VERSION: u32 = 1;
+
+/// The tables, fields, indexes, functions and params of a schema, as written by the `snapshot`
+/// subcommand.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    version: u32,
+    tables: Vec<TableSnapshot>,
+    functions: Vec<FunctionSnapshot>,
+    /// The names of the parameters, excluding the `$`.
+    params: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct TableSnapshot {
+    name: String,
+    /// Whether only fields or indexes are defined on the table, and not the table itself.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    undefined: bool,
+    schemafull: bool,
+    kind: crate::schema::TableKind,
+    fields: Vec<FieldSnapshot>,
+    indexes: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct FieldSnapshot {
+    name: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct FunctionSnapshot {
+    name: String,
+    params: Vec<String>,
+    returns: Option<String>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(schema: &crate::schema::Schema) -> Self {
+        // Fields and indexes may be defined for tables that are not.
+        let mut names: Vec<&String> = schema.tables.keys().collect();
+        names.extend(schema.fields.keys().chain(schema.indexes.keys()));
+        names.sort();
+        names.dedup();
+
+        let tables = names
+            .into_iter()
+            .map(|name| {
+                let table = schema.tables.get(name);
+                TableSnapshot {
+                    name: name.clone(),
+                    undefined: table.is_none(),
+                    schemafull: table.is_some_and(|table| table.schemafull),
+                    kind: table.map_or(crate::schema::TableKind::Any, |table| table.kind.clone()),
+                    fields: schema
+                        .fields_of(name)
+                        .map(|field| FieldSnapshot {
+                            name: field.name.clone(),
+                            kind: field.kind.as_ref().map(ToString::to_string),
+                        })
+                        .collect(),
+                    indexes: schema
+                        .indexes
+                        .get(name)
+                        .into_iter()
+                        .flat_map(|indexes| indexes.keys().cloned())
+                        .collect(),
+                }
+            })
+            .collect();
+        let functions = schema
+            .functions
+            .values()
+            .map(|function| FunctionSnapshot {
+                name: function.name.clone(),
+                params: function.params.clone(),
+                returns: function.returns.as_ref().map(ToString::to_string),
+            })
+            .collect();
+        let params = schema.params.keys().cloned().collect();
+        Self { version: VERSION, tables, functions, params }
+    }
+
+    /// Reads the snapshot at `path`, returning why it could not be.
+    pub(crate) fn load(path: &std::path::Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let snapshot: Snapshot = serde_json::from_str(&content)
+            .map_err(|err| format!("Invalid snapshot {}: {}", path.display(), err))?;
+        if snapshot.version > VERSION {
+            return Err(format!(
+                "Snapshot {} has version {}, but at most {} is supported",
+                path.display(),
+                snapshot.version,
+                VERSION
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    /// Returns the definitions of the snapshot as changes to a schema, whose statements are not
+    /// known.
+    pub(crate) fn changes(self, uri: &url::Url) -> Vec<crate::schema::Change> {
+        self.definitions(uri)
+            .into_iter()
+            .map(|definition| crate::schema::Change::Define {
+                definition: Box::new(definition),
+                mode: crate::schema::DefineMode::Define,
+                statement: None,
+            })
+            .collect()
+    }
+
+    /// Returns the definitions of the snapshot, all of which are located at the start of `uri`.
+    fn definitions(self, uri: &url::Url) -> Vec<crate::schema::Definition> {
+        let location = crate::FileRange { file: uri.clone(), range: 0..0 };
+        let mut definitions = Vec::new();
+        for table in self.tables {
+            for field in table.fields {
+                definitions.push(crate::schema::Definition::Field(
+                    crate::schema::FieldDefinition {
+                        table: table.name.clone(),
+                        name: field.name,
+                        kind: field.kind.as_deref().and_then(crate::types::Kind::parse),
+                    },
+                ));
+            }
+            for index in table.indexes {
+                definitions.push(crate::schema::Definition::Index(
+                    crate::schema::IndexDefinition { table: table.name.clone(), name: index },
+                ));
+            }
+            if table.undefined {
+                continue;
+            }
+            definitions.push(crate::schema::Definition::Table(crate::schema::TableDefinition {
+                name: table.name,
+                schemafull: table.schemafull,
+                kind: table.kind,
+                location: location.clone(),
+                end: None,
+            }));
+        }
+        for function in self.functions {
+            definitions.push(crate::schema::Definition::Function(
+                crate::schema::FunctionDefinition {
+                    name: function.name,
+                    params: function.params,
+                    returns: function.returns.as_deref().and_then(crate::types::Kind::parse),
+                },
+            ));
+        }
+        for param in self.params {
+            definitions.push(crate::schema::Definition::Param(crate::schema::ParamDefinition {
+                name: param,
+                location: location.clone(),
+            }));
+        }
+        definitions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const SCHEMA: &str = "DEFINE TABLE person SCHEMAFULL;\n\
+                          DEFINE FIELD name ON person TYPE string;\n\
+                          DEFINE FIELD address.city ON person TYPE option<string>;\n\
+                          DEFINE FIELD tags ON person TYPE array<string>;\n\
+                          DEFINE INDEX person_name ON person FIELDS name UNIQUE;\n\
+                          DEFINE TABLE post SCHEMALESS;\n\
+                          DEFINE FIELD author ON post TYPE record<person>;\n\
+                          DEFINE TABLE likes TYPE RELATION FROM person TO post;\n\
+                          DEFINE FIELD at ON likes TYPE datetime;\n\
+                          DEFINE FIELD views ON comment TYPE int;\n\
+                          DEFINE FUNCTION fn::greet($name: string) -> string { RETURN $name; };\n\
+                          DEFINE PARAM $limit VALUE 10;\n";
+
+    #[test]
+    fn a_written_snapshot_reads_back_as_the_same_schema() {
+        let uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        let schema = crate::schema::Schema::from_changes(
+            &crate::schema::changes(&uri, SCHEMA),
+            crate::config::Dialect::V2,
+        );
+        let json = serde_json::to_string_pretty(&super::Snapshot::new(&schema)).unwrap();
+        let path = std::env::temp_dir()
+            .join(format!("surrealql-snapshot-round-trip-{}.json", std::process::id()));
+        std::fs::write(&path, json).unwrap();
+        let mut index = crate::schema::SchemaIndex::default();
+        let loaded = index.update_from_snapshot(&path);
+        std::fs::remove_file(&path).unwrap();
+        loaded.unwrap();
+        let loaded = index.schema();
+
+        let tables = |schema: &crate::schema::Schema| -> Vec<_> {
+            schema
+                .tables
+                .values()
+                .map(|table| (table.name.clone(), table.schemafull, table.kind.clone()))
+                .collect()
+        };
+        assert_eq!(tables(&loaded), tables(&schema));
+        assert_eq!(loaded.fields, schema.fields);
+        assert_eq!(loaded.indexes, schema.indexes);
+        assert_eq!(loaded.functions, schema.functions);
+        assert!(loaded.params.keys().eq(schema.params.keys()));
+    }
+
+    #[test]
+    fn newer_snapshots_are_rejected() {
+        let path = std::env::temp_dir()
+            .join(format!("surrealql-snapshot-version-{}.json", std::process::id()));
+        let json = r#"{ "version": 2, "tables": [], "functions": [], "params": [] }"#;
+        std::fs::write(&path, json).unwrap();
+        let loaded = super::Snapshot::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.unwrap_err(),
+            format!("Snapshot {} has version 2, but at most 1 is supported", path.display())
+        );
+    }
+}
@@ -0,0 +1,808 @@
+//! Types for application code generated from the `DEFINE TABLE` and `DEFINE FIELD` statements of
+//! a schema. Each table becomes a type whose fields have the types that SurrealDB returns them as
+//! in JSON: datetimes, durations, uuids and record ids are strings, and geometries GeoJSON.
+
+/// The language types are generated in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TypeLanguage {
+    /// An `interface` per table.
+    #[default]
+    TypeScript,
+    /// A `struct` per table that derives `serde::Serialize` and `serde::Deserialize`.
+    Rust,
+    /// A JSON Schema (draft 2020-12) document with a definition per table under `$defs`.
+    JsonSchema,
+}
+
+/// The fields defined at and below a field path, e.g. `address` with `address.city`.
+#[derive(Clone, Debug, Default)]
+struct Shape {
+    /// The declared `TYPE`, if any.
+    kind: Option<crate::types::Kind>,
+    /// The fields of the object, e.g. `city` of `address`.
+    fields: std::collections::BTreeMap<String, Shape>,
+    /// The elements of the array, e.g. as defined by `tags[*]` or `tags.*`.
+    element: Option<Box<Shape>>,
+}
+
+/// The shape of a field without nested fields.
+static EMPTY: Shape =
+    Shape { kind: None, fields: std::collections::BTreeMap::new(), element: None };
+
+impl Shape {
+    /// Returns the declared kind, or else `object` or `array` when there are nested fields or
+    /// elements, and `any` otherwise.
+    fn kind(&self) -> crate::types::Kind {
+        match &self.kind {
+            Some(kind) => kind.clone(),
+            None if !self.fields.is_empty() => crate::types::Kind::Object,
+            None if self.element.is_some() => {
+                crate::types::Kind::Array(Box::new(crate::types::Kind::Any), None)
+            }
+            None => crate::types::Kind::Any,
+        }
+    }
+
+    /// Returns the kind and shape of the elements of an array of `kind`, preferring the kind
+    /// declared for the elements themselves.
+    fn element(&self, kind: &crate::types::Kind) -> (crate::types::Kind, &Shape) {
+        match self.element.as_deref() {
+            Some(element) => (element.kind.clone().unwrap_or_else(|| kind.clone()), element),
+            None => (kind.clone(), &EMPTY),
+        }
+    }
+
+    fn fields(&self) -> Vec<(&str, &Shape)> {
+        self.fields.iter().map(|(name, shape)| (name.as_str(), shape)).collect()
+    }
+}
+
+/// A table and its top-level fields, starting with `id` and then `in` and `out` for relations.
+struct Table {
+    name: String,
+    schemafull: bool,
+    fields: Vec<(String, Shape)>,
+}
+
+impl Table {
+    fn fields(&self) -> Vec<(&str, &Shape)> {
+        self.fields.iter().map(|(name, shape)| (name.as_str(), shape)).collect()
+    }
+}
+
+/// Returns the types of the tables of `schema` in `language`.
+pub(crate) fn types(schema: &crate::schema::Schema, language: TypeLanguage) -> String {
+    let tables = tables(schema);
+    match language {
+        TypeLanguage::TypeScript => typescript(&tables),
+        TypeLanguage::Rust => rust(&tables),
+        TypeLanguage::JsonSchema => json_schema(&tables),
+    }
+}
+
+fn tables(schema: &crate::schema::Schema) -> Vec<Table> {
+    let mut names: Vec<&String> = schema.tables.keys().chain(schema.fields.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| {
+            let mut root = Shape::default();
+            for field in schema.fields_of(name) {
+                let mut shape = &mut root;
+                for segment in path(&field.name) {
+                    shape = match segment {
+                        Some(name) => shape.fields.entry(name).or_default(),
+                        None => shape.element.get_or_insert_with(Default::default),
+                    };
+                }
+                shape.kind = field.kind.clone();
+            }
+
+            // Every record has an `id`, and every edge its `in` and `out` records.
+            let definition = schema.tables.get(name);
+            let mut implicit = vec![(String::from("id"), vec![name.clone()])];
+            if let Some(crate::schema::TableKind::Relation { from, to }) =
+                definition.map(|table| &table.kind)
+            {
+                implicit.push((String::from("in"), from.clone()));
+                implicit.push((String::from("out"), to.clone()));
+            }
+            let mut fields = Vec::new();
+            for (field, tables) in implicit {
+                let mut shape = root.fields.remove(&field).unwrap_or_default();
+                shape.kind.get_or_insert(crate::types::Kind::Record(tables));
+                fields.push((field, shape));
+            }
+            fields.extend(root.fields);
+            Table {
+                name: name.clone(),
+                schemafull: definition.is_some_and(|table| table.schemafull),
+                fields,
+            }
+        })
+        .collect()
+}
+
+/// Splits a field path into field names, and `None` for the elements of an array.
+fn path(name: &str) -> Vec<Option<String>> {
+    let mut segments = Vec::new();
+    for part in name.split('.') {
+        let mut part = part;
+        let mut elements = 0;
+        while let Some(rest) = part.strip_suffix("[*]") {
+            part = rest;
+            elements += 1;
+        }
+        match part {
+            "*" => segments.push(None),
+            part => segments.push(Some(part.trim_matches('`').to_string())),
+        }
+        segments.extend(std::iter::repeat(None).take(elements));
+    }
+    segments
+}
+
+/// Returns whether a field of `kind` may be left out, and the kind of its value when it is not.
+/// The `id` of a record is optional too, as SurrealDB generates one when it is left out.
+fn optional(field: &str, kind: &crate::types::Kind) -> (bool, crate::types::Kind) {
+    match kind {
+        crate::types::Kind::Option(kind) => (true, (**kind).clone()),
+        crate::types::Kind::Either(kinds) if kinds.contains(&crate::types::Kind::None) => {
+            let mut kinds: Vec<crate::types::Kind> =
+                kinds.iter().filter(|kind| **kind != crate::types::Kind::None).cloned().collect();
+            let kind =
+                if kinds.len() == 1 { kinds.remove(0) } else { crate::types::Kind::Either(kinds) };
+            (true, kind)
+        }
+        kind => (field == "id", kind.clone()),
+    }
+}
+
+/// Returns the value of a literal type, e.g. `active` for `"active"`, or `None` when it is not a
+/// string or a number.
+fn literal(text: &str) -> Option<serde_json::Value> {
+    let quote = text.chars().next()?;
+    if matches!(quote, '"' | '\'') {
+        let inner = text.get(1..text.len().saturating_sub(1))?;
+        let unescaped = inner.replace(&format!("\\{}", quote), &quote.to_string());
+        return Some(serde_json::Value::String(unescaped.replace("\\\\", "\\")));
+    }
+    let number = text.trim_end_matches("dec").trim_end_matches('f');
+    serde_json::from_str::<serde_json::Number>(number).ok().map(serde_json::Value::Number)
+}
+
+/// Returns `name`, or else the first of `name2`, `name3` and so on, with `separator` before the
+/// number, that is not taken. The numbered names of raw identifiers are not raw, e.g. `type_2`.
+fn numbered(name: &str, separator: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    if !is_taken(name) {
+        return name.to_string();
+    }
+    let name = name.trim_start_matches("r#");
+    (2..)
+        .map(|number| format!("{}{}{}", name, separator, number))
+        .find(|name| !is_taken(name))
+        .unwrap_or_default()
+}
+
+/// Returns the names of the types of `tables`, numbering those that are equal in PascalCase, e.g.
+/// `blog_post` and `blogPost`, after any of `names`. The names are added to `names`.
+fn type_names(tables: &[Table], names: &mut std::collections::HashSet<String>) -> Vec<String> {
+    tables
+        .iter()
+        .map(|table| {
+            let name = numbered(&pascal_case(&table.name), "", |name| names.contains(name));
+            names.insert(name.clone());
+            name
+        })
+        .collect()
+}
+
+/// Returns `name` in PascalCase, e.g. `BlogPost` for `blog_post`.
+fn pascal_case(name: &str) -> String {
+    let mut pascal = String::new();
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            pascal.extend(first.to_uppercase());
+            pascal.extend(chars);
+        }
+    }
+    if pascal.is_empty() || pascal.starts_with(|c: char| c.is_ascii_digit()) {
+        pascal.insert(0, 'T');
+    }
+    pascal
+}
+
+/// The GeoJSON `type` and TypeScript coordinates of each kind of geometry.
+const GEOMETRIES: &[(&str, &str, &str)] = &[
+    ("point", "Point", "[number, number]"),
+    ("line", "LineString", "[number, number][]"),
+    ("polygon", "Polygon", "[number, number][][]"),
+    ("multipoint", "MultiPoint", "[number, number][]"),
+    ("multiline", "MultiLineString", "[number, number][][]"),
+    ("multipolygon", "MultiPolygon", "[number, number][][][]"),
+];
+
+fn typescript(tables: &[Table]) -> String {
+    let mut out = String::from("// Generated from the SurrealQL schema. Do not edit.\n");
+    let names = type_names(tables, &mut std::collections::HashSet::new());
+    for (table, name) in tables.iter().zip(names) {
+        out += &format!("\n/** The `{}` table. */\n", table.name);
+        out += &format!("export interface {} ", name);
+        out += &typescript_object(&table.fields(), !table.schemafull, 0);
+        out += "\n";
+    }
+    out
+}
+
+/// Returns an object type with `fields`, and an index signature when it may have others.
+fn typescript_object(fields: &[(&str, &Shape)], is_open: bool, depth: usize) -> String {
+    let indent = "  ".repeat(depth + 1);
+    let mut out = String::from("{\n");
+    for (name, shape) in fields {
+        let (is_optional, kind) = optional(name, &shape.kind());
+        out += &format!("{}/** `{}` */\n", indent, shape.kind());
+        out += &format!(
+            "{}{}{}: {};\n",
+            indent,
+            typescript_key(name),
+            if is_optional { "?" } else { "" },
+            typescript_type(&kind, shape, depth + 1)
+        );
+    }
+    if is_open {
+        out += &format!("{}[field: string]: unknown;\n", indent);
+    }
+    out + &"  ".repeat(depth) + "}"
+}
+
+fn typescript_key(name: &str) -> String {
+    let is_identifier = !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier && !name.is_empty() {
+        name.to_string()
+    } else {
+        serde_json::Value::String(name.to_string()).to_string()
+    }
+}
+
+fn typescript_type(kind: &crate::types::Kind, shape: &Shape, depth: usize) -> String {
+    let element = |kind: &crate::types::Kind| {
+        let (kind, element) = shape.element(kind);
+        let element_type = typescript_type(&kind, element, depth);
+        if element_type.contains(" | ") && !element_type.starts_with('{') {
+            format!("({})[]", element_type)
+        } else {
+            format!("{}[]", element_type)
+        }
+    };
+    match kind {
+        crate::types::Kind::Any => String::from("unknown"),
+        crate::types::Kind::None => String::from("undefined"),
+        crate::types::Kind::Null => String::from("null"),
+        crate::types::Kind::Bool => String::from("boolean"),
+        crate::types::Kind::Int
+        | crate::types::Kind::Float
+        | crate::types::Kind::Decimal
+        | crate::types::Kind::Number => String::from("number"),
+        crate::types::Kind::String
+        | crate::types::Kind::Datetime
+        | crate::types::Kind::Duration
+        | crate::types::Kind::Uuid => String::from("string"),
+        crate::types::Kind::Bytes => String::from("number[]"),
+        crate::types::Kind::Object if shape.fields.is_empty() => {
+            String::from("Record<string, unknown>")
+        }
+        crate::types::Kind::Object => typescript_object(&shape.fields(), false, depth),
+        crate::types::Kind::Geometry(kinds) => {
+            let geometries: Vec<String> = GEOMETRIES
+                .iter()
+                .filter(|(name, _, _)| kinds.is_empty() || kinds.iter().any(|kind| kind == name))
+                .map(|(_, kind, coordinates)| {
+                    format!("{{ type: \"{}\"; coordinates: {} }}", kind, coordinates)
+                })
+                .collect();
+            if geometries.is_empty() {
+                String::from("{ type: string; [field: string]: unknown }")
+            } else {
+                geometries.join(" | ")
+            }
+        }
+        crate::types::Kind::Option(kind) => {
+            format!("{} | undefined", typescript_type(kind, shape, depth))
+        }
+        crate::types::Kind::Array(kind, _) | crate::types::Kind::Set(kind, _) => element(kind),
+        crate::types::Kind::Record(tables) if tables.is_empty() => String::from("string"),
+        crate::types::Kind::Record(tables) => {
+            let ids: Vec<String> =
+                tables.iter().map(|table| format!("`{}:${{string}}`", table)).collect();
+            ids.join(" | ")
+        }
+        crate::types::Kind::Either(kinds) => {
+            let kinds: Vec<String> =
+                kinds.iter().map(|kind| typescript_type(kind, shape, depth)).collect();
+            kinds.join(" | ")
+        }
+        crate::types::Kind::Literal(text) => {
+            literal(text).map_or(String::from("unknown"), |value| value.to_string())
+        }
+    }
+}
+
+/// Generates Rust types, collecting the structs and enums of nested objects and literal unions
+/// as they are named.
+#[derive(Default)]
+struct RustTypes {
+    items: Vec<String>,
+    /// The names of the tables, of `Geometry` and of the items added so far.
+    names: std::collections::HashSet<String>,
+    uses_geometry: bool,
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+fn rust(tables: &[Table]) -> String {
+    let mut types = RustTypes::default();
+    types.names.insert(String::from("Geometry"));
+    // The tables are named first, so that nested objects such as `meta` of `post` do not take the
+    // name of a table such as `post_meta`.
+    let names = type_names(tables, &mut types.names);
+    for (table, name) in tables.iter().zip(names) {
+        let doc = format!("The `{}` table.", table.name);
+        types.structure(&name, &doc, &table.fields(), !table.schemafull);
+    }
+    let mut out = String::from("// Generated from the SurrealQL schema. Do not edit.\n");
+    if types.uses_geometry {
+        out += "\n/// A GeoJSON geometry.\n";
+        out += "#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]\n";
+        out += "pub struct Geometry<C> {\n";
+        out += "    #[serde(rename = \"type\")]\n    pub kind: String,\n";
+        out += "    pub coordinates: C,\n}\n";
+    }
+    for item in &types.items {
+        out += "\n";
+        out += item;
+    }
+    out
+}
+
+impl RustTypes {
+    /// Returns `hint`, numbered when a table or another item already has that name.
+    fn name(&mut self, hint: &str) -> String {
+        let name = numbered(hint, "", |name| self.names.contains(name));
+        self.names.insert(name.clone());
+        name
+    }
+
+    /// Adds a struct called `name` with `fields`, and a map of the other fields when it may have
+    /// others. Fields whose names are already identifiers keep them, and the others are numbered
+    /// when they would be the same, e.g. `user_name` and `user_name_2` for `userName`.
+    fn structure(&mut self, name: &str, doc: &str, fields: &[(&str, &Shape)], is_open: bool) {
+        let exact: std::collections::HashSet<String> = fields
+            .iter()
+            .map(|(field, _)| rust_field(field))
+            .filter(|(_, is_renamed)| !is_renamed)
+            .map(|(ident, _)| ident)
+            .collect();
+        let mut idents = std::collections::HashSet::new();
+        let mut body = String::new();
+        for (field, shape) in fields {
+            let (is_optional, kind) = optional(field, &shape.kind());
+            let hint = format!("{}{}", name, pascal_case(field));
+            let mut rust_type = self.rust_type(&kind, shape, &hint);
+            body += &format!("    /// `{}`\n", shape.kind());
+            let (mut ident, is_renamed) = rust_field(field);
+            if is_renamed {
+                ident =
+                    numbered(&ident, "_", |ident| exact.contains(ident) || idents.contains(ident));
+            }
+            idents.insert(ident.clone());
+            if ident.trim_start_matches("r#") != *field {
+                body += &format!("    #[serde(rename = {})]\n", serde_json::json!(field));
+            }
+            if is_optional {
+                // SurrealDB accepts `NONE` for an `option<T>`, but not `null`.
+                body += "    #[serde(skip_serializing_if = \"Option::is_none\")]\n";
+                rust_type = format!("Option<{}>", rust_type);
+            }
+            body += &format!("    pub {}: {},\n", ident, rust_type);
+        }
+        if is_open {
+            let other = numbered("other", "_", |ident| idents.contains(ident));
+            body += "    #[serde(flatten)]\n";
+            body += &format!("    pub {}: serde_json::Map<String, serde_json::Value>,\n", other);
+        }
+        self.items.push(format!(
+            "/// {}\n#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]\n\
+             pub struct {} {{\n{}}}\n",
+            doc, name, body
+        ));
+    }
+
+    /// Returns the Rust type of `kind`, naming the types it adds after `hint`.
+    fn rust_type(&mut self, kind: &crate::types::Kind, shape: &Shape, hint: &str) -> String {
+        match kind {
+            crate::types::Kind::Any | crate::types::Kind::Literal(_) => {
+                String::from("serde_json::Value")
+            }
+            crate::types::Kind::None | crate::types::Kind::Null => String::from("()"),
+            crate::types::Kind::Bool => String::from("bool"),
+            crate::types::Kind::Int => String::from("i64"),
+            crate::types::Kind::Float
+            | crate::types::Kind::Decimal
+            | crate::types::Kind::Number => String::from("f64"),
+            crate::types::Kind::String
+            | crate::types::Kind::Datetime
+            | crate::types::Kind::Duration
+            | crate::types::Kind::Uuid
+            | crate::types::Kind::Record(_) => String::from("String"),
+            crate::types::Kind::Bytes => String::from("Vec<u8>"),
+            crate::types::Kind::Object if shape.fields.is_empty() => {
+                String::from("serde_json::Map<String, serde_json::Value>")
+            }
+            crate::types::Kind::Object => {
+                let name = self.name(hint);
+                self.structure(&name, "An object of fields.", &shape.fields(), false);
+                name
+            }
+            crate::types::Kind::Geometry(kinds) => {
+                let coordinates = match kinds.as_slice() {
+                    [kind] => match kind.as_str() {
+                        "point" => Some("(f64, f64)"),
+                        "line" | "multipoint" => Some("Vec<(f64, f64)>"),
+                        "polygon" | "multiline" => Some("Vec<Vec<(f64, f64)>>"),
+                        "multipolygon" => Some("Vec<Vec<Vec<(f64, f64)>>>"),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match coordinates {
+                    Some(coordinates) => {
+                        self.uses_geometry = true;
+                        format!("Geometry<{}>", coordinates)
+                    }
+                    None => String::from("serde_json::Value"),
+                }
+            }
+            crate::types::Kind::Option(kind) => {
+                format!("Option<{}>", self.rust_type(kind, shape, hint))
+            }
+            crate::types::Kind::Array(kind, _) | crate::types::Kind::Set(kind, _) => {
+                let (kind, element) = shape.element(kind);
+                format!("Vec<{}>", self.rust_type(&kind, element, &format!("{}Item", hint)))
+            }
+            crate::types::Kind::Either(kinds) => {
+                let values: Vec<&crate::types::Kind> = kinds
+                    .iter()
+                    .filter(|kind| {
+                        !matches!(kind, crate::types::Kind::None | crate::types::Kind::Null)
+                    })
+                    .collect();
+                let rust_type = match values.as_slice() {
+                    [] => String::from("()"),
+                    [kind] => self.rust_type(kind, shape, hint),
+                    kinds => self.literal_enum(kinds, hint),
+                };
+                if values.len() < kinds.len() {
+                    format!("Option<{}>", rust_type)
+                } else {
+                    rust_type
+                }
+            }
+        }
+    }
+
+    /// Adds an enum named after `hint` for a union of string literals, or returns
+    /// `serde_json::Value` for any other union. Values that are the same in PascalCase, e.g.
+    /// `active` and `Active`, are numbered.
+    fn literal_enum(&mut self, kinds: &[&crate::types::Kind], hint: &str) -> String {
+        let mut values = Vec::new();
+        for kind in kinds {
+            let crate::types::Kind::Literal(text) = kind else {
+                return String::from("serde_json::Value");
+            };
+            let Some(serde_json::Value::String(value)) = literal(text) else {
+                return String::from("serde_json::Value");
+            };
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        let mut names = std::collections::HashSet::new();
+        let mut variants = String::new();
+        for value in values {
+            let variant = numbered(&pascal_case(&value), "", |name| names.contains(name));
+            names.insert(variant.clone());
+            variants += &format!(
+                "    #[serde(rename = {})]\n    {},\n",
+                serde_json::Value::String(value),
+                variant
+            );
+        }
+        let name = self.name(hint);
+        self.items.push(format!(
+            "/// One of the literal values of a field.\n\
+             #[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]\n\
+             pub enum {} {{\n{}}}\n",
+            name, variants
+        ));
+        name
+    }
+}
+
+/// Returns the Rust identifier of a field, and whether it differs from the field's name.
+fn rust_field(name: &str) -> (String, bool) {
+    let mut ident = String::new();
+    let mut previous = '_';
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && (previous.is_ascii_lowercase() || previous.is_ascii_digit()) {
+            ident.push('_');
+        }
+        ident.push(if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' });
+        previous = c;
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    let is_renamed = ident != name;
+    if matches!(ident.as_str(), "self" | "super" | "crate") {
+        return (ident + "_", true);
+    }
+    if RUST_KEYWORDS.contains(&ident.as_str()) {
+        return (format!("r#{}", ident), is_renamed);
+    }
+    (ident, is_renamed)
+}
+
+fn json_schema(tables: &[Table]) -> String {
+    let definitions: serde_json::Map<String, serde_json::Value> = tables
+        .iter()
+        .map(|table| {
+            let mut schema = json_object(&table.fields(), !table.schemafull);
+            schema["title"] = serde_json::Value::String(table.name.clone());
+            (table.name.clone(), schema)
+        })
+        .collect();
+    let document = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$defs": definitions,
+    });
+    serde_json::to_string_pretty(&document).unwrap_or_default() + "\n"
+}
+
+fn json_object(fields: &[(&str, &Shape)], is_open: bool) -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    for (name, shape) in fields {
+        let (is_optional, kind) = optional(name, &shape.kind());
+        let mut schema = json_type(&kind, shape);
+        if let Some(schema) = schema.as_object_mut() {
+            schema.insert(String::from("description"), serde_json::json!(shape.kind().to_string()));
+        }
+        properties.insert(name.to_string(), schema);
+        if !is_optional {
+            required.push(name.to_string());
+        }
+    }
+    serde_json::json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": is_open,
+    })
+}
+
+fn json_type(kind: &crate::types::Kind, shape: &Shape) -> serde_json::Value {
+    match kind {
+        crate::types::Kind::Any => serde_json::json!({}),
+        crate::types::Kind::None | crate::types::Kind::Null => {
+            serde_json::json!({ "type": "null" })
+        }
+        crate::types::Kind::Bool => serde_json::json!({ "type": "boolean" }),
+        crate::types::Kind::Int => serde_json::json!({ "type": "integer" }),
+        crate::types::Kind::Float | crate::types::Kind::Decimal | crate::types::Kind::Number => {
+            serde_json::json!({ "type": "number" })
+        }
+        crate::types::Kind::String | crate::types::Kind::Duration => {
+            serde_json::json!({ "type": "string" })
+        }
+        crate::types::Kind::Datetime => {
+            serde_json::json!({ "type": "string", "format": "date-time" })
+        }
+        crate::types::Kind::Uuid => serde_json::json!({ "type": "string", "format": "uuid" }),
+        crate::types::Kind::Bytes => serde_json::json!({
+            "type": "array",
+            "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+        }),
+        crate::types::Kind::Object if shape.fields.is_empty() => {
+            serde_json::json!({ "type": "object" })
+        }
+        crate::types::Kind::Object => json_object(&shape.fields(), false),
+        crate::types::Kind::Geometry(kinds) => {
+            let geometries: Vec<serde_json::Value> = GEOMETRIES
+                .iter()
+                .filter(|(name, _, _)| kinds.is_empty() || kinds.iter().any(|kind| kind == name))
+                .map(|(_, kind, _)| {
+                    serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "type": { "const": kind },
+                            "coordinates": { "type": "array" },
+                        },
+                        "required": ["type", "coordinates"],
+                    })
+                })
+                .collect();
+            match <[serde_json::Value; 1]>::try_from(geometries) {
+                Ok([geometry]) => geometry,
+                Err(geometries) => serde_json::json!({ "anyOf": geometries }),
+            }
+        }
+        crate::types::Kind::Option(kind) => serde_json::json!({
+            "anyOf": [json_type(kind, shape), { "type": "null" }],
+        }),
+        crate::types::Kind::Array(item, size) | crate::types::Kind::Set(item, size) => {
+            let (item, element) = shape.element(item);
+            let mut schema = serde_json::json!({
+                "type": "array",
+                "items": json_type(&item, element),
+            });
+            if let Some(size) = size {
+                schema["maxItems"] = serde_json::json!(size);
+            }
+            if matches!(kind, crate::types::Kind::Set(..)) {
+                schema["uniqueItems"] = serde_json::Value::Bool(true);
+            }
+            schema
+        }
+        crate::types::Kind::Record(tables) if tables.is_empty() => {
+            serde_json::json!({ "type": "string" })
+        }
+        crate::types::Kind::Record(tables) => serde_json::json!({
+            "type": "string",
+            "pattern": format!("^({}):", tables.join("|")),
+        }),
+        crate::types::Kind::Either(kinds) => serde_json::json!({
+            "anyOf": kinds.iter().map(|kind| json_type(kind, shape)).collect::<Vec<_>>(),
+        }),
+        crate::types::Kind::Literal(text) => literal(text)
+            .map_or(serde_json::json!({}), |value| serde_json::json!({ "const": value })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn types(text: &str, language: super::TypeLanguage) -> String {
+        let uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        let schema = crate::schema::Schema::from_changes(
+            &crate::schema::changes(&uri, text),
+            crate::config::Dialect::V2,
+        );
+        super::types(&schema, language)
+    }
+
+    /// Returns the names of the structs and enums of `file`, and the fields or variants of each.
+    fn items(file: &syn::File) -> Vec<(String, Vec<String>)> {
+        file.items
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Struct(item) => Some((
+                    item.ident.to_string(),
+                    item.fields
+                        .iter()
+                        .filter_map(|field| field.ident.as_ref().map(ToString::to_string))
+                        .collect(),
+                )),
+                syn::Item::Enum(item) => Some((
+                    item.ident.to_string(),
+                    item.variants.iter().map(|variant| variant.ident.to_string()).collect(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    const TYPES: &str = "\
+        DEFINE TABLE person SCHEMAFULL;
+        DEFINE FIELD nickname ON person TYPE option<string>;
+        DEFINE FIELD best_friend ON person TYPE record<person>;
+        DEFINE FIELD tags ON person TYPE set<string>;
+        DEFINE FIELD home ON person TYPE geometry<point>;
+        DEFINE FIELD status ON person TYPE \"active\" | \"away\";";
+
+    #[test]
+    fn typescript_types() {
+        let out = types(TYPES, super::TypeLanguage::TypeScript);
+        for line in [
+            "export interface Person {",
+            "  nickname?: string;",
+            "  best_friend: `person:${string}`;",
+            "  tags: string[];",
+            "  home: { type: \"Point\"; coordinates: [number, number] };",
+            "  status: \"active\" | \"away\";",
+        ] {
+            assert!(out.lines().any(|l| l == line), "{} is not in\n{}", line, out);
+        }
+    }
+
+    #[test]
+    fn rust_types() {
+        let out = types(TYPES, super::TypeLanguage::Rust);
+        for line in [
+            "pub struct Geometry<C> {",
+            "pub struct Person {",
+            "    pub nickname: Option<String>,",
+            "    pub best_friend: String,",
+            "    pub tags: Vec<String>,",
+            "    pub home: Geometry<(f64, f64)>,",
+            "    pub status: PersonStatus,",
+            "pub enum PersonStatus {",
+            "    #[serde(rename = \"active\")]",
+            "    Active,",
+        ] {
+            assert!(out.lines().any(|l| l == line), "{} is not in\n{}", line, out);
+        }
+    }
+
+    #[test]
+    fn json_schema_types() {
+        let out = types(TYPES, super::TypeLanguage::JsonSchema);
+        let document: serde_json::Value = serde_json::from_str(&out).unwrap();
+        let person = &document["$defs"]["person"];
+        let properties = &person["properties"];
+        assert_eq!(
+            person["required"],
+            serde_json::json!(["best_friend", "home", "status", "tags"])
+        );
+        assert_eq!(properties["nickname"]["type"], "string");
+        assert_eq!(properties["best_friend"]["pattern"], "^(person):");
+        assert_eq!(properties["tags"]["items"]["type"], "string");
+        assert_eq!(properties["tags"]["uniqueItems"], true);
+        assert_eq!(properties["home"]["properties"]["type"]["const"], "Point");
+        assert_eq!(
+            properties["status"]["anyOf"],
+            serde_json::json!([{ "const": "active" }, { "const": "away" }])
+        );
+    }
+
+    #[test]
+    fn rust_names_do_not_collide() {
+        let out = types(
+            "DEFINE TABLE post SCHEMAFULL;
+            DEFINE FIELD meta ON post TYPE object;
+            DEFINE FIELD meta.at ON post TYPE datetime;
+            DEFINE TABLE post_meta SCHEMAFULL;
+            DEFINE TABLE geometry SCHEMAFULL;
+            DEFINE FIELD at ON geometry TYPE geometry<point>;
+            DEFINE TABLE user SCHEMALESS;
+            DEFINE FIELD userName ON user TYPE string;
+            DEFINE FIELD user_name ON user TYPE string;
+            DEFINE FIELD other ON user TYPE string;
+            DEFINE FIELD status ON user TYPE \"active\" | \"Active\" | \"active\";",
+            super::TypeLanguage::Rust,
+        );
+        let file = syn::parse_file(&out).unwrap_or_else(|err| panic!("{}\n{}", err, out));
+        let items = items(&file);
+        let names: Vec<&str> = items.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Geometry", "Geometry2", "PostMeta2", "Post", "PostMeta", "UserStatus", "User"]
+        );
+        let members = |name: &str| &items.iter().find(|item| item.0 == name).unwrap().1;
+        assert_eq!(members("Post"), &["id", "meta"]);
+        assert_eq!(
+            members("User"),
+            &["id", "other", "status", "user_name_2", "user_name", "other_2"]
+        );
+        assert_eq!(members("UserStatus"), &["Active", "Active2"]);
+        assert!(out.contains("    #[serde(rename = \"userName\")]\n    pub user_name_2: String,"));
+        assert!(out.contains("    pub meta: PostMeta2,"));
+    }
+}
@@ -0,0 +1,696 @@
+//! SurrealQL types and a best-effort type inference for expressions.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Any,
+    None,
+    Null,
+    Bool,
+    Int,
+    Float,
+    Decimal,
+    Number,
+    String,
+    Datetime,
+    Duration,
+    Uuid,
+    Bytes,
+    Object,
+    Geometry(Vec<String>),
+    Option(Box<Kind>),
+    Array(Box<Kind>, Option<u64>),
+    Set(Box<Kind>, Option<u64>),
+    /// A record link to any of the given tables, or to any table when empty.
+    Record(Vec<String>),
+    Either(Vec<Kind>),
+    /// A literal type such as `"active"` or `42`, holding its source text.
+    Literal(String),
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let with_size =
+            |f: &mut std::fmt::Formatter<'_>, name, kind: &Kind, size: &Option<u64>| match (
+                kind, size,
+            ) {
+                (Kind::Any, None) => write!(f, "{}", name),
+                (kind, None) => write!(f, "{}<{}>", name, kind),
+                (kind, Some(size)) => write!(f, "{}<{}, {}>", name, kind, size),
+            };
+        match self {
+            Kind::Any => write!(f, "any"),
+            Kind::None => write!(f, "none"),
+            Kind::Null => write!(f, "null"),
+            Kind::Bool => write!(f, "bool"),
+            Kind::Int => write!(f, "int"),
+            Kind::Float => write!(f, "float"),
+            Kind::Decimal => write!(f, "decimal"),
+            Kind::Number => write!(f, "number"),
+            Kind::String => write!(f, "string"),
+            Kind::Datetime => write!(f, "datetime"),
+            Kind::Duration => write!(f, "duration"),
+            Kind::Uuid => write!(f, "uuid"),
+            Kind::Bytes => write!(f, "bytes"),
+            Kind::Object => write!(f, "object"),
+            Kind::Geometry(kinds) if kinds.is_empty() => write!(f, "geometry"),
+            Kind::Geometry(kinds) => write!(f, "geometry<{}>", kinds.join(" | ")),
+            Kind::Option(kind) => write!(f, "option<{}>", kind),
+            Kind::Array(kind, size) => with_size(f, "array", kind, size),
+            Kind::Set(kind, size) => with_size(f, "set", kind, size),
+            Kind::Record(tables) if tables.is_empty() => write!(f, "record"),
+            Kind::Record(tables) => write!(f, "record<{}>", tables.join(" | ")),
+            Kind::Either(kinds) => {
+                let kinds: Vec<String> = kinds.iter().map(Kind::to_string).collect();
+                write!(f, "{}", kinds.join(" | "))
+            }
+            Kind::Literal(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl Kind {
+    /// Parses the source text of a type, e.g. `option<array<record<user>, 10>>`.
+    pub(crate) fn parse(text: &str) -> Option<Kind> {
+        let tokens = crate::lexer::tokenize(text);
+        let (kind, rest) = parse_union(&tokens)?;
+        rest.is_empty().then_some(kind)
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Kind::Int | Kind::Float | Kind::Decimal | Kind::Number)
+    }
+
+    /// Returns whether a value of this kind can be stored in a field of kind `target`.
+    ///
+    /// With the 1.x dialect strings are implicitly cast to datetimes, uuids and record ids.
+    pub(crate) fn is_assignable_to(&self, target: &Kind, dialect: crate::config::Dialect) -> bool {
+        match (self, target) {
+            (_, Kind::Any) | (Kind::Any, _) => true,
+            (Kind::Either(kinds), target) => {
+                kinds.iter().all(|kind| kind.is_assignable_to(target, dialect))
+            }
+            (value, Kind::Either(kinds)) => {
+                kinds.iter().any(|kind| value.is_assignable_to(kind, dialect))
+            }
+            (Kind::None, Kind::Option(_)) => true,
+            (Kind::Option(value), Kind::Option(target)) => value.is_assignable_to(target, dialect),
+            (value, Kind::Option(target)) => value.is_assignable_to(target, dialect),
+            (Kind::Int, Kind::Int | Kind::Float | Kind::Decimal | Kind::Number) => true,
+            (Kind::Float, Kind::Float | Kind::Decimal | Kind::Number) => true,
+            (Kind::Decimal, Kind::Decimal | Kind::Number) => true,
+            (Kind::Number, target) => target.is_numeric(),
+            (Kind::String, Kind::Datetime | Kind::Uuid | Kind::Record(_)) => {
+                dialect == crate::config::Dialect::V1
+            }
+            (Kind::Record(tables), Kind::Record(targets)) => {
+                targets.is_empty()
+                    || (!tables.is_empty() && tables.iter().all(|table| targets.contains(table)))
+            }
+            (Kind::Array(kind, size), Kind::Array(target, max) | Kind::Set(target, max)) => {
+                let fits = match (size, max) {
+                    (Some(size), Some(max)) => size <= max,
+                    _ => true,
+                };
+                fits && kind.is_assignable_to(target, dialect)
+            }
+            (Kind::Object, Kind::Geometry(_)) => true,
+            (Kind::Literal(text), target) => match (literal_kind(text), target) {
+                (Some(_), Kind::Literal(other)) => other == text || literal_kind(other).is_none(),
+                (Some(kind), target) => kind.is_assignable_to(target, dialect),
+                (None, _) => true,
+            },
+            (value, Kind::Literal(text)) => match literal_kind(text) {
+                // A non-literal value may still hold the literal at runtime.
+                Some(kind) => kind == *value,
+                None => true,
+            },
+            (value, target) => value == target,
+        }
+    }
+
+    /// Widens an inferred kind into one suitable for a field definition, dropping the sizes of
+    /// array literals and the values of literal types.
+    pub(crate) fn widened(&self) -> Kind {
+        match self {
+            Kind::Array(kind, _) => Kind::Array(Box::new(kind.widened()), None),
+            Kind::Set(kind, _) => Kind::Set(Box::new(kind.widened()), None),
+            Kind::Option(kind) => Kind::Option(Box::new(kind.widened())),
+            Kind::Either(kinds) => Kind::Either(kinds.iter().map(Kind::widened).collect()),
+            Kind::Literal(text) => literal_kind(text).unwrap_or(Kind::Any),
+            kind => kind.clone(),
+        }
+    }
+
+    /// Returns the kind of the elements of an array or set.
+    pub(crate) fn element(&self) -> Option<&Kind> {
+        match self {
+            Kind::Array(kind, _) | Kind::Set(kind, _) => Some(kind),
+            Kind::Option(kind) => kind.element(),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the base kind of a literal type, if it is a scalar literal.
+fn literal_kind(text: &str) -> Option<Kind> {
+    let tokens = crate::lexer::tokenize(text);
+    match tokens.as_slice() {
+        [token] => literal(token),
+        _ => None,
+    }
+}
+
+fn parse_union<'t, 'a>(
+    tokens: &'t [crate::lexer::Token<'a>],
+) -> Option<(Kind, &'t [crate::lexer::Token<'a>])> {
+    let (first, mut rest) = parse_single(tokens)?;
+    let mut kinds = vec![first];
+    while let [pipe, after @ ..] = rest {
+        if !pipe.is_punct("|") {
+            break;
+        }
+        let (kind, after) = parse_single(after)?;
+        kinds.push(kind);
+        rest = after;
+    }
+    let kind = if kinds.len() == 1 { kinds.remove(0) } else { Kind::Either(kinds) };
+    Some((kind, rest))
+}
+
+/// Parses `<...>` generic arguments as a list of union types.
+fn parse_arguments<'t, 'a>(
+    tokens: &'t [crate::lexer::Token<'a>],
+) -> Option<(Vec<Kind>, Option<u64>, &'t [crate::lexer::Token<'a>])> {
+    let [open, rest @ ..] = tokens else {
+        return Some((Vec::new(), None, tokens));
+    };
+    if !open.is_punct("<") {
+        return Some((Vec::new(), None, tokens));
+    }
+    let mut rest = rest;
+    let mut kinds = Vec::new();
+    let mut size = None;
+    loop {
+        match rest {
+            [number, after @ ..] if number.kind == crate::lexer::TokenKind::Number => {
+                size = number.text.parse().ok();
+                rest = after;
+            }
+            _ => {
+                let (kind, after) = parse_union(rest)?;
+                kinds.push(kind);
+                rest = after;
+            }
+        }
+        match rest {
+            [comma, after @ ..] if comma.is_punct(",") => rest = after,
+            [close, after @ ..] if close.is_punct(">") => return Some((kinds, size, after)),
+            _ => return None,
+        }
+    }
+}
+
+fn parse_single<'t, 'a>(
+    tokens: &'t [crate::lexer::Token<'a>],
+) -> Option<(Kind, &'t [crate::lexer::Token<'a>])> {
+    let (first, rest) = tokens.split_first()?;
+    if first.kind != crate::lexer::TokenKind::Ident {
+        // Literal types, e.g. `"draft" | "published"` or `{ name: string }`.
+        if first.is_punct("{") || first.is_punct("[") {
+            let close = crate::lexer::matching_bracket(tokens, 0)?;
+            let kind = if first.is_punct("{") {
+                Kind::Object
+            } else {
+                Kind::Array(Box::new(Kind::Any), None)
+            };
+            return Some((kind, &tokens[close + 1..]));
+        }
+        return literal(first).map(|_| (Kind::Literal(first.text.to_string()), rest));
+    }
+
+    let name = first.text.to_ascii_lowercase();
+    if matches!(name.as_str(), "record" | "geometry") {
+        let (names, rest) = parse_names(rest)?;
+        let kind = if name == "record" { Kind::Record(names) } else { Kind::Geometry(names) };
+        return Some((kind, rest));
+    }
+
+    let (mut arguments, size, rest) = parse_arguments(rest)?;
+    let mut argument = || arguments.pop().map(Box::new).unwrap_or(Box::new(Kind::Any));
+    let kind = match name.as_str() {
+        "any" => Kind::Any,
+        "none" => Kind::None,
+        "null" => Kind::Null,
+        "bool" => Kind::Bool,
+        "int" => Kind::Int,
+        "float" => Kind::Float,
+        "decimal" => Kind::Decimal,
+        "number" => Kind::Number,
+        "string" => Kind::String,
+        "datetime" => Kind::Datetime,
+        "duration" => Kind::Duration,
+        "uuid" => Kind::Uuid,
+        "bytes" => Kind::Bytes,
+        "object" => Kind::Object,
+        "option" => Kind::Option(argument()),
+        "array" => Kind::Array(argument(), size),
+        "set" => Kind::Set(argument(), size),
+        _ => Kind::Any,
+    };
+    Some((kind, rest))
+}
+
+/// Parses the `<a | b>` table names of a record type or the kinds of a geometry type.
+fn parse_names<'t, 'a>(
+    tokens: &'t [crate::lexer::Token<'a>],
+) -> Option<(Vec<String>, &'t [crate::lexer::Token<'a>])> {
+    let [open, rest @ ..] = tokens else {
+        return Some((Vec::new(), tokens));
+    };
+    if !open.is_punct("<") {
+        return Some((Vec::new(), tokens));
+    }
+    let mut rest = rest;
+    let mut names = Vec::new();
+    loop {
+        let (name, after) = rest.split_first()?;
+        names.push(name.ident().to_string());
+        match after {
+            [pipe, after @ ..] if pipe.is_punct("|") => rest = after,
+            [close, after @ ..] if close.is_punct(">") => return Some((names, after)),
+            _ => return None,
+        }
+    }
+}
+
+/// Returns the kind of a literal token.
+fn literal(token: &crate::lexer::Token<'_>) -> Option<Kind> {
+    let kind = match token.kind {
+        crate::lexer::TokenKind::Number => {
+            if token.text.ends_with("dec") {
+                Kind::Decimal
+            } else if token.text.ends_with('f') || token.text.contains(['.', 'e', 'E']) {
+                Kind::Float
+            } else {
+                Kind::Int
+            }
+        }
+        crate::lexer::TokenKind::Duration => Kind::Duration,
+        crate::lexer::TokenKind::String => match token.text.chars().next() {
+            Some('r') => Kind::Record(record_literal_table(token.text).into_iter().collect()),
+            Some('d') => Kind::Datetime,
+            Some('u') => Kind::Uuid,
+            _ => Kind::String,
+        },
+        crate::lexer::TokenKind::Ident => match token.text.to_ascii_uppercase().as_str() {
+            "TRUE" | "FALSE" => Kind::Bool,
+            "NONE" => Kind::None,
+            "NULL" => Kind::Null,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Returns the table of a record string such as `r"person:tobie"`.
+fn record_literal_table(text: &str) -> Option<String> {
+    let inner = text.get(2..text.len().saturating_sub(1))?;
+    inner.split_once(':').map(|(table, _)| table.to_string())
+}
+
+const COMPARISON_OPERATORS: &[&str] =
+    &["=", "==", "!=", "?=", "*=", "<", ">", "<=", ">=", "~", "!~", "?~", "*~", "@@"];
+
+const COMPARISON_KEYWORDS: &[&str] = &[
+    "IS",
+    "CONTAINS",
+    "CONTAINSNOT",
+    "CONTAINSALL",
+    "CONTAINSANY",
+    "CONTAINSNONE",
+    "INSIDE",
+    "NOTINSIDE",
+    "ALLINSIDE",
+    "ANYINSIDE",
+    "NONEINSIDE",
+    "IN",
+    "OUTSIDE",
+    "INTERSECTS",
+];
+
+/// Infers the kind of the expression made up of `tokens`, or `None` when it cannot be known
+/// without evaluating it.
+pub(crate) fn infer(
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+) -> Option<Kind> {
+    match tokens {
+        [] => None,
+        [token] => literal(token),
+        _ => infer_operation(tokens, schema).unwrap_or_else(|| infer_operand(tokens, schema)),
+    }
+}
+
+/// Returns the index of the `>` closing the cast opened at `open`, such as the `<int>` of
+/// `<int> "42"` or the `<record<user>>` of `<record<user>> $id`. A `<` after an operand is a
+/// comparison instead.
+pub(crate) fn cast_end(tokens: &[crate::lexer::Token<'_>], open: usize) -> Option<usize> {
+    if !tokens[open].is_punct("<") {
+        return None;
+    }
+    let is_operand_position = match open.checked_sub(1).map(|index| &tokens[index]) {
+        None => true,
+        Some(previous) if previous.kind == crate::lexer::TokenKind::Punct => {
+            !matches!(previous.text, ")" | "]" | "}")
+        }
+        Some(previous) => ["AND", "OR", "NOT", "RETURN", "THEN", "ELSE"]
+            .iter()
+            .chain(COMPARISON_KEYWORDS)
+            .any(|keyword| previous.is_keyword(keyword)),
+    };
+    if !is_operand_position {
+        return None;
+    }
+    let mut depth = 0usize;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        if token.is_punct("<") {
+            depth += 1;
+        } else if token.is_punct(">") {
+            depth -= 1;
+            if depth == 0 {
+                return cast_kind(&tokens[open..=index]).map(|_| index);
+            }
+        }
+    }
+    None
+}
+
+/// Returns the kind that the `<kind>` cast of `tokens` casts to.
+fn cast_kind(tokens: &[crate::lexer::Token<'_>]) -> Option<Kind> {
+    let text: Vec<&str> = tokens[1..tokens.len() - 1].iter().map(|token| token.text).collect();
+    Kind::parse(&text.join(" "))
+}
+
+/// Infers the kind of a binary operation at the top level of `tokens`.
+fn infer_operation(
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+) -> Option<Option<Kind>> {
+    let mut depth = 0usize;
+    let mut arithmetic = Vec::new();
+    // Comparisons bind more loosely than arithmetic, but more tightly than `AND` and `OR`.
+    let mut comparison = false;
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        // The `<` and `>` of a cast are not comparisons, and a cast applies to a single operand.
+        if let Some(close) = cast_end(tokens, index).filter(|_| depth == 0) {
+            index = close + 1;
+            continue;
+        }
+        match token.text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            _ if depth > 0 || index == 0 => {}
+            _ if token.is_keyword("AND")
+                || token.is_keyword("OR")
+                || token.is_punct("&&")
+                || token.is_punct("||")
+                || token.is_punct("??")
+                || token.is_punct("?:")
+                || token.is_punct("?") =>
+            {
+                return Some(None);
+            }
+            _ if (token.kind == crate::lexer::TokenKind::Punct
+                && COMPARISON_OPERATORS.contains(&token.text))
+                || COMPARISON_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword)) =>
+            {
+                comparison = true;
+            }
+            "+" | "-" | "*" | "/" | "**" if token.kind == crate::lexer::TokenKind::Punct => {
+                arithmetic.push(index);
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    if comparison {
+        return Some(Some(Kind::Bool));
+    }
+    if arithmetic.is_empty() {
+        return None;
+    }
+
+    let mut operands = Vec::new();
+    let mut start = 0;
+    for index in arithmetic.iter().copied().chain([tokens.len()]) {
+        operands.push(infer(&tokens[start..index], schema));
+        start = index + 1;
+    }
+    let operands: Option<Vec<Kind>> = operands.into_iter().collect();
+    let Some(operands) = operands else {
+        return Some(None);
+    };
+    let kind = if operands.iter().all(|kind| *kind == Kind::String) {
+        Some(Kind::String)
+    } else if operands.iter().all(|kind| *kind == Kind::Int) {
+        Some(Kind::Int)
+    } else if operands.iter().all(Kind::is_numeric) {
+        if operands.contains(&Kind::Decimal) {
+            Some(Kind::Decimal)
+        } else if operands.contains(&Kind::Float) {
+            Some(Kind::Float)
+        } else {
+            Some(Kind::Number)
+        }
+    } else if operands.iter().all(|kind| *kind == Kind::Duration) {
+        Some(Kind::Duration)
+    } else if operands[0] == Kind::Datetime && operands[1..].iter().all(|k| *k == Kind::Duration) {
+        Some(Kind::Datetime)
+    } else {
+        None
+    };
+    Some(kind)
+}
+
+fn infer_operand(
+    tokens: &[crate::lexer::Token<'_>],
+    schema: &crate::schema::Schema,
+) -> Option<Kind> {
+    let first = tokens.first()?;
+    if let Some(close) = cast_end(tokens, 0) {
+        return cast_kind(&tokens[..=close]).filter(|_| close + 1 < tokens.len());
+    }
+    let close = if first.kind == crate::lexer::TokenKind::Punct {
+        crate::lexer::matching_bracket(tokens, 0)
+    } else {
+        None
+    };
+    if let Some(close) = close.filter(|close| close + 1 == tokens.len()) {
+        let inner = &tokens[1..close];
+        return match first.text {
+            "(" if crate::query::kind(inner) == crate::query::StatementKind::Select => {
+                Some(Kind::Array(Box::new(Kind::Any), None))
+            }
+            "(" => infer(inner, schema),
+            "[" => {
+                let parts = crate::lexer::split_top_level(inner, ",");
+                let size = parts.iter().filter(|part| !part.is_empty()).count() as u64;
+                let kinds: Vec<Option<Kind>> =
+                    parts.into_iter().map(|part| infer(&inner[part], schema)).collect();
+                let element = match kinds.split_first() {
+                    Some((Some(first), rest))
+                        if rest.iter().all(|kind| kind.as_ref() == Some(first)) =>
+                    {
+                        first.clone()
+                    }
+                    _ => Kind::Any,
+                };
+                Some(Kind::Array(Box::new(element), Some(size)))
+            }
+            "{" if inner.is_empty() || inner.get(1).is_some_and(|token| token.is_punct(":")) => {
+                Some(Kind::Object)
+            }
+            _ => None,
+        };
+    }
+
+    match tokens {
+        // A record id such as `person:tobie` or `person:⟨a b⟩`.
+        [table, colon, _]
+            if table.kind == crate::lexer::TokenKind::Ident && colon.is_punct(":") =>
+        {
+            Some(Kind::Record(vec![table.ident().to_string()]))
+        }
+        [table, colon, open, ..]
+            if table.kind == crate::lexer::TokenKind::Ident
+                && colon.is_punct(":")
+                && (open.is_punct("[") || open.is_punct("{"))
+                && crate::lexer::matching_bracket(tokens, 2) == Some(tokens.len() - 1) =>
+        {
+            Some(Kind::Record(vec![table.ident().to_string()]))
+        }
+        _ => infer_call(tokens, schema),
+    }
+}
+
+/// Infers the return kind of a built-in or custom function call.
+fn infer_call(tokens: &[crate::lexer::Token<'_>], schema: &crate::schema::Schema) -> Option<Kind> {
+    let open = tokens.iter().position(|token| token.is_punct("("))?;
+    if crate::lexer::matching_bracket(tokens, open)? != tokens.len() - 1 {
+        return None;
+    }
+    let name = &tokens[..open];
+    let is_path = name.iter().enumerate().all(|(index, token)| {
+        if index % 2 == 0 {
+            token.kind == crate::lexer::TokenKind::Ident
+        } else {
+            token.is_punct("::")
+        }
+    });
+    if !is_path || name.is_empty() {
+        return None;
+    }
+    let name: String = name.iter().map(|token| token.text).collect();
+    if name.starts_with("fn::") {
+        return schema.functions.get(&name).and_then(|function| function.returns.clone());
+    }
+    crate::functions::builtin(&name.to_lowercase())
+        .and_then(|function| Kind::parse(function.returns))
+}
+
+#[cfg(test)]
+mod tests {
+    fn kind(text: &str) -> super::Kind {
+        super::Kind::parse(text).unwrap()
+    }
+
+    fn infer(source: &str) -> Option<String> {
+        let schema_uri = url::Url::parse("file:///project/schema.surql").unwrap();
+        let changes = crate::schema::changes(
+            &schema_uri,
+            "DEFINE FUNCTION fn::age($born: datetime) -> int { RETURN 1; };",
+        );
+        let schema = crate::schema::Schema::from_changes(&changes, crate::config::Dialect::V2);
+        super::infer(&crate::lexer::tokenize(source), &schema).map(|kind| kind.to_string())
+    }
+
+    fn assignable(value: &str, target: &str) -> bool {
+        kind(value).is_assignable_to(&kind(target), crate::config::Dialect::V2)
+    }
+
+    #[test]
+    fn parses_and_displays_types() {
+        for text in [
+            "option<array<record<user>, 10>>",
+            "set<string>",
+            "record<user | admin>",
+            "geometry<point | polygon>",
+            "\"draft\" | \"published\"",
+            "int | string | none",
+        ] {
+            assert_eq!(kind(text).to_string(), text);
+        }
+        assert_eq!(kind("array").to_string(), "array");
+        assert_eq!(kind("{ name: string }"), super::Kind::Object);
+        assert_eq!(super::Kind::parse("array<int"), None);
+    }
+
+    #[test]
+    fn infers_literals_and_operations() {
+        assert_eq!(infer("\"twelve\"").as_deref(), Some("string"));
+        assert_eq!(infer("12").as_deref(), Some("int"));
+        assert_eq!(infer("1.5").as_deref(), Some("float"));
+        assert_eq!(infer("1 + 2.5").as_deref(), Some("float"));
+        assert_eq!(infer("1 + 2dec").as_deref(), Some("decimal"));
+        assert_eq!(infer("'a' + 'b'").as_deref(), Some("string"));
+        assert_eq!(infer("d'2024-01-01' + 1d").as_deref(), Some("datetime"));
+        assert_eq!(infer("age > 18").as_deref(), Some("bool"));
+        assert_eq!(infer("$a OR $b"), None);
+        assert_eq!(infer("[1, 2, 3]").as_deref(), Some("array<int, 3>"));
+        assert_eq!(infer("{ name: 'x' }").as_deref(), Some("object"));
+        assert_eq!(infer("person:tobie").as_deref(), Some("record<person>"));
+        assert_eq!(infer("r\"person:tobie\"").as_deref(), Some("record<person>"));
+        assert_eq!(infer("(SELECT * FROM person)").as_deref(), Some("array"));
+        assert_eq!(infer("string::len('abc')").as_deref(), Some("int"));
+        assert_eq!(infer("fn::age(d'2000-01-01')").as_deref(), Some("int"));
+        assert_eq!(infer("$unknown"), None);
+    }
+
+    #[test]
+    fn casts_are_operands_rather_than_comparisons() {
+        assert_eq!(infer("<int> \"42\"").as_deref(), Some("int"));
+        assert_eq!(infer("<record<user>> $id").as_deref(), Some("record<user>"));
+        assert_eq!(infer("\"a\" + <string> $n").as_deref(), Some("string"));
+        assert_eq!(infer("<string> $n + \"a\"").as_deref(), Some("string"));
+        assert_eq!(infer("<int> $a + <int> $b").as_deref(), Some("int"));
+        assert_eq!(infer("1 + <float> $n").as_deref(), Some("float"));
+        assert_eq!(infer("<int> $a > 1").as_deref(), Some("bool"));
+        assert_eq!(infer("$a < $b").as_deref(), Some("bool"));
+        assert_eq!(infer("1 < 2 AND 3 > 2"), None);
+        assert_eq!(infer("<int>"), None);
+    }
+
+    #[test]
+    fn scalars_are_assignable_to_wider_kinds() {
+        assert!(!assignable("string", "int"));
+        assert!(assignable("int", "float"));
+        assert!(assignable("int", "number"));
+        assert!(!assignable("float", "int"));
+        assert!(assignable("string", "any"));
+        assert!(assignable("any", "int"));
+    }
+
+    #[test]
+    fn options_accept_none_and_their_kind() {
+        assert!(assignable("none", "option<string>"));
+        assert!(assignable("string", "option<string>"));
+        assert!(!assignable("int", "option<string>"));
+        assert!(!assignable("none", "string"));
+    }
+
+    #[test]
+    fn arrays_check_their_elements_and_size() {
+        assert!(assignable("array<int, 3>", "array<int>"));
+        assert!(assignable("array<int, 3>", "array<int, 3>"));
+        assert!(!assignable("array<int, 4>", "array<int, 3>"));
+        assert!(!assignable("array<string>", "array<int>"));
+        assert!(assignable("array<int, 2>", "set<number>"));
+    }
+
+    #[test]
+    fn records_check_their_tables() {
+        assert!(assignable("record<user>", "record<user | admin>"));
+        assert!(assignable("record<user>", "record"));
+        assert!(!assignable("record<post>", "record<user>"));
+        assert!(!assignable("record", "record<user>"));
+    }
+
+    #[test]
+    fn strings_are_cast_to_records_and_datetimes_in_1x_only() {
+        let string = kind("string");
+        for target in ["record<user>", "datetime", "uuid"] {
+            assert!(string.is_assignable_to(&kind(target), crate::config::Dialect::V1));
+            assert!(!string.is_assignable_to(&kind(target), crate::config::Dialect::V2));
+        }
+    }
+
+    #[test]
+    fn unions_need_every_value_kind_to_fit_one_target() {
+        assert!(assignable("int", "int | string"));
+        assert!(assignable("int | string", "int | string | bool"));
+        assert!(!assignable("int | bool", "int | string"));
+        assert!(assignable("\"draft\"", "\"draft\" | \"published\""));
+        assert!(!assignable("\"deleted\"", "\"draft\" | \"published\""));
+        assert!(assignable("string", "\"draft\" | \"published\""));
+        assert!(!assignable("int", "\"draft\" | \"published\""));
+    }
+
+    #[test]
+    fn widening_drops_sizes_and_literal_values() {
+        assert_eq!(kind("array<int, 3>").widened().to_string(), "array<int>");
+        assert_eq!(kind("\"draft\"").widened().to_string(), "string");
+    }
+}
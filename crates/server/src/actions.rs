@@ -0,0 +1,78 @@
+//! Code actions offered by the server.
+
+/// The code action kind of "Uppercase all keywords".
+pub(crate) const UPPERCASE_KEYWORDS: &str = "source.uppercaseKeywords";
+/// The code action kind of "Lowercase all keywords".
+pub(crate) const LOWERCASE_KEYWORDS: &str = "source.lowercaseKeywords";
+
+pub(crate) fn code_action_kind(
+    kind: surrealql_analysis::AssistKind,
+) -> tower_lsp::lsp_types::CodeActionKind {
+    match kind {
+        surrealql_analysis::AssistKind::QuickFix => tower_lsp::lsp_types::CodeActionKind::QUICKFIX,
+        surrealql_analysis::AssistKind::Extract => {
+            tower_lsp::lsp_types::CodeActionKind::REFACTOR_EXTRACT
+        }
+        surrealql_analysis::AssistKind::Inline => {
+            tower_lsp::lsp_types::CodeActionKind::REFACTOR_INLINE
+        }
+        surrealql_analysis::AssistKind::UppercaseKeywords => {
+            tower_lsp::lsp_types::CodeActionKind::from(UPPERCASE_KEYWORDS)
+        }
+        surrealql_analysis::AssistKind::LowercaseKeywords => {
+            tower_lsp::lsp_types::CodeActionKind::from(LOWERCASE_KEYWORDS)
+        }
+    }
+}
+
+/// Returns whether actions of `kind` were asked for by the `only` filter of a request.
+pub(crate) fn is_requested(
+    only: Option<&Vec<tower_lsp::lsp_types::CodeActionKind>>,
+    kind: &tower_lsp::lsp_types::CodeActionKind,
+) -> bool {
+    only.map_or(true, |only| {
+        only.iter().any(|requested| {
+            kind.as_str() == requested.as_str()
+                || kind.as_str().starts_with(&format!("{}.", requested.as_str()))
+        })
+    })
+}
+
+/// Returns whether assists of `kind` should be offered for the `only` filter of a request. Like
+/// other source actions, the keyword case actions are only offered when asked for by kind.
+pub(crate) fn is_wanted(
+    only: Option<&Vec<tower_lsp::lsp_types::CodeActionKind>>,
+    kind: surrealql_analysis::AssistKind,
+) -> bool {
+    let is_source = matches!(
+        kind,
+        surrealql_analysis::AssistKind::UppercaseKeywords
+            | surrealql_analysis::AssistKind::LowercaseKeywords
+    );
+    (only.is_some() || !is_source) && is_requested(only, &code_action_kind(kind))
+}
+
+/// Returns the quick fixes of the diagnostics of `doc` that overlap `range`.
+pub(crate) fn quick_fixes(
+    analysis: &surrealql_analysis::Analysis,
+    doc: &lsp_textdocument::FullTextDocument,
+    diagnostics: Vec<surrealql_analysis::Diagnostic>,
+    range: tower_lsp::lsp_types::Range,
+) -> Vec<tower_lsp::lsp_types::CodeAction> {
+    let mut actions = Vec::new();
+    for diagnostic in diagnostics {
+        let diagnostic_range = crate::convert::range(doc, &diagnostic.range);
+        if diagnostic_range.end < range.start || range.end < diagnostic_range.start {
+            continue;
+        }
+        let converted = crate::convert::diagnostic(analysis, doc, &diagnostic);
+        for fix in &diagnostic.fixes {
+            actions.push(tower_lsp::lsp_types::CodeAction {
+                diagnostics: Some(vec![converted.clone()]),
+                is_preferred: Some(fix.preferred),
+                ..crate::convert::code_action(analysis, fix)
+            });
+        }
+    }
+    actions
+}
@@ -0,0 +1,321 @@
+//! The `check` subcommand, which reports the diagnostics of `.surql` files exactly as the server
+//! publishes them, so that CI can reject the files an editor would flag.
+
+/// How the diagnostics of `check` are printed.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub(crate) enum Format {
+    /// One line per diagnostic, followed by a summary.
+    Human,
+    /// A JSON array of diagnostics.
+    Json,
+    /// A SARIF 2.1.0 log, as uploaded to code scanning services.
+    Sarif,
+}
+
+/// A range of a file, with 1-based lines and columns. Columns count UTF-16 code units, like LSP
+/// and SARIF.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Span {
+    /// The file as printed, relative to the current directory when it is below it.
+    file: String,
+    #[serde(skip)]
+    uri: tower_lsp::lsp_types::Url,
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Related {
+    #[serde(flatten)]
+    span: Span,
+    message: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Report {
+    #[serde(flatten)]
+    span: Span,
+    severity: &'static str,
+    code: Option<&'static str>,
+    message: String,
+    related: Vec<Related>,
+}
+
+/// Checks the `.surql` files at `paths`, or below them for directories, and prints their
+/// diagnostics in `format`. Returns whether none of them is an error.
+///
+/// The files are analysed together, along with the schema of their projects, so that a file can
+/// use the tables and functions defined by another. Hints are left out, as they point at code to
+/// tidy up rather than at problems.
+pub(crate) fn run(paths: &[std::path::PathBuf], format: Format) -> Result<bool, String> {
+    let mut host = surrealql_analysis::AnalysisHost::load(paths)?;
+    let files = files(paths)?;
+    let mut uris = Vec::new();
+    for file in &files {
+        uris.push(open(&mut host, file)?);
+    }
+    let analysis = host.analysis();
+
+    let mut reports = Vec::new();
+    for uri in &uris {
+        let doc = crate::convert::document(&analysis, uri);
+        for diagnostic in analysis.diagnostics(uri) {
+            if diagnostic.severity != surrealql_analysis::Severity::Hint {
+                reports.push(report(&analysis, &doc, uri, &diagnostic));
+            }
+        }
+    }
+
+    let output = match format {
+        Format::Human => human(&reports, files.len()),
+        Format::Json => serde_json::to_string_pretty(&reports).map_err(|err| err.to_string())?,
+        Format::Sarif => {
+            serde_json::to_string_pretty(&sarif(&reports)).map_err(|err| err.to_string())?
+        }
+    };
+    println!("{}", output);
+    Ok(reports.iter().all(|report| report.severity != "error"))
+}
+
+/// Returns the `.surql` files at `paths`, or below them for directories, as absolute paths.
+pub(crate) fn files(paths: &[std::path::PathBuf]) -> Result<Vec<std::path::PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = std::fs::canonicalize(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        files.extend(surrealql_analysis::config::surql_files(&path)?);
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Reads `file` into `host`, returning its URL.
+pub(crate) fn open(
+    host: &mut surrealql_analysis::AnalysisHost,
+    file: &std::path::Path,
+) -> Result<tower_lsp::lsp_types::Url, String> {
+    let text = std::fs::read_to_string(file)
+        .map_err(|err| format!("Failed to read {}: {}", file.display(), err))?;
+    let uri = tower_lsp::lsp_types::Url::from_file_path(file)
+        .map_err(|()| format!("Invalid path {}", file.display()))?;
+    host.set_file_text(uri.clone(), text);
+    Ok(uri)
+}
+
+fn report(
+    analysis: &surrealql_analysis::Analysis,
+    doc: &lsp_textdocument::FullTextDocument,
+    uri: &tower_lsp::lsp_types::Url,
+    diagnostic: &surrealql_analysis::Diagnostic,
+) -> Report {
+    let related = diagnostic
+        .related
+        .iter()
+        .map(|related| {
+            let doc = crate::convert::document(analysis, &related.location.file);
+            Related {
+                span: span(&doc, &related.location.file, &related.location.range),
+                message: related.message.clone(),
+            }
+        })
+        .collect();
+    Report {
+        span: span(doc, uri, &diagnostic.range),
+        severity: match diagnostic.severity {
+            surrealql_analysis::Severity::Error => "error",
+            surrealql_analysis::Severity::Warning => "warning",
+            surrealql_analysis::Severity::Info => "info",
+            surrealql_analysis::Severity::Hint => "hint",
+        },
+        code: diagnostic.lint.map(surrealql_analysis::Lint::name),
+        message: diagnostic.message.clone(),
+        related,
+    }
+}
+
+fn span(
+    doc: &lsp_textdocument::FullTextDocument,
+    uri: &tower_lsp::lsp_types::Url,
+    range: &surrealql_analysis::TextRange,
+) -> Span {
+    let range = crate::convert::range(doc, range);
+    Span {
+        file: display(uri),
+        uri: uri.clone(),
+        line: range.start.line + 1,
+        column: range.start.character + 1,
+        end_line: range.end.line + 1,
+        end_column: range.end.character + 1,
+    }
+}
+
+/// Returns the path of `uri` relative to the current directory when it is below it, or else its
+/// absolute path or its URL.
+pub(crate) fn display(uri: &tower_lsp::lsp_types::Url) -> String {
+    let Ok(path) = uri.to_file_path() else {
+        return uri.to_string();
+    };
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|dir| std::fs::canonicalize(dir).ok())
+        .and_then(|dir| path.strip_prefix(dir).ok().map(std::path::Path::to_path_buf));
+    relative.unwrap_or(path).display().to_string()
+}
+
+fn human(reports: &[Report], files: usize) -> String {
+    let mut output = String::new();
+    for report in reports {
+        let Span { file, line, column, .. } = &report.span;
+        let code = report.code.map(|code| format!("[{}]", code)).unwrap_or_default();
+        output += &format!(
+            "{}:{}:{}: {}{}: {}\n",
+            file, line, column, report.severity, code, report.message
+        );
+        for related in &report.related {
+            let Span { file, line, column, .. } = &related.span;
+            output += &format!("  {}:{}:{}: note: {}\n", file, line, column, related.message);
+        }
+    }
+    let count =
+        |severity: &str| reports.iter().filter(|report| report.severity == severity).count();
+    output += &format!(
+        "Checked {}: {}, {}, {}",
+        plural(files, "file"),
+        plural(count("error"), "error"),
+        plural(count("warning"), "warning"),
+        plural(count("info"), "info")
+    );
+    output
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+/// Returns a SARIF log of `reports`, whose rules are the lints of `surrealql.toml`. Files below
+/// the current directory are located relative to it as `%SRCROOT%`, and others by their URI.
+fn sarif(reports: &[Report]) -> serde_json::Value {
+    let root = std::env::current_dir()
+        .ok()
+        .and_then(|dir| std::fs::canonicalize(dir).ok())
+        .and_then(|dir| tower_lsp::lsp_types::Url::from_directory_path(dir).ok());
+    let artifact = |uri: &tower_lsp::lsp_types::Url| match root
+        .as_ref()
+        .and_then(|root| uri.as_str().strip_prefix(root.as_str()))
+    {
+        Some(relative) => serde_json::json!({ "uri": relative, "uriBaseId": "SRCROOT" }),
+        None => serde_json::json!({ "uri": uri }),
+    };
+    let location = |span: &Span| {
+        serde_json::json!({
+            "physicalLocation": {
+                "artifactLocation": artifact(&span.uri),
+                "region": {
+                    "startLine": span.line,
+                    "startColumn": span.column,
+                    "endLine": span.end_line,
+                    "endColumn": span.end_column,
+                },
+            },
+        })
+    };
+    let results: Vec<serde_json::Value> = reports
+        .iter()
+        .map(|report| {
+            let related: Vec<serde_json::Value> = report
+                .related
+                .iter()
+                .map(|related| {
+                    let mut location = location(&related.span);
+                    location["message"] = serde_json::json!({ "text": related.message });
+                    location
+                })
+                .collect();
+            let mut result = serde_json::json!({
+                "level": if report.severity == "info" { "note" } else { report.severity },
+                "message": { "text": report.message },
+                "locations": [location(&report.span)],
+                "relatedLocations": related,
+            });
+            if let Some(code) = report.code {
+                result["ruleId"] = serde_json::json!(code);
+            }
+            result
+        })
+        .collect();
+    let rules: Vec<serde_json::Value> = surrealql_analysis::Lint::ALL
+        .iter()
+        .map(|lint| serde_json::json!({ "id": lint.name() }))
+        .collect();
+    let mut run = serde_json::json!({
+        "tool": {
+            "driver": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+                "rules": rules,
+            },
+        },
+        "results": results,
+    });
+    if let Some(root) = root {
+        run["originalUriBaseIds"] = serde_json::json!({ "SRCROOT": { "uri": root } });
+    }
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [run],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    fn report(uri: &tower_lsp::lsp_types::Url) -> super::Report {
+        super::Report {
+            span: super::Span {
+                file: super::display(uri),
+                uri: uri.clone(),
+                line: 1,
+                column: 15,
+                end_line: 1,
+                end_column: 20,
+            },
+            severity: "warning",
+            code: Some("unknown-table"),
+            message: String::from("Table `persn` is not defined."),
+            related: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn sarif_locates_files_by_uri() {
+        let root = std::fs::canonicalize(std::env::current_dir().unwrap()).unwrap();
+        let below = root.join("queries").join("top users.surql");
+        let below = tower_lsp::lsp_types::Url::from_file_path(below).unwrap();
+        let elsewhere = tower_lsp::lsp_types::Url::parse("surrealdb://localhost:8000/test/app");
+        let log = super::sarif(&[report(&below), report(&elsewhere.unwrap())]);
+
+        let run = &log["runs"][0];
+        let root = tower_lsp::lsp_types::Url::from_directory_path(root).unwrap();
+        assert_eq!(run["originalUriBaseIds"]["SRCROOT"]["uri"], root.as_str());
+        let artifact = |result: usize| {
+            run["results"][result]["locations"][0]["physicalLocation"]["artifactLocation"].clone()
+        };
+        assert_eq!(
+            artifact(0),
+            serde_json::json!({ "uri": "queries/top%20users.surql", "uriBaseId": "SRCROOT" })
+        );
+        assert_eq!(
+            artifact(1),
+            serde_json::json!({ "uri": "surrealdb://localhost:8000/test/app" })
+        );
+        assert_eq!(run["results"][0]["ruleId"], "unknown-table");
+        assert_eq!(run["results"][0]["level"], "warning");
+    }
+}
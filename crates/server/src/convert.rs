@@ -0,0 +1,152 @@
+//! Conversions between the byte offsets of the analysis and the line and character positions of
+//! LSP.
+
+pub(crate) fn range(
+    doc: &lsp_textdocument::FullTextDocument,
+    range: &surrealql_analysis::TextRange,
+) -> tower_lsp::lsp_types::Range {
+    tower_lsp::lsp_types::Range {
+        start: doc.position_at(range.start as u32),
+        end: doc.position_at(range.end as u32),
+    }
+}
+
+pub(crate) fn text_range(
+    doc: &lsp_textdocument::FullTextDocument,
+    range: tower_lsp::lsp_types::Range,
+) -> surrealql_analysis::TextRange {
+    doc.offset_at(range.start) as usize..doc.offset_at(range.end) as usize
+}
+
+/// Returns a document to convert the offsets of `file` with, which is empty when the analysis
+/// does not know its text, e.g. for a snapshot.
+pub(crate) fn document(
+    analysis: &surrealql_analysis::Analysis,
+    file: &tower_lsp::lsp_types::Url,
+) -> lsp_textdocument::FullTextDocument {
+    let text = analysis.file_text(file).unwrap_or_default();
+    lsp_textdocument::FullTextDocument::new(String::from("surrealql"), 0, text.to_string())
+}
+
+pub(crate) fn location(
+    analysis: &surrealql_analysis::Analysis,
+    location: &surrealql_analysis::FileRange,
+) -> tower_lsp::lsp_types::Location {
+    let doc = document(analysis, &location.file);
+    tower_lsp::lsp_types::Location {
+        uri: location.file.clone(),
+        range: range(&doc, &location.range),
+    }
+}
+
+pub(crate) fn severity(
+    severity: surrealql_analysis::Severity,
+) -> tower_lsp::lsp_types::DiagnosticSeverity {
+    match severity {
+        surrealql_analysis::Severity::Error => tower_lsp::lsp_types::DiagnosticSeverity::ERROR,
+        surrealql_analysis::Severity::Warning => tower_lsp::lsp_types::DiagnosticSeverity::WARNING,
+        surrealql_analysis::Severity::Info => tower_lsp::lsp_types::DiagnosticSeverity::INFORMATION,
+        surrealql_analysis::Severity::Hint => tower_lsp::lsp_types::DiagnosticSeverity::HINT,
+    }
+}
+
+/// Converts a diagnostic of `doc`, whose related locations may be in other files.
+pub(crate) fn diagnostic(
+    analysis: &surrealql_analysis::Analysis,
+    doc: &lsp_textdocument::FullTextDocument,
+    diagnostic: &surrealql_analysis::Diagnostic,
+) -> tower_lsp::lsp_types::Diagnostic {
+    let tags: Vec<tower_lsp::lsp_types::DiagnosticTag> = diagnostic
+        .tags
+        .iter()
+        .map(|tag| match tag {
+            surrealql_analysis::Tag::Deprecated => tower_lsp::lsp_types::DiagnosticTag::DEPRECATED,
+            surrealql_analysis::Tag::Unnecessary => {
+                tower_lsp::lsp_types::DiagnosticTag::UNNECESSARY
+            }
+        })
+        .collect();
+    let related: Vec<tower_lsp::lsp_types::DiagnosticRelatedInformation> = diagnostic
+        .related
+        .iter()
+        .map(|related| tower_lsp::lsp_types::DiagnosticRelatedInformation {
+            location: location(analysis, &related.location),
+            message: related.message.clone(),
+        })
+        .collect();
+    tower_lsp::lsp_types::Diagnostic {
+        range: range(doc, &diagnostic.range),
+        severity: Some(severity(diagnostic.severity)),
+        code: diagnostic
+            .lint
+            .map(|lint| tower_lsp::lsp_types::NumberOrString::String(lint.name().to_string())),
+        source: Some(String::from("surrealql-lsp")),
+        message: diagnostic.message.clone(),
+        related_information: (!related.is_empty()).then_some(related),
+        tags: (!tags.is_empty()).then_some(tags),
+        ..tower_lsp::lsp_types::Diagnostic::default()
+    }
+}
+
+pub(crate) fn workspace_edit(
+    analysis: &surrealql_analysis::Analysis,
+    change: &surrealql_analysis::SourceChange,
+) -> tower_lsp::lsp_types::WorkspaceEdit {
+    let changes = change
+        .edits
+        .iter()
+        .map(|(file, edits)| {
+            let doc = document(analysis, file);
+            let edits = edits.iter().map(|edit| text_edit(&doc, edit)).collect();
+            (file.clone(), edits)
+        })
+        .collect();
+    tower_lsp::lsp_types::WorkspaceEdit {
+        changes: Some(changes),
+        ..tower_lsp::lsp_types::WorkspaceEdit::default()
+    }
+}
+
+pub(crate) fn text_edit(
+    doc: &lsp_textdocument::FullTextDocument,
+    edit: &surrealql_analysis::TextEdit,
+) -> tower_lsp::lsp_types::TextEdit {
+    tower_lsp::lsp_types::TextEdit {
+        range: range(doc, &edit.range),
+        new_text: edit.new_text.clone(),
+    }
+}
+
+pub(crate) fn code_action(
+    analysis: &surrealql_analysis::Analysis,
+    change: &surrealql_analysis::SourceChange,
+) -> tower_lsp::lsp_types::CodeAction {
+    tower_lsp::lsp_types::CodeAction {
+        title: change.label.clone(),
+        kind: Some(crate::actions::code_action_kind(change.kind)),
+        edit: Some(workspace_edit(analysis, change)),
+        is_preferred: change.preferred.then_some(true),
+        ..tower_lsp::lsp_types::CodeAction::default()
+    }
+}
+
+pub(crate) fn inlay_hint(
+    doc: &lsp_textdocument::FullTextDocument,
+    hint: surrealql_analysis::InlayHint,
+) -> tower_lsp::lsp_types::InlayHint {
+    let is_parameter = hint.kind == surrealql_analysis::InlayHintKind::Parameter;
+    tower_lsp::lsp_types::InlayHint {
+        position: doc.position_at(hint.offset as u32),
+        label: tower_lsp::lsp_types::InlayHintLabel::String(hint.label),
+        kind: Some(if is_parameter {
+            tower_lsp::lsp_types::InlayHintKind::PARAMETER
+        } else {
+            tower_lsp::lsp_types::InlayHintKind::TYPE
+        }),
+        text_edits: None,
+        tooltip: hint.tooltip.map(tower_lsp::lsp_types::InlayHintTooltip::String),
+        padding_left: Some(!is_parameter),
+        padding_right: Some(is_parameter),
+        data: None,
+    }
+}
@@ -0,0 +1,159 @@
+//! Pull diagnostics, which clients request for a document or for the whole workspace rather than
+//! wait for the server to publish them. Each report has the result ID of the diagnostics it was
+//! computed from, which stays the same until they are computed again, so that a report the client
+//! already holds is answered as unchanged instead of sent again.
+
+/// Distinguishes the diagnostics of this server from those of others in the client.
+pub(crate) const IDENTIFIER: &str = "surrealql";
+
+/// Returns the diagnostics of `file`, whose offsets `doc` converts, and their result ID.
+fn diagnostics(
+    analysis: &surrealql_analysis::Analysis,
+    file: &tower_lsp::lsp_types::Url,
+    doc: &lsp_textdocument::FullTextDocument,
+) -> (String, Vec<tower_lsp::lsp_types::Diagnostic>) {
+    let diagnostics = analysis.file_diagnostics(file);
+    let items = diagnostics
+        .diagnostics
+        .iter()
+        .map(|diagnostic| crate::convert::diagnostic(analysis, doc, diagnostic))
+        .collect();
+    (result_id(&diagnostics), items)
+}
+
+/// Returns the result ID of `diagnostics`, which includes the ID of the process as the IDs of the
+/// diagnostics start over whenever the server is started.
+fn result_id(diagnostics: &surrealql_analysis::FileDiagnostics) -> String {
+    format!("{}-{}", std::process::id(), diagnostics.id)
+}
+
+/// Answers a `textDocument/diagnostic` request for the open document `doc` at `file`.
+pub(crate) fn document_report(
+    analysis: &surrealql_analysis::Analysis,
+    file: &tower_lsp::lsp_types::Url,
+    doc: &lsp_textdocument::FullTextDocument,
+    previous_result_id: Option<&str>,
+) -> tower_lsp::lsp_types::DocumentDiagnosticReport {
+    let (result_id, items) = diagnostics(analysis, file, doc);
+    if previous_result_id == Some(result_id.as_str()) {
+        return tower_lsp::lsp_types::DocumentDiagnosticReport::Unchanged(
+            tower_lsp::lsp_types::RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report:
+                    tower_lsp::lsp_types::UnchangedDocumentDiagnosticReport { result_id },
+            },
+        );
+    }
+    tower_lsp::lsp_types::DocumentDiagnosticReport::Full(
+        tower_lsp::lsp_types::RelatedFullDocumentDiagnosticReport {
+            related_documents: None,
+            full_document_diagnostic_report: tower_lsp::lsp_types::FullDocumentDiagnosticReport {
+                result_id: Some(result_id),
+                items,
+            },
+        },
+    )
+}
+
+/// Answers a `workspace/diagnostic` request with a report for every open document, at its
+/// version, and every file indexed from disk. Returns `None` when the workspace changes before
+/// every file is reported, rather than a partial report.
+pub(crate) fn workspace_report(
+    analysis: &surrealql_analysis::Analysis,
+    documents: &std::collections::BTreeMap<
+        tower_lsp::lsp_types::Url,
+        std::sync::Arc<lsp_textdocument::FullTextDocument>,
+    >,
+    previous_result_ids: &[tower_lsp::lsp_types::PreviousResultId],
+) -> Option<tower_lsp::lsp_types::WorkspaceDiagnosticReport> {
+    let mut items = Vec::new();
+    for file in analysis.files() {
+        if analysis.is_cancelled() {
+            return None;
+        }
+        let (result_id, diagnostics) = match documents.get(&file) {
+            Some(doc) => diagnostics(analysis, &file, doc),
+            None => diagnostics(analysis, &file, &crate::convert::document(analysis, &file)),
+        };
+        let version = documents.get(&file).map(|doc| i64::from(doc.version()));
+        let previous = previous_result_ids.iter().find(|previous| previous.uri == file);
+        let item = if previous.is_some_and(|previous| previous.value == result_id) {
+            tower_lsp::lsp_types::WorkspaceDocumentDiagnosticReport::Unchanged(
+                tower_lsp::lsp_types::WorkspaceUnchangedDocumentDiagnosticReport {
+                    uri: file,
+                    version,
+                    unchanged_document_diagnostic_report:
+                        tower_lsp::lsp_types::UnchangedDocumentDiagnosticReport { result_id },
+                },
+            )
+        } else {
+            tower_lsp::lsp_types::WorkspaceDocumentDiagnosticReport::Full(
+                tower_lsp::lsp_types::WorkspaceFullDocumentDiagnosticReport {
+                    uri: file,
+                    version,
+                    full_document_diagnostic_report:
+                        tower_lsp::lsp_types::FullDocumentDiagnosticReport {
+                            result_id: Some(result_id),
+                            items: diagnostics,
+                        },
+                },
+            )
+        };
+        items.push(item);
+    }
+    Some(tower_lsp::lsp_types::WorkspaceDiagnosticReport { items })
+}
+
+#[cfg(test)]
+mod tests {
+    fn uri(name: &str) -> tower_lsp::lsp_types::Url {
+        tower_lsp::lsp_types::Url::parse(&format!("file:///project/{}.surql", name)).unwrap()
+    }
+
+    fn host() -> surrealql_analysis::AnalysisHost {
+        let mut host = surrealql_analysis::AnalysisHost::default();
+        host.set_disk_file(&uri("schema"), Some("DEFINE TABLE person;"));
+        host.set_disk_file(&uri("queries"), Some("SELECT * FROM persn;"));
+        host
+    }
+
+    #[test]
+    fn reports_the_files_the_client_holds_as_unchanged() {
+        let host = host();
+        let documents = std::collections::BTreeMap::new();
+        let report = super::workspace_report(&host.analysis(), &documents, &[]).unwrap();
+        let previous: Vec<tower_lsp::lsp_types::PreviousResultId> = report
+            .items
+            .iter()
+            .map(|item| match item {
+                tower_lsp::lsp_types::WorkspaceDocumentDiagnosticReport::Full(full) => {
+                    tower_lsp::lsp_types::PreviousResultId {
+                        uri: full.uri.clone(),
+                        value: full.full_document_diagnostic_report.result_id.clone().unwrap(),
+                    }
+                }
+                unchanged => panic!("{:?} was reported as unchanged", unchanged),
+            })
+            .collect();
+        assert_eq!(previous.len(), 2);
+
+        let report = super::workspace_report(&host.analysis(), &documents, &previous).unwrap();
+        for (item, previous) in report.items.iter().zip(&previous) {
+            let tower_lsp::lsp_types::WorkspaceDocumentDiagnosticReport::Unchanged(unchanged) =
+                item
+            else {
+                panic!("{:?} was reported in full", item);
+            };
+            assert_eq!(unchanged.unchanged_document_diagnostic_report.result_id, previous.value);
+        }
+    }
+
+    #[test]
+    fn does_not_report_a_workspace_that_changed() {
+        let mut host = host();
+        let analysis = host.analysis();
+        host.set_disk_file(&uri("queries"), Some("SELECT * FROM person;"));
+        let documents = std::collections::BTreeMap::new();
+        assert!(super::workspace_report(&analysis, &documents, &[]).is_none());
+    }
+}
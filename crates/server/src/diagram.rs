@@ -0,0 +1,87 @@
+//! Entity-relationship diagrams of the schema, for the `diagram` subcommand and the
+//! `surrealql.exportDiagram` command, so that documentation can be regenerated from the schema.
+
+/// Returns the schema of the workspace as a diagram. Takes an optional [`DiagramParams`] argument.
+pub(crate) const EXPORT_DIAGRAM: &str = "surrealql.exportDiagram";
+
+/// The language a diagram is written in.
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Format {
+    /// A Mermaid `erDiagram`, as rendered in Markdown by GitHub and most documentation sites.
+    #[default]
+    Mermaid,
+    /// A Graphviz `digraph`, e.g. for `dot -Tsvg`.
+    Dot,
+}
+
+impl From<Format> for surrealql_analysis::DiagramFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Mermaid => surrealql_analysis::DiagramFormat::Mermaid,
+            Format::Dot => surrealql_analysis::DiagramFormat::Dot,
+        }
+    }
+}
+
+/// The argument of the `surrealql.exportDiagram` command.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct DiagramParams {
+    pub(crate) format: Format,
+}
+
+/// Writes the diagram of the workspace at `root`, as the `snapshot` subcommand loads it, to
+/// `output`, or to stdout.
+pub(crate) async fn write(
+    root: &std::path::Path,
+    format: Format,
+    output: Option<&std::path::Path>,
+) -> Result<(), String> {
+    let host = crate::snapshot::load(root).await?;
+    let diagram = host.analysis().diagram(format.into());
+    match output {
+        Some(path) => std::fs::write(path, diagram)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err)),
+        None => {
+            print!("{}", diagram);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn writes_the_edges_of_the_relation_tables() {
+        let dir = std::env::temp_dir().join(format!("surrealql-diagram-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("schema.surql"),
+            "DEFINE TABLE person SCHEMAFULL;\n\
+             DEFINE TABLE post SCHEMAFULL;\n\
+             DEFINE TABLE wrote TYPE RELATION FROM person TO post;\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("queries.surql"), "RELATE person:ada->likes->person:grace;\n")
+            .unwrap();
+
+        let output = dir.join("schema.dot");
+        let written = super::write(&dir, super::Format::Dot, Some(&output)).await;
+        let diagram = std::fs::read_to_string(&output);
+        std::fs::remove_dir_all(&dir).unwrap();
+        written.unwrap();
+
+        let edges: Vec<&str> =
+            diagram.as_ref().unwrap().lines().filter(|line| line.contains("->")).collect();
+        assert_eq!(
+            edges,
+            [
+                "    \"likes\" -> \"person\" [label=\"in\"];",
+                "    \"likes\" -> \"person\" [label=\"out\"];",
+                "    \"wrote\" -> \"person\" [label=\"in\"];",
+                "    \"wrote\" -> \"post\" [label=\"out\"];",
+            ]
+        );
+    }
+}
@@ -0,0 +1,584 @@
+//! Executes queries against the SurrealDB instance configured in `surrealql.toml`, for the
+//! `surrealql.executeStatement` and `surrealql.executeFile` commands.
+
+/// Runs the statements within a range, or the statement under the cursor.
+pub(crate) const EXECUTE_STATEMENT: &str = "surrealql.executeStatement";
+/// Runs every statement of a document.
+pub(crate) const EXECUTE_FILE: &str = "surrealql.executeFile";
+
+/// How long connecting, signing in and running a query may take altogether.
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The argument of the execute commands.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExecuteParams {
+    pub(crate) text_document: tower_lsp::lsp_types::TextDocumentIdentifier,
+    /// Runs the statement under this position, when no `range` is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) position: Option<tower_lsp::lsp_types::Position>,
+    /// Runs the statements that start within this range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) range: Option<tower_lsp::lsp_types::Range>,
+    /// Runs `SELECT` statements with an `EXPLAIN` clause, to show their query plan instead.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) explain: bool,
+}
+
+/// Returns the argument of `command`, which runs every statement for `surrealql.executeFile`.
+pub(crate) fn params(command: &str, argument: serde_json::Value) -> Result<ExecuteParams, String> {
+    let mut params: ExecuteParams =
+        serde_json::from_value(argument).map_err(|err| err.to_string())?;
+    match command {
+        EXECUTE_STATEMENT if params.position.is_some() || params.range.is_some() => {}
+        EXECUTE_STATEMENT => {
+            return Err(String::from("Either a `position` or a `range` is required"));
+        }
+        EXECUTE_FILE => {
+            params.position = None;
+            params.range = None;
+        }
+        command => return Err(format!("Unknown command `{}`", command)),
+    }
+    Ok(params)
+}
+
+/// The outcome of a single statement, as reported by SurrealDB.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct StatementResult {
+    /// The statement in the document, when the results could be matched up with the statements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) range: Option<tower_lsp::lsp_types::Range>,
+    /// `"OK"` or `"ERR"`.
+    pub(crate) status: String,
+    /// The execution time, e.g. `"1.5ms"`.
+    pub(crate) time: String,
+    /// The returned records, or the error message of a failed statement.
+    pub(crate) result: serde_json::Value,
+}
+
+/// The statements of a document to execute.
+pub(crate) struct Query {
+    pub(crate) text: String,
+    /// The statements that SurrealDB reports a result for, which excludes transaction statements.
+    pub(crate) ranges: Vec<tower_lsp::lsp_types::Range>,
+}
+
+/// Returns the statements of `doc` that `params` selects: those within its range, the one under
+/// its position or, failing both, every statement.
+pub(crate) fn query(
+    analysis: &surrealql_analysis::Analysis,
+    doc: &lsp_textdocument::FullTextDocument,
+    params: &ExecuteParams,
+) -> Option<Query> {
+    let selection = match (params.range, params.position) {
+        (Some(range), _) => {
+            surrealql_analysis::Selection::Range(crate::convert::text_range(doc, range))
+        }
+        (None, Some(position)) => {
+            surrealql_analysis::Selection::Offset(doc.offset_at(position) as usize)
+        }
+        (None, None) => surrealql_analysis::Selection::All,
+    };
+    let query = analysis.query(&params.text_document.uri, &selection, params.explain)?;
+    let ranges = query.ranges.iter().map(|range| crate::convert::range(doc, range)).collect();
+    Some(Query { text: query.text, ranges })
+}
+
+/// A SurrealDB instance, reached over HTTP or the WebSocket RPC protocol.
+pub(crate) struct Connection {
+    endpoint: tower_lsp::lsp_types::Url,
+    dialect: surrealql_analysis::config::Dialect,
+    namespace: Option<String>,
+    database: Option<String>,
+    credentials: Option<(String, String)>,
+    timeout: std::time::Duration,
+}
+
+impl Connection {
+    /// Returns the connection configured for the project, or why there is none.
+    pub(crate) fn from_config(
+        config: Option<&surrealql_analysis::config::ProjectConfig>,
+    ) -> Result<Self, String> {
+        let Some((config, connection)) =
+            config.and_then(|config| Some((config, config.connection.as_ref()?)))
+        else {
+            return Err(format!(
+                "No [connection] is configured in {}",
+                surrealql_analysis::config::CONFIG_FILE_NAME
+            ));
+        };
+        let endpoint = tower_lsp::lsp_types::Url::parse(connection.endpoint.get_ref())
+            .map_err(|err| format!("Invalid endpoint: {}", err))?;
+        let username = connection.username.clone().or_else(|| std::env::var("SURREAL_USER").ok());
+        let password = connection.password.clone().or_else(|| std::env::var("SURREAL_PASS").ok());
+        Ok(Self {
+            endpoint,
+            dialect: config.dialect,
+            namespace: config.namespace.as_ref().map(|namespace| namespace.get_ref().clone()),
+            database: config.database.as_ref().map(|database| database.get_ref().clone()),
+            credentials: username.zip(password),
+            timeout: TIMEOUT,
+        })
+    }
+
+    pub(crate) fn dialect(&self) -> surrealql_analysis::config::Dialect {
+        self.dialect
+    }
+
+    /// Returns the URI that the definitions introspected from the database are reported against,
+    /// e.g. `surrealdb://localhost:8000/test/test`.
+    pub(crate) fn schema_uri(&self) -> Option<tower_lsp::lsp_types::Url> {
+        let host = self.endpoint.host_str()?;
+        let port = self.endpoint.port().map(|port| format!(":{}", port)).unwrap_or_default();
+        let namespace = self.namespace.as_deref().unwrap_or_default();
+        let database = self.database.as_deref().unwrap_or_default();
+        tower_lsp::lsp_types::Url::parse(&format!(
+            "{}://{}{}/{}/{}",
+            surrealql_analysis::LIVE_SCHEME,
+            host,
+            port,
+            namespace,
+            database
+        ))
+        .ok()
+    }
+
+    /// Executes `query`, returning the result of each of its statements.
+    pub(crate) async fn execute(&self, query: &Query) -> Result<Vec<StatementResult>, String> {
+        let execute = async {
+            match self.endpoint.scheme() {
+                "http" | "https" => self.execute_http(&query.text).await,
+                "ws" | "wss" => self.execute_rpc(&query.text).await,
+                scheme => Err(format!("Unsupported endpoint scheme `{}`", scheme)),
+            }
+        };
+        let mut results = tokio::time::timeout(self.timeout, execute)
+            .await
+            .map_err(|_| format!("Timed out after {:?}", self.timeout))??;
+        if results.len() == query.ranges.len() {
+            for (result, range) in results.iter_mut().zip(&query.ranges) {
+                result.range = Some(*range);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Posts `query` to the `/sql` endpoint.
+    async fn execute_http(&self, query: &str) -> Result<Vec<StatementResult>, String> {
+        let url = self.endpoint.join("sql").map_err(|err| format!("Invalid endpoint: {}", err))?;
+        let (ns_header, db_header) = match self.dialect {
+            surrealql_analysis::config::Dialect::V1 => ("NS", "DB"),
+            surrealql_analysis::config::Dialect::V2 => ("surreal-ns", "surreal-db"),
+        };
+        let mut request = reqwest::Client::new()
+            .post(url.as_str())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .body(query.to_string());
+        if let Some(namespace) = &self.namespace {
+            request = request.header(ns_header, namespace);
+        }
+        if let Some(database) = &self.database {
+            request = request.header(db_header, database);
+        }
+        if let Some((username, password)) = &self.credentials {
+            request = request.basic_auth(username, Some(password));
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|err| format!("Failed to connect to {}: {}", self.endpoint, err))?;
+        let status = response.status();
+        let body = response.text().await.map_err(|err| err.to_string())?;
+        if !status.is_success() {
+            return Err(format!("{} responded with {}: {}", self.endpoint, status, body.trim()));
+        }
+        serde_json::from_str(&body)
+            .map_err(|err| format!("Unexpected response from {}: {}", self.endpoint, err))
+    }
+
+    /// Signs in, selects the namespace and database and runs `query` over the `/rpc` endpoint.
+    async fn execute_rpc(&self, query: &str) -> Result<Vec<StatementResult>, String> {
+        let mut url = self.endpoint.clone();
+        if matches!(url.path(), "" | "/") {
+            url.set_path("/rpc");
+        }
+        let (socket, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .map_err(|err| format!("Failed to connect to {}: {}", url, err))?;
+        let mut rpc = Rpc { socket, id: 0 };
+
+        if let Some((username, password)) = &self.credentials {
+            rpc.call("signin", serde_json::json!([{ "user": username, "pass": password }])).await?;
+        }
+        if self.namespace.is_some() || self.database.is_some() {
+            rpc.call("use", serde_json::json!([self.namespace, self.database])).await?;
+        }
+        let results = rpc.call("query", serde_json::json!([query])).await?;
+        let _ = futures_util::SinkExt::close(&mut rpc.socket).await;
+        serde_json::from_value(results)
+            .map_err(|err| format!("Unexpected response from {}: {}", url, err))
+    }
+}
+
+struct Rpc {
+    socket: tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >,
+    id: u64,
+}
+
+#[derive(serde::Deserialize)]
+struct RpcResponse {
+    id: Option<serde_json::Value>,
+    #[serde(default)]
+    result: serde_json::Value,
+    error: Option<RpcError>,
+}
+
+#[derive(serde::Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+impl Rpc {
+    /// Sends a request and waits for its response, skipping any other messages such as live query
+    /// notifications.
+    async fn call(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        self.id += 1;
+        let request = serde_json::json!({ "id": self.id, "method": method, "params": params });
+        futures_util::SinkExt::send(
+            &mut self.socket,
+            tokio_tungstenite::tungstenite::Message::Text(request.to_string()),
+        )
+        .await
+        .map_err(|err| err.to_string())?;
+
+        while let Some(message) = futures_util::StreamExt::next(&mut self.socket).await {
+            let tokio_tungstenite::tungstenite::Message::Text(text) =
+                message.map_err(|err| err.to_string())?
+            else {
+                continue;
+            };
+            let Ok(response) = serde_json::from_str::<RpcResponse>(&text) else {
+                continue;
+            };
+            if response.id != Some(serde_json::Value::from(self.id)) {
+                continue;
+            }
+            return match response.error {
+                Some(error) => Err(format!("`{}` failed: {}", method, error.message)),
+                None => Ok(response.result),
+            };
+        }
+        Err(String::from("The connection was closed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// A transaction between two statements, which SurrealDB reports no results for.
+    const DOCUMENT: &str = "\
+CREATE person:tobie SET name = 'Tobie';
+BEGIN;
+SELECT * FROM person;
+COMMIT;
+";
+
+    /// What SurrealDB reports for the statements of `DOCUMENT`.
+    fn reported() -> serde_json::Value {
+        serde_json::json!([
+            {
+                "time": "1.5ms",
+                "status": "OK",
+                "result": [{ "id": "person:tobie", "name": "Tobie" }],
+            },
+            { "time": "250µs", "status": "ERR", "result": "The table does not exist" },
+        ])
+    }
+
+    fn connection(endpoint: &str, dialect: &str) -> super::Connection {
+        let content = format!(
+            "dialect = \"{}\"\nnamespace = \"test\"\ndatabase = \"app\"\n\n[connection]\n\
+             endpoint = \"{}\"\nusername = \"root\"\npassword = \"secret\"\n",
+            dialect, endpoint
+        );
+        let path = std::path::Path::new("/project/surrealql.toml");
+        let (config, diagnostics) = surrealql_analysis::config::parse(path, &content);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        super::Connection::from_config(config.as_ref()).unwrap()
+    }
+
+    /// Returns the query that `command` runs for `DOCUMENT` with the other fields of `argument`.
+    fn selected(command: &str, mut argument: serde_json::Value) -> Result<super::Query, String> {
+        let uri = tower_lsp::lsp_types::Url::parse("file:///project/queries.surql").unwrap();
+        let mut host = surrealql_analysis::AnalysisHost::default();
+        host.set_file_text(uri.clone(), String::from(DOCUMENT));
+        let doc = lsp_textdocument::FullTextDocument::new(
+            String::from("surrealql"),
+            1,
+            String::from(DOCUMENT),
+        );
+        argument["textDocument"] = serde_json::json!({ "uri": uri });
+        let params = super::params(command, argument)?;
+        Ok(super::query(&host.analysis(), &doc, &params).unwrap())
+    }
+
+    fn range(line: u32, start: u32, end: u32) -> tower_lsp::lsp_types::Range {
+        tower_lsp::lsp_types::Range::new(
+            tower_lsp::lsp_types::Position::new(line, start),
+            tower_lsp::lsp_types::Position::new(line, end),
+        )
+    }
+
+    async fn listen() -> (tokio::net::TcpListener, std::net::SocketAddr) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        (listener, address)
+    }
+
+    /// Answers a single HTTP request with `status` and `body`, returning the endpoint and the
+    /// request it received.
+    async fn serve_http(status: u16, body: String) -> (String, tokio::task::JoinHandle<String>) {
+        let (listener, address) = listen().await;
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            loop {
+                let read = tokio::io::AsyncReadExt::read(&mut stream, &mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                let Some(end) = text.find("\r\n\r\n") else {
+                    continue;
+                };
+                let length = text[..end]
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .map_or(0, |(_, value)| value.trim().parse().unwrap());
+                if read == 0 || request.len() >= end + 4 + length {
+                    break;
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\
+                 connection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            tokio::io::AsyncWriteExt::write_all(&mut stream, response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (format!("http://{}", address), server)
+    }
+
+    /// Answers the RPC requests of a single connection with `answer`, sending a live query
+    /// notification before each response. Returns the endpoint, and the path the connection was
+    /// made to along with the method and params of each request.
+    async fn serve_rpc(
+        answer: fn(&str) -> Result<serde_json::Value, String>,
+    ) -> (String, tokio::task::JoinHandle<(String, Vec<(String, serde_json::Value)>)>) {
+        let (listener, address) = listen().await;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut request_line = [0; 64];
+            let read = stream.peek(&mut request_line).await.unwrap();
+            let request_line = String::from_utf8_lossy(&request_line[..read]);
+            let path = request_line.split(' ').nth(1).unwrap().to_string();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut calls = Vec::new();
+            while let Some(Ok(message)) = futures_util::StreamExt::next(&mut socket).await {
+                let tokio_tungstenite::tungstenite::Message::Text(text) = message else {
+                    continue;
+                };
+                let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                let method = request["method"].as_str().unwrap().to_string();
+                let id = request["id"].clone();
+                let response = match answer(&method) {
+                    Ok(result) => serde_json::json!({ "id": id, "result": result }),
+                    Err(message) => serde_json::json!({
+                        "id": id,
+                        "error": { "code": -32000, "message": message },
+                    }),
+                };
+                let notification = serde_json::json!({ "result": { "action": "CREATE" } });
+                for message in [notification, response] {
+                    let message =
+                        tokio_tungstenite::tungstenite::Message::Text(message.to_string());
+                    futures_util::SinkExt::send(&mut socket, message).await.unwrap();
+                }
+                calls.push((method, request["params"].clone()));
+            }
+            (path, calls)
+        });
+        (format!("ws://{}", address), server)
+    }
+
+    #[test]
+    fn execute_statement_runs_the_statement_under_the_cursor_or_within_the_range() {
+        let cursor = serde_json::json!({ "position": { "line": 2, "character": 3 } });
+        let query = selected(super::EXECUTE_STATEMENT, cursor).unwrap();
+        assert_eq!(query.text, "SELECT * FROM person;\n");
+        assert_eq!(query.ranges, [range(2, 0, 20)]);
+
+        let start = serde_json::json!({ "line": 0, "character": 0 });
+        let end = serde_json::json!({ "line": 2, "character": 0 });
+        let selection = serde_json::json!({ "range": { "start": start, "end": end } });
+        let query = selected(super::EXECUTE_STATEMENT, selection).unwrap();
+        assert_eq!(
+            query.text,
+            "CREATE person:tobie SET name = 'Tobie';\nBEGIN;\nSELECT * FROM person;\n"
+        );
+        assert_eq!(query.ranges, [range(0, 0, 38), range(2, 0, 20)]);
+
+        let error = selected(super::EXECUTE_STATEMENT, serde_json::json!({}));
+        assert_eq!(error.err().unwrap(), "Either a `position` or a `range` is required");
+    }
+
+    #[test]
+    fn execute_file_runs_every_statement() {
+        let cursor = serde_json::json!({ "position": { "line": 2, "character": 3 } });
+        let query = selected(super::EXECUTE_FILE, cursor).unwrap();
+        assert_eq!(query.text, DOCUMENT);
+        assert_eq!(query.ranges, [range(0, 0, 38), range(2, 0, 20)]);
+
+        let error = selected("surrealql.executeNothing", serde_json::json!({}));
+        assert_eq!(error.err().unwrap(), "Unknown command `surrealql.executeNothing`");
+    }
+
+    #[tokio::test]
+    async fn posts_the_query_to_the_sql_endpoint() {
+        let (endpoint, server) = serve_http(200, reported().to_string()).await;
+        let query = selected(super::EXECUTE_FILE, serde_json::json!({})).unwrap();
+        let results = connection(&endpoint, "2.x").execute(&query).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(results).unwrap(),
+            serde_json::json!([
+                {
+                    "range": range(0, 0, 38),
+                    "status": "OK",
+                    "time": "1.5ms",
+                    "result": [{ "id": "person:tobie", "name": "Tobie" }],
+                },
+                {
+                    "range": range(2, 0, 20),
+                    "status": "ERR",
+                    "time": "250µs",
+                    "result": "The table does not exist",
+                },
+            ])
+        );
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /sql HTTP/1.1\r\n"), "{}", request);
+        for header in
+            ["surreal-ns: test", "surreal-db: app", "authorization: Basic cm9vdDpzZWNyZXQ="]
+        {
+            assert!(request.contains(header), "{} in {}", header, request);
+        }
+        assert!(request.ends_with(&format!("\r\n\r\n{}", DOCUMENT)), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn sends_the_namespace_and_database_headers_of_the_dialect() {
+        let (endpoint, server) = serve_http(200, reported().to_string()).await;
+        let query = selected(super::EXECUTE_FILE, serde_json::json!({})).unwrap();
+        connection(&endpoint, "1.x").execute(&query).await.unwrap();
+        let request = server.await.unwrap();
+        assert!(request.contains("\r\nns: test\r\n"), "{}", request);
+        assert!(request.contains("\r\ndb: app\r\n"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn reports_failed_requests() {
+        let (endpoint, server) =
+            serve_http(400, String::from("{\"information\":\"Bad query\"}\n")).await;
+        let query = selected(super::EXECUTE_FILE, serde_json::json!({})).unwrap();
+        let error = connection(&endpoint, "2.x").execute(&query).await.unwrap_err();
+        assert_eq!(
+            error,
+            format!(
+                "{}/ responded with 400 Bad Request: {{\"information\":\"Bad query\"}}",
+                endpoint
+            )
+        );
+        server.await.unwrap();
+
+        let (endpoint, server) = serve_http(200, String::from("[{\"status\":\"OK\"}]")).await;
+        let error = connection(&endpoint, "2.x").execute(&query).await.unwrap_err();
+        assert_eq!(
+            error,
+            format!(
+                "Unexpected response from {}/: missing field `time` at line 1 column 16",
+                endpoint
+            )
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn signs_in_and_uses_the_database_over_rpc() {
+        let (endpoint, server) = serve_rpc(|method| match method {
+            "query" => Ok(reported()),
+            _ => Ok(serde_json::Value::Null),
+        })
+        .await;
+        let cursor = serde_json::json!({ "position": { "line": 0, "character": 0 } });
+        let query = selected(super::EXECUTE_STATEMENT, cursor).unwrap();
+        let results = connection(&endpoint, "2.x").execute(&query).await.unwrap();
+        // Two results for one statement cannot be matched up with the statements.
+        assert_eq!(serde_json::to_value(results).unwrap(), reported());
+
+        let (path, calls) = server.await.unwrap();
+        assert_eq!(path, "/rpc");
+        assert_eq!(
+            calls,
+            [
+                (String::from("signin"), serde_json::json!([{ "user": "root", "pass": "secret" }])),
+                (String::from("use"), serde_json::json!(["test", "app"])),
+                (
+                    String::from("query"),
+                    serde_json::json!(["CREATE person:tobie SET name = 'Tobie';\n"]),
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_failed_rpc_calls() {
+        let (endpoint, server) = serve_rpc(|method| match method {
+            "signin" => Err(String::from("There was a problem with authentication")),
+            _ => Ok(serde_json::Value::Null),
+        })
+        .await;
+        let query = selected(super::EXECUTE_FILE, serde_json::json!({})).unwrap();
+        let error = connection(&endpoint, "2.x").execute(&query).await.unwrap_err();
+        assert_eq!(error, "`signin` failed: There was a problem with authentication");
+        let (_, calls) = server.await.unwrap();
+        assert_eq!(calls.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let (listener, address) = listen().await;
+        // Accepts the connection but never answers.
+        let server = tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+        let mut connection = connection(&format!("http://{}", address), "2.x");
+        assert_eq!(connection.timeout, super::TIMEOUT);
+        connection.timeout = std::time::Duration::from_millis(100);
+        let query = selected(super::EXECUTE_FILE, serde_json::json!({})).unwrap();
+        let error = connection.execute(&query).await.unwrap_err();
+        assert_eq!(error, "Timed out after 100ms");
+        server.abort();
+    }
+}
@@ -0,0 +1,66 @@
+//! The `fmt` subcommand, which formats `.surql` files exactly as the server does, so that
+//! pre-commit hooks and CI can enforce the layout.
+
+/// Formats the `.surql` files at `paths`, or below them for directories, in place, with the
+/// `[format]` settings of their projects. With `check`, the files are left untouched and the diff
+/// of each unformatted file is printed instead. Returns whether every file was formatted already,
+/// which only matters with `check`.
+pub(crate) fn run(paths: &[std::path::PathBuf], check: bool) -> Result<bool, String> {
+    let mut host = surrealql_analysis::AnalysisHost::load(paths)?;
+    let mut is_formatted = true;
+    for file in crate::check::files(paths)? {
+        let uri = crate::check::open(&mut host, &file)?;
+        let analysis = host.analysis();
+        let edits = analysis.format(&uri);
+        if edits.is_empty() {
+            continue;
+        }
+        let text = analysis.file_text(&uri).unwrap_or_default();
+        let formatted = surrealql_analysis::TextEdit::apply(text, &edits);
+        if check {
+            let name = crate::check::display(&uri);
+            let diff = similar::TextDiff::from_lines(text, &formatted);
+            print!("{}", diff.unified_diff().header(&name, &name));
+            is_formatted = false;
+        } else {
+            std::fs::write(&file, formatted)
+                .map_err(|err| format!("Failed to write {}: {}", file.display(), err))?;
+        }
+    }
+    Ok(is_formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn checks_and_formats_files_with_the_settings_of_their_project() {
+        let dir = std::env::temp_dir().join(format!("surrealql-fmt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("surrealql.toml"), "[format]\nindent-width = 2\n").unwrap();
+        let file = dir.join("query.surql");
+        let unformatted = "SELECT *\nFROM person\n\n\nWHERE age > 18;";
+        std::fs::write(&file, unformatted).unwrap();
+        let paths = [dir.clone()];
+        let read = || std::fs::read_to_string(&file).unwrap();
+
+        let checked = super::run(&paths, true);
+        let after_check = read();
+        let formatted = super::run(&paths, false);
+        let after_format = read();
+        let checked_again = super::run(&paths, true);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(checked, Ok(false));
+        assert_eq!(after_check, unformatted);
+        assert!(formatted.is_ok());
+        assert_eq!(after_format, "SELECT *\n  FROM person\n\n  WHERE age > 18;\n");
+        assert_eq!(checked_again, Ok(true));
+    }
+
+    #[test]
+    fn reports_paths_that_cannot_be_read() {
+        let missing = std::env::temp_dir().join("surrealql-fmt-missing/query.surql");
+        let error = super::run(std::slice::from_ref(&missing), true).unwrap_err();
+        assert!(error.starts_with(&format!("Failed to read {}", missing.display())), "{}", error);
+    }
+}
@@ -0,0 +1,357 @@
+//! Reads the schema of a live database with `INFO FOR DB` and `INFO FOR TABLE`, so that it can be
+//! merged into the workspace schema.
+
+/// Introspects the databases of every project again. Takes no arguments.
+pub(crate) const REFRESH_SCHEMA: &str = "surrealql.refreshSchema";
+
+/// Returns the `DEFINE` statements of the database `connection` uses, as a SurrealQL document,
+/// which is parsed like any other file.
+///
+/// SurrealDB 2.x reports the structure of each definition with `INFO ... STRUCTURE`, from which
+/// the statements are written. SurrealDB 1.x only reports the statements themselves.
+pub(crate) async fn introspect(connection: &crate::execute::Connection) -> Result<String, String> {
+    let definitions = match connection.dialect() {
+        surrealql_analysis::config::Dialect::V1 => introspect_statements(connection).await?,
+        surrealql_analysis::config::Dialect::V2 => introspect_structure(connection).await?,
+    };
+    Ok(definitions.join(";\n"))
+}
+
+/// Collects the statements reported by `INFO FOR DB` and `INFO FOR TABLE`.
+async fn introspect_statements(
+    connection: &crate::execute::Connection,
+) -> Result<Vec<String>, String> {
+    let database = info(connection, String::from("INFO FOR DB;")).await?;
+    let mut definitions = Vec::new();
+    collect_definitions(&database, &mut definitions);
+
+    // Tables are listed under `tb` before SurrealDB 1.1.
+    let tables: Vec<&String> = database
+        .iter()
+        .flat_map(|result| ["tables", "tb"].map(|key| result.get(key)))
+        .flatten()
+        .filter_map(serde_json::Value::as_object)
+        .flat_map(|tables| tables.keys())
+        .collect();
+    if !tables.is_empty() {
+        let query = tables
+            .iter()
+            .map(|table| format!("INFO FOR TABLE `{}`;\n", table.replace('`', "\\`")))
+            .collect();
+        collect_definitions(&info(connection, query).await?, &mut definitions);
+    }
+    Ok(definitions)
+}
+
+/// Writes the statements of the structures reported by `INFO FOR DB STRUCTURE` and
+/// `INFO FOR TABLE ... STRUCTURE`.
+async fn introspect_structure(
+    connection: &crate::execute::Connection,
+) -> Result<Vec<String>, String> {
+    let database = info(connection, String::from("INFO FOR DB STRUCTURE;")).await?;
+    let database = database.into_iter().next().unwrap_or_default();
+    let query: String = items(&database, "tables")
+        .filter_map(|table| text(table, "name"))
+        .map(|table| format!("INFO FOR TABLE {} STRUCTURE;\n", ident(table)))
+        .collect();
+    let tables = if query.is_empty() { Vec::new() } else { info(connection, query).await? };
+    Ok(structure_definitions(&database, &tables))
+}
+
+/// Runs `INFO` statements, returning their results or the first error.
+async fn info(
+    connection: &crate::execute::Connection,
+    text: String,
+) -> Result<Vec<serde_json::Value>, String> {
+    let query = crate::execute::Query { text, ranges: Vec::new() };
+    let mut results = Vec::new();
+    for result in connection.execute(&query).await? {
+        if result.status != "OK" {
+            return Err(format!("Failed to introspect the database: {}", result.result));
+        }
+        results.push(result.result);
+    }
+    Ok(results)
+}
+
+/// Collects the `DEFINE` statements found anywhere within `values`.
+fn collect_definitions<'v>(
+    values: impl IntoIterator<Item = &'v serde_json::Value>,
+    definitions: &mut Vec<String>,
+) {
+    for value in values {
+        match value {
+            serde_json::Value::String(text) if is_definition(text) => {
+                definitions.push(text.clone());
+            }
+            serde_json::Value::Array(values) => collect_definitions(values, definitions),
+            serde_json::Value::Object(values) => collect_definitions(values.values(), definitions),
+            _ => {}
+        }
+    }
+}
+
+fn is_definition(text: &str) -> bool {
+    text.get(..7).is_some_and(|start| start.eq_ignore_ascii_case("DEFINE "))
+}
+
+/// Returns the statements of the params, functions and tables of `database`, followed by the
+/// fields and indexes of `tables`, skipping any structure without a name.
+fn structure_definitions(
+    database: &serde_json::Value,
+    tables: &[serde_json::Value],
+) -> Vec<String> {
+    let mut definitions = Vec::new();
+    definitions.extend(items(database, "params").filter_map(param));
+    definitions.extend(items(database, "functions").filter_map(function));
+    definitions.extend(items(database, "tables").filter_map(table));
+    for table in tables {
+        definitions.extend(items(table, "fields").filter_map(field));
+        definitions.extend(items(table, "indexes").filter_map(index));
+    }
+    definitions
+}
+
+fn param(param: &serde_json::Value) -> Option<String> {
+    let name = text(param, "name")?.trim_start_matches('$');
+    let mut statement = format!("DEFINE PARAM ${}", name);
+    if let Some(value) = text(param, "value") {
+        statement.push_str(&format!(" VALUE {}", value));
+    }
+    Some(statement + &comment(param) + &permissions(param))
+}
+
+fn function(function: &serde_json::Value) -> Option<String> {
+    let name = text(function, "name")?;
+    let name = name.strip_prefix("fn::").unwrap_or(name);
+    let args: Vec<String> = items(function, "args")
+        .filter_map(|arg| match arg.as_array()?.as_slice() {
+            [name, kind] => {
+                Some(format!("${}: {}", name.as_str()?.trim_start_matches('$'), kind.as_str()?))
+            }
+            _ => None,
+        })
+        .collect();
+    let mut statement = format!("DEFINE FUNCTION fn::{}({})", name, args.join(", "));
+    if let Some(returns) = text(function, "returns") {
+        statement.push_str(&format!(" -> {}", returns));
+    }
+    statement.push_str(&format!(" {}", text(function, "block").unwrap_or("{}")));
+    Some(statement + &comment(function) + &permissions(function))
+}
+
+fn table(table: &serde_json::Value) -> Option<String> {
+    let mut statement = format!("DEFINE TABLE {}", ident(text(table, "name")?));
+    if flag(table, "drop") {
+        statement.push_str(" DROP");
+    }
+    statement.push_str(if flag(table, "full") { " SCHEMAFULL" } else { " SCHEMALESS" });
+    if let Some(kind) = table.get("kind") {
+        statement.push_str(&format!(" TYPE {}", text(kind, "kind").unwrap_or("ANY")));
+        for (key, keyword) in [("in", "IN"), ("out", "OUT")] {
+            let tables: Vec<String> =
+                items(kind, key).filter_map(|table| table.as_str()).map(ident).collect();
+            if !tables.is_empty() {
+                statement.push_str(&format!(" {} {}", keyword, tables.join(" | ")));
+            }
+        }
+        if flag(kind, "enforced") {
+            statement.push_str(" ENFORCED");
+        }
+    }
+    if let Some(view) = text(table, "view") {
+        statement.push_str(&format!(" {}", view));
+    }
+    Some(statement + &comment(table) + &permissions(table))
+}
+
+fn field(field: &serde_json::Value) -> Option<String> {
+    let name = text(field, "name")?;
+    let mut statement = format!("DEFINE FIELD {} ON {}", name, ident(text(field, "what")?));
+    if flag(field, "flex") {
+        statement.push_str(" FLEXIBLE");
+    }
+    if let Some(kind) = text(field, "kind") {
+        statement.push_str(&format!(" TYPE {}", kind));
+    }
+    if flag(field, "readonly") {
+        statement.push_str(" READONLY");
+    }
+    for (key, keyword) in [("value", "VALUE"), ("assert", "ASSERT"), ("default", "DEFAULT")] {
+        if let Some(clause) = text(field, key) {
+            statement.push_str(&format!(" {} {}", keyword, clause));
+        }
+    }
+    Some(statement + &comment(field) + &permissions(field))
+}
+
+fn index(index: &serde_json::Value) -> Option<String> {
+    let name = text(index, "name")?;
+    let columns: Vec<&str> = items(index, "cols").filter_map(serde_json::Value::as_str).collect();
+    let mut statement = format!(
+        "DEFINE INDEX {} ON {} FIELDS {}",
+        ident(name),
+        ident(text(index, "what")?),
+        columns.join(", ")
+    );
+    // Only unique indexes matter to the analysis, so other kinds are written as plain indexes.
+    if text(index, "index").is_some_and(|kind| kind.eq_ignore_ascii_case("UNIQUE")) {
+        statement.push_str(" UNIQUE");
+    }
+    Some(statement + &comment(index))
+}
+
+/// Writes the `COMMENT` clause of a structure, if it has one.
+fn comment(structure: &serde_json::Value) -> String {
+    match structure.get("comment").filter(|comment| comment.is_string()) {
+        Some(comment) => format!(" COMMENT {}", comment),
+        None => String::new(),
+    }
+}
+
+/// Writes the `PERMISSIONS` clause of a structure, in which `true` stands for `FULL` and `false`
+/// for `NONE`, and a clause per kind of access is reported as an object.
+fn permissions(structure: &serde_json::Value) -> String {
+    let permission = |permission: &serde_json::Value| match permission {
+        serde_json::Value::Bool(true) => Some(String::from("FULL")),
+        serde_json::Value::Bool(false) => Some(String::from("NONE")),
+        serde_json::Value::String(clause) => Some(clause.clone()),
+        _ => None,
+    };
+    let clause = match structure.get("permissions") {
+        Some(serde_json::Value::Object(kinds)) => {
+            let kinds: Vec<String> = kinds
+                .iter()
+                .filter_map(|(kind, clause)| Some(format!("FOR {} {}", kind, permission(clause)?)))
+                .collect();
+            Some(kinds.join(", ")).filter(|clause| !clause.is_empty())
+        }
+        Some(clause) => permission(clause),
+        None => None,
+    };
+    clause.map(|clause| format!(" PERMISSIONS {}", clause)).unwrap_or_default()
+}
+
+fn items<'v>(
+    structure: &'v serde_json::Value,
+    key: &str,
+) -> impl Iterator<Item = &'v serde_json::Value> {
+    structure.get(key).and_then(serde_json::Value::as_array).into_iter().flatten()
+}
+
+fn text<'v>(structure: &'v serde_json::Value, key: &str) -> Option<&'v str> {
+    structure.get(key).and_then(serde_json::Value::as_str)
+}
+
+fn flag(structure: &serde_json::Value, key: &str) -> bool {
+    structure.get(key).and_then(serde_json::Value::as_bool).unwrap_or(false)
+}
+
+/// Quotes `name` in backticks unless it is a plain identifier.
+fn ident(name: &str) -> String {
+    let plain = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "\\`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn writes_the_statements_of_the_database_structure() {
+        let database = serde_json::json!({
+            "accesses": [],
+            "functions": [{
+                "name": "greet",
+                "args": [["name", "string"]],
+                "block": "{ RETURN 'Hello ' + $name; }",
+                "permissions": true,
+                "returns": "string",
+            }],
+            "params": [{ "name": "limit", "value": "10", "permissions": true }],
+            "tables": [
+                {
+                    "name": "person",
+                    "drop": false,
+                    "full": true,
+                    "kind": { "kind": "NORMAL" },
+                    "permissions": {
+                        "create": false,
+                        "delete": false,
+                        "select": "WHERE published = true",
+                        "update": false,
+                    },
+                },
+                {
+                    "name": "likes",
+                    "drop": false,
+                    "full": false,
+                    "kind": { "kind": "RELATION", "in": ["person"], "out": ["post", "my-post"] },
+                    "comment": "Who likes what",
+                },
+                { "drop": false },
+            ],
+        });
+        let tables = [serde_json::json!({
+            "events": [],
+            "fields": [
+                {
+                    "name": "email",
+                    "what": "person",
+                    "kind": "option<string>",
+                    "flex": false,
+                    "readonly": true,
+                    "assert": "string::is::email($value)",
+                },
+                { "name": "settings", "what": "person", "kind": "object", "flex": true },
+            ],
+            "indexes": [
+                { "name": "email", "what": "person", "cols": ["email"], "index": "UNIQUE" },
+                { "name": "name", "what": "person", "cols": ["first", "last"], "index": "IDX" },
+            ],
+        })];
+        assert_eq!(
+            super::structure_definitions(&database, &tables),
+            [
+                "DEFINE PARAM $limit VALUE 10 PERMISSIONS FULL",
+                "DEFINE FUNCTION fn::greet($name: string) -> string { RETURN 'Hello ' + $name; } \
+                 PERMISSIONS FULL",
+                "DEFINE TABLE person SCHEMAFULL TYPE NORMAL PERMISSIONS FOR create NONE, \
+                 FOR delete NONE, FOR select WHERE published = true, FOR update NONE",
+                "DEFINE TABLE likes SCHEMALESS TYPE RELATION IN person OUT post | `my-post` \
+                 COMMENT \"Who likes what\"",
+                "DEFINE FIELD email ON person TYPE option<string> READONLY \
+                 ASSERT string::is::email($value)",
+                "DEFINE FIELD settings ON person FLEXIBLE TYPE object",
+                "DEFINE INDEX email ON person FIELDS email UNIQUE",
+                "DEFINE INDEX name ON person FIELDS first, last",
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_the_statements_of_1x() {
+        let database = [serde_json::json!({
+            "analyzers": {},
+            "tables": { "person": "DEFINE TABLE person SCHEMALESS" },
+            "params": { "limit": "DEFINE PARAM $limit VALUE 10" },
+            "users": { "root": "define user root ON DATABASE PASSHASH '...'" },
+            "note": "not a definition",
+        })];
+        let mut definitions = Vec::new();
+        super::collect_definitions(&database, &mut definitions);
+        definitions.sort();
+        assert_eq!(
+            definitions,
+            [
+                "DEFINE PARAM $limit VALUE 10",
+                "DEFINE TABLE person SCHEMALESS",
+                "define user root ON DATABASE PASSHASH '...'",
+            ]
+        );
+    }
+}
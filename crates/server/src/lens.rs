@@ -0,0 +1,82 @@
+//! Code lenses running the statements and transactions of a document with the execute commands.
+
+/// Returns a "▶ Run" lens above every top-level statement, an "Explain" lens above every `SELECT`
+/// and a "▶ Run transaction" lens above every `BEGIN`.
+pub(crate) fn code_lenses(
+    analysis: &surrealql_analysis::Analysis,
+    uri: &tower_lsp::lsp_types::Url,
+    doc: &lsp_textdocument::FullTextDocument,
+) -> Vec<tower_lsp::lsp_types::CodeLens> {
+    analysis.runnables(uri).into_iter().map(|runnable| lens(uri, doc, runnable)).collect()
+}
+
+/// Returns a lens shown above `runnable` that runs its statements.
+fn lens(
+    uri: &tower_lsp::lsp_types::Url,
+    doc: &lsp_textdocument::FullTextDocument,
+    runnable: surrealql_analysis::Runnable,
+) -> tower_lsp::lsp_types::CodeLens {
+    let title = match runnable.kind {
+        surrealql_analysis::RunnableKind::Statement => "▶ Run",
+        surrealql_analysis::RunnableKind::Explain => "Explain",
+        surrealql_analysis::RunnableKind::Transaction => "▶ Run transaction",
+    };
+    let params = crate::execute::ExecuteParams {
+        text_document: tower_lsp::lsp_types::TextDocumentIdentifier { uri: uri.clone() },
+        position: None,
+        range: Some(crate::convert::range(doc, &runnable.statements)),
+        explain: runnable.kind == surrealql_analysis::RunnableKind::Explain,
+    };
+    tower_lsp::lsp_types::CodeLens {
+        range: crate::convert::range(doc, &runnable.range),
+        command: Some(tower_lsp::lsp_types::Command {
+            title: String::from(title),
+            command: String::from(crate::execute::EXECUTE_STATEMENT),
+            arguments: serde_json::to_value(params).ok().map(|params| vec![params]),
+        }),
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn lenses_run_each_statement_and_transaction() {
+        let document = "SELECT * FROM person;\nBEGIN;\nDELETE person;\nCOMMIT;\n";
+        let uri = tower_lsp::lsp_types::Url::parse("file:///project/queries.surql").unwrap();
+        let mut host = surrealql_analysis::AnalysisHost::default();
+        host.set_file_text(uri.clone(), String::from(document));
+        let doc = lsp_textdocument::FullTextDocument::new(
+            String::from("surrealql"),
+            1,
+            String::from(document),
+        );
+
+        let lenses: Vec<(u32, String, serde_json::Value)> =
+            super::code_lenses(&host.analysis(), &uri, &doc)
+                .into_iter()
+                .map(|lens| {
+                    let command = lens.command.unwrap();
+                    assert_eq!(command.command, crate::execute::EXECUTE_STATEMENT);
+                    let argument = command.arguments.unwrap().remove(0);
+                    (lens.range.start.line, command.title, argument)
+                })
+                .collect();
+        let titles: Vec<(u32, &str)> =
+            lenses.iter().map(|(line, title, _)| (*line, title.as_str())).collect();
+        assert_eq!(titles, [(0, "▶ Run"), (0, "Explain"), (1, "▶ Run transaction"), (2, "▶ Run")]);
+
+        let (_, _, explain) = &lenses[1];
+        assert_eq!(explain["explain"], true);
+        assert_eq!(explain["textDocument"]["uri"], uri.as_str());
+        let (_, _, transaction) = &lenses[2];
+        assert_eq!(
+            transaction["range"],
+            serde_json::json!({
+                "start": { "line": 1, "character": 0 },
+                "end": { "line": 3, "character": 6 },
+            })
+        );
+        assert_eq!(transaction.get("explain"), None);
+    }
+}
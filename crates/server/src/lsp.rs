@@ -1,64 +1,481 @@
-fn text_doc_change_to_tree_sitter_edit(
-    change: &tower_lsp::lsp_types::TextDocumentContentChangeEvent,
-    doc: &lsp_textdocument::FullTextDocument,
-) -> Result<tree_sitter::InputEdit, &'static str> {
-    let range = change.range.as_ref().ok_or("Invalid edit range")?;
-    let start = range.start;
-    let end = range.end;
-
-    let start_byte = doc.offset_at(start) as usize;
-    let old_end_byte = doc.offset_at(end) as usize;
-    let new_end_byte = start_byte + change.text.len();
-
-    let new_end_pos = doc.position_at(new_end_byte as u32);
-
-    Ok(tree_sitter::InputEdit {
-        start_byte,
-        old_end_byte,
-        new_end_byte,
-        start_position: tree_sitter::Point {
-            row: start.line as usize,
-            column: start.character as usize,
-        },
-        old_end_position: tree_sitter::Point {
-            row: end.line as usize,
-            column: end.character as usize,
-        },
-        new_end_position: tree_sitter::Point {
-            row: new_end_pos.line as usize,
-            column: new_end_pos.character as usize,
-        },
-    })
+/// The number of closed files read at a time while the workspace is indexed.
+const INDEX_BATCH_SIZE: usize = 100;
+
+/// What edits change: the open documents and the analysis of the workspace, which are changed
+/// together under one lock so that they always agree.
+#[derive(Default)]
+struct State {
+    /// The open documents, which map the positions of LSP to the offsets of the analysis. They are
+    /// shared with the requests that read them.
+    documents: std::collections::BTreeMap<
+        tower_lsp::lsp_types::Url,
+        std::sync::Arc<lsp_textdocument::FullTextDocument>,
+    >,
+    host: surrealql_analysis::AnalysisHost,
+}
+
+impl State {
+    /// Returns a snapshot of the analysis and the open document at `uri`.
+    fn snapshot(
+        &self,
+        uri: &tower_lsp::lsp_types::Url,
+    ) -> Option<(surrealql_analysis::Analysis, std::sync::Arc<lsp_textdocument::FullTextDocument>)>
+    {
+        let doc = self.documents.get(uri)?;
+        Some((self.host.analysis(), doc.clone()))
+    }
+}
+
+/// Flags its request as cancelled when dropped, as tower-lsp drops the handler of a request that
+/// `$/cancelRequest` cancels.
+struct CancelOnDrop(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Answers a request with `f`, which runs on a blocking thread against the snapshot `analysis`, so
+/// that requests are answered in parallel without holding up edits. It is skipped when the request
+/// is cancelled before it starts, and the request fails as "content modified" when its `document`
+/// is edited or closed before it is answered, as its answer would be outdated. Requests about the
+/// whole workspace, without a document, fail when anything in the workspace changes.
+async fn compute<T: Send + 'static>(
+    analysis: surrealql_analysis::Analysis,
+    document: Option<tower_lsp::lsp_types::Url>,
+    f: impl FnOnce(&surrealql_analysis::Analysis) -> T + Send + 'static,
+) -> tower_lsp::jsonrpc::Result<T> {
+    let cancelled = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let _guard = CancelOnDrop(cancelled.clone());
+    let task = tokio::task::spawn_blocking(move || {
+        let modified = |analysis: &surrealql_analysis::Analysis| match &document {
+            Some(document) => analysis.is_modified(document),
+            None => analysis.is_cancelled(),
+        };
+        if cancelled.load(std::sync::atomic::Ordering::Relaxed) || modified(&analysis) {
+            return None;
+        }
+        let result = f(&analysis);
+        (!modified(&analysis)).then_some(result)
+    });
+    match task.await {
+        Ok(Some(result)) => Ok(result),
+        Ok(None) => Err(tower_lsp::jsonrpc::Error::content_modified()),
+        Err(err) => Err(tower_lsp::jsonrpc::Error {
+            code: tower_lsp::jsonrpc::ErrorCode::InternalError,
+            message: err.to_string().into(),
+            data: None,
+        }),
+    }
 }
 
 pub(crate) struct Backend {
     client: tower_lsp::Client,
-    parser: std::sync::Arc<tokio::sync::Mutex<tree_sitter::Parser>>,
-    curr_doc: std::sync::Arc<tokio::sync::Mutex<Option<lsp_textdocument::FullTextDocument>>>,
-    tree: std::sync::Arc<tokio::sync::Mutex<Option<tree_sitter::Tree>>>,
-    kw_docs: crate::keywords::KeywordDocsMap,
+    /// Only held to apply an edit or to take a snapshot, never while a request is answered.
+    state: std::sync::Arc<tokio::sync::Mutex<State>>,
+    workspace_roots: std::sync::Arc<tokio::sync::Mutex<Vec<std::path::PathBuf>>>,
+    /// Project files keyed by path; `None` when the file exists but could not be read.
+    configs: std::sync::Arc<
+        tokio::sync::Mutex<
+            std::collections::BTreeMap<
+                std::path::PathBuf,
+                Option<surrealql_analysis::config::ProjectConfig>,
+            >,
+        >,
+    >,
+    can_register_file_watchers: std::sync::atomic::AtomicBool,
+    /// Whether the client pulls diagnostics, in which case they are not published.
+    pulls_diagnostics: std::sync::atomic::AtomicBool,
+    can_refresh_diagnostics: std::sync::atomic::AtomicBool,
+    can_report_progress: std::sync::atomic::AtomicBool,
 }
 
 impl Backend {
     pub(crate) fn new(client: tower_lsp::Client) -> Self {
         Self {
             client,
-            parser: std::sync::Arc::new(
-                tokio::sync::Mutex::new(crate::parser::initialise_parser()),
+            state: std::sync::Arc::default(),
+            workspace_roots: std::sync::Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            configs: std::sync::Arc::new(
+                tokio::sync::Mutex::new(std::collections::BTreeMap::new()),
             ),
-            curr_doc: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
-            tree: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
-            kw_docs: crate::keywords::load_kw_docs(),
+            can_register_file_watchers: std::sync::atomic::AtomicBool::new(false),
+            pulls_diagnostics: std::sync::atomic::AtomicBool::new(false),
+            can_refresh_diagnostics: std::sync::atomic::AtomicBool::new(false),
+            can_report_progress: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    /// Answers a request about the open document at `uri` with `f`, or with `None` when the
+    /// document is not open.
+    async fn read<T: Send + 'static>(
+        &self,
+        uri: tower_lsp::lsp_types::Url,
+        f: impl FnOnce(
+                &surrealql_analysis::Analysis,
+                &tower_lsp::lsp_types::Url,
+                &lsp_textdocument::FullTextDocument,
+            ) -> T
+            + Send
+            + 'static,
+    ) -> tower_lsp::jsonrpc::Result<Option<T>> {
+        let Some((analysis, doc)) = self.state.lock().await.snapshot(&uri) else {
+            return Ok(None);
+        };
+        compute(analysis, Some(uri.clone()), move |analysis| f(analysis, &uri, &doc))
+            .await
+            .map(Some)
+    }
+
+    /// Brings the diagnostics of every document up to date after the schema or the project
+    /// configurations changed: clients pulling diagnostics are asked to pull them again, and
+    /// those of the open documents are published again for the others.
+    async fn refresh_diagnostics(&self) {
+        if self.pulls_diagnostics.load(std::sync::atomic::Ordering::Relaxed) {
+            if !self.can_refresh_diagnostics.load(std::sync::atomic::Ordering::Relaxed) {
+                return;
+            }
+            if let Err(err) = self.client.workspace_diagnostic_refresh().await {
+                self.client
+                    .log_message(
+                        tower_lsp::lsp_types::MessageType::WARNING,
+                        format!("Failed to refresh diagnostics: {}", err),
+                    )
+                    .await;
+            }
+            return;
+        }
+        let documents: Vec<(tower_lsp::lsp_types::Url, i32)> = {
+            let state = self.state.lock().await;
+            state.documents.iter().map(|(uri, doc)| (uri.clone(), doc.version())).collect()
+        };
+        for (uri, version) in documents {
+            self.analyse_document(uri, version);
+        }
+    }
+
+    /// Returns the configuration of the project containing `uri`, preferring the innermost one.
+    async fn config_for(
+        &self,
+        uri: &tower_lsp::lsp_types::Url,
+    ) -> Option<surrealql_analysis::config::ProjectConfig> {
+        self.state.lock().await.host.analysis().config(uri).cloned()
+    }
+
+    /// Re-indexes the schema files and snapshots of every project, followed by the closed files.
+    async fn index_workspace(&self) {
+        let errors = self.state.lock().await.host.index_workspace();
+        for error in errors {
+            self.client.log_message(tower_lsp::lsp_types::MessageType::WARNING, error).await;
+        }
+        self.index_closed_files().await;
+    }
+
+    /// Indexes every `.surql` file below the workspace roots that is not open, reporting the
+    /// progress of reading them. They are read in batches off the lock, so that edits and requests
+    /// are answered in the meantime, with the files of later batches as they were indexed before.
+    /// The files that were not found again are forgotten once every batch has been read.
+    async fn index_closed_files(&self) {
+        let roots = self.workspace_roots.lock().await.clone();
+        let progress = crate::progress::Progress::begin(
+            &self.client,
+            self.can_report_progress.load(std::sync::atomic::Ordering::Relaxed),
+            "Indexing SurrealQL files",
+        )
+        .await;
+        let paths = tokio::task::spawn_blocking(move || {
+            let mut paths = std::collections::BTreeSet::new();
+            for root in &roots {
+                paths.extend(surrealql_analysis::config::surql_files(root).unwrap_or_default());
+            }
+            paths.into_iter().collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let mut done = 0;
+        for batch in paths.chunks(INDEX_BATCH_SIZE) {
+            done += batch.len();
+            let batch = batch.to_vec();
+            let files = tokio::task::spawn_blocking(move || read_files(&batch)).await;
+            let mut state = self.state.lock().await;
+            state
+                .host
+                .set_disk_files(files.iter().flatten().map(|(uri, text)| (uri, text.as_deref())));
+            drop(state);
+            progress.report(done, paths.len()).await;
+        }
+        self.state.lock().await.host.forget_stale_files();
+        progress.end(format!("Indexed {} files", paths.len())).await;
+    }
+
+    /// Introspects the databases of the projects that ask for it, returning why any of them could
+    /// not be. The last known schema of a database that cannot be reached is kept.
+    async fn introspect_databases(&self) -> Vec<String> {
+        let configs = self.configs.lock().await.clone();
+        let mut uris = Vec::new();
+        let mut errors = Vec::new();
+        for config in configs.values().flatten() {
+            if !config.connection.as_ref().is_some_and(|connection| connection.introspect) {
+                continue;
+            }
+            let Ok(connection) = crate::execute::Connection::from_config(Some(config)) else {
+                continue;
+            };
+            let Some(uri) = connection.schema_uri() else {
+                continue;
+            };
+            match crate::introspect::introspect(&connection).await {
+                Ok(definitions) => self.state.lock().await.host.set_live_schema(&uri, definitions),
+                Err(err) => errors.push(format!("Failed to introspect {}: {}", uri, err)),
+            }
+            uris.push(uri);
+        }
+        self.state.lock().await.host.retain_live_schemas(&uris);
+        errors
+    }
+
+    /// Introspects the databases as part of another request, logging any failures.
+    async fn refresh_databases(&self) {
+        for error in self.introspect_databases().await {
+            self.client.log_message(tower_lsp::lsp_types::MessageType::WARNING, error).await;
         }
     }
+
+    /// Publishes the diagnostics of `version` of the open document at `uri` from a task of its
+    /// own, so that the edit that called for them is not held up while they are computed. They
+    /// are dropped once the document is edited again, as that edit publishes diagnostics of its
+    /// own. Nothing is published to clients that pull diagnostics.
+    fn analyse_document(&self, uri: tower_lsp::lsp_types::Url, version: i32) {
+        if self.pulls_diagnostics.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+        let client = self.client.clone();
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            let Some((analysis, doc)) = state.lock().await.snapshot(&uri) else {
+                return;
+            };
+            if doc.version() != version {
+                return;
+            }
+            let task = tokio::task::spawn_blocking({
+                let uri = uri.clone();
+                move || {
+                    analysis
+                        .diagnostics(&uri)
+                        .iter()
+                        .map(|diagnostic| crate::convert::diagnostic(&analysis, &doc, diagnostic))
+                        .collect()
+                }
+            });
+            let Ok(diagnostics) = task.await else {
+                return;
+            };
+            let current = state.lock().await.documents.get(&uri).map(|doc| doc.version());
+            if current == Some(version) {
+                client.publish_diagnostics(uri, diagnostics, Some(version)).await;
+            }
+        });
+    }
+
+    /// Re-discovers the project files of every workspace root and publishes their diagnostics.
+    async fn reload_configs(&self) {
+        let roots = self.workspace_roots.lock().await.clone();
+        let mut paths: Vec<std::path::PathBuf> =
+            roots.iter().filter_map(|root| surrealql_analysis::config::discover(root)).collect();
+        paths.sort();
+        paths.dedup();
+
+        let mut configs = self.configs.lock().await;
+        for stale in configs.keys().filter(|path| !paths.contains(path)) {
+            if let Ok(uri) = tower_lsp::lsp_types::Url::from_file_path(stale) {
+                self.client.publish_diagnostics(uri, Vec::new(), None).await;
+            }
+        }
+        configs.clear();
+
+        for path in paths {
+            let (config, diagnostics) = match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    let (config, diagnostics) = surrealql_analysis::config::parse(&path, &content);
+                    let doc =
+                        lsp_textdocument::FullTextDocument::new(String::from("toml"), 0, content);
+                    let analysis = self.state.lock().await.host.analysis();
+                    let diagnostics = diagnostics
+                        .iter()
+                        .map(|diagnostic| crate::convert::diagnostic(&analysis, &doc, diagnostic))
+                        .collect();
+                    (config, diagnostics)
+                }
+                Err(err) => {
+                    let diagnostic = tower_lsp::lsp_types::Diagnostic {
+                        severity: Some(tower_lsp::lsp_types::DiagnosticSeverity::ERROR),
+                        source: Some(String::from("surrealql-lsp")),
+                        message: format!(
+                            "Failed to read {}: {}",
+                            surrealql_analysis::config::CONFIG_FILE_NAME,
+                            err
+                        ),
+                        ..tower_lsp::lsp_types::Diagnostic::default()
+                    };
+                    (None, vec![diagnostic])
+                }
+            };
+            if let Ok(uri) = tower_lsp::lsp_types::Url::from_file_path(&path) {
+                self.client.publish_diagnostics(uri, diagnostics, None).await;
+            }
+            configs.insert(path, config);
+        }
+        let configs = configs.values().flatten().cloned().collect();
+        self.state.lock().await.host.set_configs(configs);
+    }
+
+    /// Answers the `surrealql.generateMigration` command with the migration as a document, and
+    /// its statements and warnings. The database is introspected anew, so that the migration
+    /// applies to it as it is now.
+    async fn generate_migration(
+        &self,
+        arguments: Vec<serde_json::Value>,
+    ) -> tower_lsp::jsonrpc::Result<serde_json::Value> {
+        let params: crate::migrate::MigrationParams = match arguments.into_iter().next() {
+            Some(argument) => serde_json::from_value(argument)
+                .map_err(|err| tower_lsp::jsonrpc::Error::invalid_params(err.to_string()))?,
+            None => crate::migrate::MigrationParams::default(),
+        };
+        let analysis = self.state.lock().await.host.analysis();
+        let root = self.workspace_roots.lock().await.first().cloned().unwrap_or_default();
+        let config = analysis.configs().first();
+        let from = crate::migrate::resolve(&params.from, config, &root).await;
+        let to = crate::migrate::resolve(&params.to, config, &root).await;
+        let migration = from.and_then(|from| analysis.migration(&from, &to?));
+        let migration = migration.map_err(|message| tower_lsp::jsonrpc::Error {
+            code: tower_lsp::jsonrpc::ErrorCode::InternalError,
+            message: message.into(),
+            data: None,
+        })?;
+        Ok(serde_json::json!({
+            "text": migration.to_string(),
+            "statements": migration.statements,
+            "warnings": migration.warnings,
+        }))
+    }
+
+    async fn register_file_watchers(&self) {
+        if !self.can_register_file_watchers.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+        let options = tower_lsp::lsp_types::DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![
+                tower_lsp::lsp_types::FileSystemWatcher {
+                    glob_pattern: tower_lsp::lsp_types::GlobPattern::String(format!(
+                        "**/{}",
+                        surrealql_analysis::config::CONFIG_FILE_NAME
+                    )),
+                    kind: None,
+                },
+                tower_lsp::lsp_types::FileSystemWatcher {
+                    glob_pattern: tower_lsp::lsp_types::GlobPattern::String(String::from(
+                        "**/*.surql",
+                    )),
+                    kind: None,
+                },
+            ],
+        };
+        let registration = tower_lsp::lsp_types::Registration {
+            id: String::from("surrealql-file-watcher"),
+            method: String::from("workspace/didChangeWatchedFiles"),
+            register_options: serde_json::to_value(options).ok(),
+        };
+        if let Err(err) = self.client.register_capability(vec![registration]).await {
+            self.client
+                .log_message(
+                    tower_lsp::lsp_types::MessageType::WARNING,
+                    format!(
+                        "Failed to watch {} and .surql files: {}",
+                        surrealql_analysis::config::CONFIG_FILE_NAME,
+                        err
+                    ),
+                )
+                .await;
+        }
+    }
+}
+
+/// Reads the files at `paths`, returning the text of each, or `None` for those that could not be
+/// read, e.g. as they were deleted.
+fn read_files(paths: &[std::path::PathBuf]) -> Vec<(tower_lsp::lsp_types::Url, Option<String>)> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let uri = tower_lsp::lsp_types::Url::from_file_path(path).ok()?;
+            Some((uri, std::fs::read_to_string(path).ok()))
+        })
+        .collect()
+}
+
+/// Returns the open document `doc` for editing, copying it first when a request still reads it.
+fn document_mut(
+    doc: &mut std::sync::Arc<lsp_textdocument::FullTextDocument>,
+) -> &mut lsp_textdocument::FullTextDocument {
+    if std::sync::Arc::get_mut(doc).is_none() {
+        // `FullTextDocument` cannot be cloned.
+        *doc = std::sync::Arc::new(lsp_textdocument::FullTextDocument::new(
+            doc.language_id().to_string(),
+            doc.version(),
+            doc.get_content(None).to_string(),
+        ));
+    }
+    std::sync::Arc::get_mut(doc).expect("The document was just copied")
+}
+
+fn workspace_roots(params: &tower_lsp::lsp_types::InitializeParams) -> Vec<std::path::PathBuf> {
+    #[allow(deprecated)]
+    let root_uri = params.root_uri.iter();
+    match &params.workspace_folders {
+        Some(folders) => folders.iter().map(|folder| &folder.uri).collect::<Vec<_>>(),
+        None => root_uri.collect(),
+    }
+    .into_iter()
+    .filter_map(|uri| uri.to_file_path().ok())
+    .collect()
 }
 
 #[tower_lsp::async_trait]
 impl tower_lsp::LanguageServer for Backend {
     async fn initialize(
         &self,
-        _: tower_lsp::lsp_types::InitializeParams,
+        params: tower_lsp::lsp_types::InitializeParams,
     ) -> tower_lsp::jsonrpc::Result<tower_lsp::lsp_types::InitializeResult> {
+        *self.workspace_roots.lock().await = workspace_roots(&params);
+        let pulls_diagnostics = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .is_some_and(|text_document| text_document.diagnostic.is_some());
+        self.pulls_diagnostics.store(pulls_diagnostics, std::sync::atomic::Ordering::Relaxed);
+        let workspace = params.capabilities.workspace;
+        let can_refresh_diagnostics = workspace
+            .as_ref()
+            .and_then(|workspace| workspace.diagnostic.as_ref())
+            .and_then(|diagnostic| diagnostic.refresh_support)
+            .unwrap_or(false);
+        self.can_refresh_diagnostics
+            .store(can_refresh_diagnostics, std::sync::atomic::Ordering::Relaxed);
+        let can_report_progress = params
+            .capabilities
+            .window
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+        self.can_report_progress.store(can_report_progress, std::sync::atomic::Ordering::Relaxed);
+        let can_register_file_watchers = workspace
+            .and_then(|workspace| workspace.did_change_watched_files)
+            .and_then(|watched_files| watched_files.dynamic_registration)
+            .unwrap_or(false);
+        self.can_register_file_watchers
+            .store(can_register_file_watchers, std::sync::atomic::Ordering::Relaxed);
+
         Ok(tower_lsp::lsp_types::InitializeResult {
             server_info: Some(tower_lsp::lsp_types::ServerInfo {
                 name: String::from("surrealql-lsp"),
@@ -71,10 +488,73 @@ impl tower_lsp::LanguageServer for Backend {
                 hover_provider: Some(tower_lsp::lsp_types::HoverProviderCapability::Simple(true)),
                 completion_provider: Some(tower_lsp::lsp_types::CompletionOptions {
                     resolve_provider: Some(false),
+                    // The `>` of `->` and `<->`, and the `-` of `<-`. The completions triggered by
+                    // any other `-`, e.g. of a subtraction or `--` comment, are left empty.
+                    trigger_characters: Some(vec![String::from(">"), String::from("-")]),
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
                     ..Default::default()
                 }),
+                code_action_provider: Some(
+                    tower_lsp::lsp_types::CodeActionProviderCapability::Options(
+                        tower_lsp::lsp_types::CodeActionOptions {
+                            code_action_kinds: Some(vec![
+                                tower_lsp::lsp_types::CodeActionKind::QUICKFIX,
+                                tower_lsp::lsp_types::CodeActionKind::REFACTOR_EXTRACT,
+                                tower_lsp::lsp_types::CodeActionKind::REFACTOR_INLINE,
+                                tower_lsp::lsp_types::CodeActionKind::from(
+                                    crate::actions::UPPERCASE_KEYWORDS,
+                                ),
+                                tower_lsp::lsp_types::CodeActionKind::from(
+                                    crate::actions::LOWERCASE_KEYWORDS,
+                                ),
+                            ]),
+                            ..tower_lsp::lsp_types::CodeActionOptions::default()
+                        },
+                    ),
+                ),
+                document_formatting_provider: Some(tower_lsp::lsp_types::OneOf::Left(true)),
+                document_on_type_formatting_provider: Some(
+                    tower_lsp::lsp_types::DocumentOnTypeFormattingOptions {
+                        first_trigger_character: String::from(" "),
+                        more_trigger_character: Some(vec![String::from(";")]),
+                    },
+                ),
+                inlay_hint_provider: Some(tower_lsp::lsp_types::OneOf::Left(true)),
+                // The diagnostics of a document depend on the schema defined by other files.
+                diagnostic_provider: Some(
+                    tower_lsp::lsp_types::DiagnosticServerCapabilities::Options(
+                        tower_lsp::lsp_types::DiagnosticOptions {
+                            identifier: Some(String::from(crate::diagnostics::IDENTIFIER)),
+                            inter_file_dependencies: true,
+                            workspace_diagnostics: true,
+                            ..tower_lsp::lsp_types::DiagnosticOptions::default()
+                        },
+                    ),
+                ),
+                code_lens_provider: Some(tower_lsp::lsp_types::CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                execute_command_provider: Some(tower_lsp::lsp_types::ExecuteCommandOptions {
+                    commands: vec![
+                        String::from(crate::execute::EXECUTE_STATEMENT),
+                        String::from(crate::execute::EXECUTE_FILE),
+                        String::from(crate::introspect::REFRESH_SCHEMA),
+                        String::from(crate::migrate::GENERATE_MIGRATION),
+                        String::from(crate::diagram::EXPORT_DIAGRAM),
+                        String::from(crate::typegen::GENERATE_TYPES),
+                    ],
+                    ..tower_lsp::lsp_types::ExecuteCommandOptions::default()
+                }),
+                workspace: Some(tower_lsp::lsp_types::WorkspaceServerCapabilities {
+                    workspace_folders: Some(
+                        tower_lsp::lsp_types::WorkspaceFoldersServerCapabilities {
+                            supported: Some(true),
+                            change_notifications: Some(tower_lsp::lsp_types::OneOf::Left(true)),
+                        },
+                    ),
+                    file_operations: None,
+                }),
                 ..tower_lsp::lsp_types::ServerCapabilities::default()
             },
         })
@@ -82,6 +562,73 @@ impl tower_lsp::LanguageServer for Backend {
 
     async fn initialized(&self, _: tower_lsp::lsp_types::InitializedParams) {
         self.client.log_message(tower_lsp::lsp_types::MessageType::INFO, "initialized!").await;
+        self.register_file_watchers().await;
+        self.reload_configs().await;
+        self.index_workspace().await;
+        self.refresh_databases().await;
+        self.refresh_diagnostics().await;
+    }
+
+    async fn did_change_workspace_folders(
+        &self,
+        params: tower_lsp::lsp_types::DidChangeWorkspaceFoldersParams,
+    ) {
+        {
+            let mut roots = self.workspace_roots.lock().await;
+            for removed in params.event.removed {
+                if let Ok(path) = removed.uri.to_file_path() {
+                    roots.retain(|root| *root != path);
+                }
+            }
+            for added in params.event.added {
+                if let Ok(path) = added.uri.to_file_path() {
+                    roots.push(path);
+                }
+            }
+        }
+        self.reload_configs().await;
+        self.index_workspace().await;
+        self.refresh_databases().await;
+        self.refresh_diagnostics().await;
+    }
+
+    async fn did_change_watched_files(
+        &self,
+        params: tower_lsp::lsp_types::DidChangeWatchedFilesParams,
+    ) {
+        let config_changed = params.changes.iter().any(|change| {
+            change
+                .uri
+                .path()
+                .ends_with(&format!("/{}", surrealql_analysis::config::CONFIG_FILE_NAME))
+        });
+        if config_changed {
+            self.reload_configs().await;
+            self.index_workspace().await;
+            self.refresh_databases().await;
+            self.refresh_diagnostics().await;
+            return;
+        }
+
+        // Every file is read again whatever the kind of its change, so that the index ends up as
+        // the files are even when a checkout deletes and recreates them in quick succession.
+        let paths: Vec<std::path::PathBuf> = params
+            .changes
+            .iter()
+            .filter(|change| change.uri.path().ends_with(".surql"))
+            .filter_map(|change| change.uri.to_file_path().ok())
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+        let files = tokio::task::spawn_blocking(move || read_files(&paths)).await;
+        {
+            let mut state = self.state.lock().await;
+            state
+                .host
+                .set_disk_files(files.iter().flatten().map(|(uri, text)| (uri, text.as_deref())));
+        }
+        self.refresh_diagnostics().await;
     }
 
     async fn shutdown(&self) -> tower_lsp::jsonrpc::Result<()> {
@@ -89,137 +636,334 @@ impl tower_lsp::LanguageServer for Backend {
     }
 
     async fn did_open(&self, params: tower_lsp::lsp_types::DidOpenTextDocumentParams) {
-        let mut curr_doc = self.curr_doc.lock().await;
-        let mut tree = self.tree.lock().await;
-        let mut parser = self.parser.lock().await;
-
-        *curr_doc = Some(lsp_textdocument::FullTextDocument::new(
-            params.text_document.language_id.clone(),
-            params.text_document.version,
-            params.text_document.text.clone(),
-        ));
-        *tree = parser.parse(params.text_document.text, None);
+        let uri = params.text_document.uri;
+        {
+            let mut state = self.state.lock().await;
+            state.host.set_file_text(uri.clone(), params.text_document.text.clone());
+            let doc = lsp_textdocument::FullTextDocument::new(
+                params.text_document.language_id,
+                params.text_document.version,
+                params.text_document.text,
+            );
+            state.documents.insert(uri.clone(), std::sync::Arc::new(doc));
+        }
+        self.analyse_document(uri, params.text_document.version);
+    }
+
+    async fn did_close(&self, params: tower_lsp::lsp_types::DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        // A closed file of the workspace is indexed as it is on disk, discarding unsaved changes.
+        let roots = self.workspace_roots.lock().await.clone();
+        let in_workspace = match uri.to_file_path() {
+            Ok(path) => roots.iter().any(|root| path.starts_with(root)),
+            Err(()) => false,
+        };
+        let disk_text = match uri.to_file_path() {
+            Ok(path) if in_workspace || self.config_for(&uri).await.is_some() => {
+                tokio::fs::read_to_string(path).await.ok()
+            }
+            _ => None,
+        };
+        {
+            let mut state = self.state.lock().await;
+            state.documents.remove(&uri);
+            state.host.close_file(&uri, disk_text.as_deref());
+        }
+        if !self.pulls_diagnostics.load(std::sync::atomic::Ordering::Relaxed) {
+            self.client.publish_diagnostics(uri, Vec::new(), None).await;
+        }
+        self.refresh_diagnostics().await;
     }
 
     async fn did_change(&self, params: tower_lsp::lsp_types::DidChangeTextDocumentParams) {
-        let mut curr_doc = self.curr_doc.lock().await;
-        let mut tree = self.tree.lock().await;
-
-        if let Some(ref mut doc) = *curr_doc {
-            doc.update(&params.content_changes, params.text_document.version);
-            for change in params.content_changes.iter() {
-                if let Some(ref mut curr_tree) = *tree {
-                    match text_doc_change_to_tree_sitter_edit(change, doc) {
-                        Ok(edit) => {
-                            curr_tree.edit(&edit);
-                        }
-                        Err(err) => {
-                            self.client
-                                .log_message(
-                                    tower_lsp::lsp_types::MessageType::ERROR,
-                                    format!("Bad edit info, failed to edit tree: {}", err),
-                                )
-                                .await;
-                        }
-                    }
+        let uri = params.text_document.uri;
+        let mut errors = Vec::new();
+        {
+            let mut state = self.state.lock().await;
+            let State { documents, host } = &mut *state;
+            let Some(doc) = documents.get_mut(&uri) else {
+                return;
+            };
+            let doc = document_mut(doc);
+            for change in params.content_changes {
+                // The range is resolved against the document as it was before the change.
+                let range = change.range.map(|range| crate::convert::text_range(doc, range));
+                doc.update(std::slice::from_ref(&change), params.text_document.version);
+                let Some(range) = range else {
+                    host.set_file_text(uri.clone(), change.text);
+                    continue;
+                };
+                if let Err(err) = host.edit_file(&uri, range, &change.text) {
+                    errors.push(err);
+                    host.set_file_text(uri.clone(), doc.get_content(None).to_string());
                 }
             }
         }
+        for err in errors {
+            self.client
+                .log_message(
+                    tower_lsp::lsp_types::MessageType::ERROR,
+                    format!("Bad edit info, failed to edit document: {}", err),
+                )
+                .await;
+        }
+        self.analyse_document(uri, params.text_document.version);
+    }
+
+    async fn diagnostic(
+        &self,
+        params: tower_lsp::lsp_types::DocumentDiagnosticParams,
+    ) -> tower_lsp::jsonrpc::Result<tower_lsp::lsp_types::DocumentDiagnosticReportResult> {
+        let previous_result_id = params.previous_result_id;
+        let report = self.read(params.text_document.uri, move |analysis, uri, doc| {
+            crate::diagnostics::document_report(analysis, uri, doc, previous_result_id.as_deref())
+        });
+        // A document that is not open has no diagnostics of its own.
+        let report = report.await?.unwrap_or_else(|| {
+            tower_lsp::lsp_types::DocumentDiagnosticReport::Full(
+                tower_lsp::lsp_types::RelatedFullDocumentDiagnosticReport::default(),
+            )
+        });
+        Ok(tower_lsp::lsp_types::DocumentDiagnosticReportResult::Report(report))
+    }
+
+    async fn workspace_diagnostic(
+        &self,
+        params: tower_lsp::lsp_types::WorkspaceDiagnosticParams,
+    ) -> tower_lsp::jsonrpc::Result<tower_lsp::lsp_types::WorkspaceDiagnosticReportResult> {
+        let (analysis, documents) = {
+            let state = self.state.lock().await;
+            (state.host.analysis(), state.documents.clone())
+        };
+        let report = compute(analysis, None, move |analysis| {
+            crate::diagnostics::workspace_report(analysis, &documents, &params.previous_result_ids)
+        });
+        let report = report.await?.ok_or_else(tower_lsp::jsonrpc::Error::content_modified)?;
+        Ok(tower_lsp::lsp_types::WorkspaceDiagnosticReportResult::Report(report))
     }
 
     async fn hover(
         &self,
         params: tower_lsp::lsp_types::HoverParams,
     ) -> tower_lsp::jsonrpc::Result<Option<tower_lsp::lsp_types::Hover>> {
-        let curr_doc = self.curr_doc.lock().await;
-        let mut tree = self.tree.lock().await;
-        let mut parser = self.parser.lock().await;
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let hover = self.read(uri, move |analysis, uri, doc| {
+            let hover = analysis.hover(uri, doc.offset_at(position) as usize)?;
+            Some(tower_lsp::lsp_types::Hover {
+                contents: tower_lsp::lsp_types::HoverContents::Markup(
+                    tower_lsp::lsp_types::MarkupContent {
+                        kind: tower_lsp::lsp_types::MarkupKind::Markdown,
+                        value: hover.markdown,
+                    },
+                ),
+                range: Some(crate::convert::range(doc, &hover.range)),
+            })
+        });
+        Ok(hover.await?.flatten())
+    }
 
-        let doc = match &*curr_doc {
-            Some(doc) => doc,
-            _ => return Ok(None),
+    async fn completion(
+        &self,
+        params: tower_lsp::lsp_types::CompletionParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<tower_lsp::lsp_types::CompletionResponse>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let trigger = params.context.and_then(|context| context.trigger_character);
+        let completions = self.read(uri, move |analysis, uri, doc| {
+            let offset = doc.offset_at(position) as usize;
+            // A `-` only triggers completions when it ends the `<-` of a traversal.
+            if trigger.as_deref() == Some("-")
+                && !doc.get_content(None).get(..offset).is_some_and(|text| text.ends_with("<-"))
+            {
+                return Some(Vec::new());
+            }
+            analysis.completions(uri, offset)
+        });
+        let Some(completions) = completions.await?.flatten() else {
+            return Ok(None);
         };
 
-        let keyword = crate::position::retrieve_keyword_at_position(
-            doc.get_content(None),
-            &mut parser,
-            &mut tree,
-            params.text_document_position_params.position.line as usize,
-            params.text_document_position_params.position.character as usize,
-        );
-
-        match keyword {
-            Some(keyword) => {
-                if let Some(doc_content) = self.kw_docs.get(&keyword) {
-                    let hover_contents = tower_lsp::lsp_types::HoverContents::Markup(
+        let items = completions
+            .into_iter()
+            .map(|item| {
+                let documentation = item.documentation.map(|value| {
+                    tower_lsp::lsp_types::Documentation::MarkupContent(
                         tower_lsp::lsp_types::MarkupContent {
                             kind: tower_lsp::lsp_types::MarkupKind::Markdown,
-                            value: doc_content.clone(),
+                            value,
                         },
-                    );
-                    let hover =
-                        tower_lsp::lsp_types::Hover { contents: hover_contents, range: None };
-                    Ok(Some(hover))
-                } else {
-                    self.client
-                        .log_message(
-                            tower_lsp::lsp_types::MessageType::WARNING,
-                            format!("Documentation for keyword '{}' not found.", keyword),
-                        )
-                        .await;
-                    Ok(None)
+                    )
+                });
+                let kind = match item.kind {
+                    surrealql_analysis::CompletionKind::Keyword => {
+                        tower_lsp::lsp_types::CompletionItemKind::KEYWORD
+                    }
+                    surrealql_analysis::CompletionKind::Table => {
+                        tower_lsp::lsp_types::CompletionItemKind::CLASS
+                    }
+                };
+                tower_lsp::lsp_types::CompletionItem {
+                    label: item.label,
+                    kind: Some(kind),
+                    documentation,
+                    ..tower_lsp::lsp_types::CompletionItem::default()
                 }
+            })
+            .collect();
+        Ok(Some(tower_lsp::lsp_types::CompletionResponse::List(
+            tower_lsp::lsp_types::CompletionList { is_incomplete: true, items },
+        )))
+    }
+
+    async fn inlay_hint(
+        &self,
+        params: tower_lsp::lsp_types::InlayHintParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<tower_lsp::lsp_types::InlayHint>>> {
+        let uri = params.text_document.uri;
+        self.read(uri, move |analysis, uri, doc| {
+            let range = crate::convert::text_range(doc, params.range);
+            let hints = analysis.inlay_hints(uri, range);
+            hints.into_iter().map(|hint| crate::convert::inlay_hint(doc, hint)).collect()
+        })
+        .await
+    }
+
+    async fn code_lens(
+        &self,
+        params: tower_lsp::lsp_types::CodeLensParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<tower_lsp::lsp_types::CodeLens>>> {
+        let uri = params.text_document.uri;
+        let lenses = self.read(uri, move |analysis, uri, doc| {
+            // Statements can only be run once a connection is configured.
+            let config = analysis.config(uri);
+            config.and_then(|config| config.connection.as_ref())?;
+            Some(crate::lens::code_lenses(analysis, uri, doc))
+        });
+        Ok(lenses.await?.flatten())
+    }
+
+    async fn code_action(
+        &self,
+        params: tower_lsp::lsp_types::CodeActionParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<tower_lsp::lsp_types::CodeActionResponse>> {
+        let uri = params.text_document.uri.clone();
+        self.read(uri, move |analysis, uri, doc| {
+            let only = params.context.only.as_ref();
+            let mut actions = Vec::new();
+            if crate::actions::is_requested(only, &tower_lsp::lsp_types::CodeActionKind::QUICKFIX) {
+                // Diagnostics are recomputed rather than taken from the request, so that their
+                // fixes are available even to clients that do not round-trip diagnostic data.
+                let diagnostics = analysis.diagnostics(uri);
+                actions.extend(crate::actions::quick_fixes(
+                    analysis,
+                    doc,
+                    diagnostics,
+                    params.range,
+                ));
             }
-            _ => Ok(None),
-        }
+            let range = crate::convert::text_range(doc, params.range);
+            let assists =
+                analysis.assists(uri, range, &|kind| crate::actions::is_wanted(only, kind));
+            actions
+                .extend(assists.iter().map(|assist| crate::convert::code_action(analysis, assist)));
+            actions.into_iter().map(tower_lsp::lsp_types::CodeActionOrCommand::CodeAction).collect()
+        })
+        .await
     }
 
-    async fn completion(
+    async fn formatting(
         &self,
-        params: tower_lsp::lsp_types::CompletionParams,
-    ) -> tower_lsp::jsonrpc::Result<Option<tower_lsp::lsp_types::CompletionResponse>> {
-        let curr_doc = self.curr_doc.lock().await;
-        let mut tree = self.tree.lock().await;
-        let mut parser = self.parser.lock().await;
+        params: tower_lsp::lsp_types::DocumentFormattingParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<tower_lsp::lsp_types::TextEdit>>> {
+        let uri = params.text_document.uri;
+        self.read(uri, move |analysis, uri, doc| {
+            let edits = analysis.format(uri);
+            edits.iter().map(|edit| crate::convert::text_edit(doc, edit)).collect()
+        })
+        .await
+    }
 
-        let doc = match &*curr_doc {
-            Some(doc) => doc,
-            _ => return Ok(None),
-        };
+    async fn on_type_formatting(
+        &self,
+        params: tower_lsp::lsp_types::DocumentOnTypeFormattingParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<tower_lsp::lsp_types::TextEdit>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let edit = self.read(uri, move |analysis, uri, doc| {
+            let edit = analysis.on_type_edit(uri, doc.offset_at(position) as usize)?;
+            Some(vec![crate::convert::text_edit(doc, &edit)])
+        });
+        Ok(edit.await?.flatten())
+    }
+
+    async fn execute_command(
+        &self,
+        params: tower_lsp::lsp_types::ExecuteCommandParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<serde_json::Value>> {
+        if params.command == crate::introspect::REFRESH_SCHEMA {
+            let errors = self.introspect_databases().await;
+            self.refresh_diagnostics().await;
+            if errors.is_empty() {
+                return Ok(None);
+            }
+            return Err(tower_lsp::jsonrpc::Error {
+                code: tower_lsp::jsonrpc::ErrorCode::InternalError,
+                message: errors.join("\n").into(),
+                data: None,
+            });
+        }
+
+        if params.command == crate::migrate::GENERATE_MIGRATION {
+            return self.generate_migration(params.arguments).await.map(Some);
+        }
+
+        if params.command == crate::diagram::EXPORT_DIAGRAM {
+            let params: crate::diagram::DiagramParams = match params.arguments.into_iter().next() {
+                Some(argument) => serde_json::from_value(argument)
+                    .map_err(|err| tower_lsp::jsonrpc::Error::invalid_params(err.to_string()))?,
+                None => crate::diagram::DiagramParams::default(),
+            };
+            let analysis = self.state.lock().await.host.analysis();
+            let diagram =
+                compute(analysis, None, move |analysis| analysis.diagram(params.format.into()))
+                    .await?;
+            return Ok(Some(serde_json::Value::String(diagram)));
+        }
 
-        let completion_list = crate::completion::get_completion_list(
-            doc.get_content(None),
-            &mut parser,
-            &mut tree,
-            &params,
-        );
-
-        match completion_list {
-            Some(list) => {
-                let items: Vec<tower_lsp::lsp_types::CompletionItem> = list
-                    .iter()
-                    .map(|item| {
-                        let documentation =
-                            self.kw_docs.get(item).map(|doc| tower_lsp::lsp_types::MarkupContent {
-                                kind: tower_lsp::lsp_types::MarkupKind::Markdown,
-                                value: doc.to_string(),
-                            });
-
-                        tower_lsp::lsp_types::CompletionItem {
-                            label: item.to_string(),
-                            kind: Some(tower_lsp::lsp_types::CompletionItemKind::KEYWORD),
-                            documentation: documentation
-                                .map(tower_lsp::lsp_types::Documentation::MarkupContent),
-                            ..tower_lsp::lsp_types::CompletionItem::default()
-                        }
-                    })
-                    .collect();
-
-                Ok(Some(tower_lsp::lsp_types::CompletionResponse::List(
-                    tower_lsp::lsp_types::CompletionList { is_incomplete: true, items },
-                )))
-            }
-            _ => Ok(None),
+        if params.command == crate::typegen::GENERATE_TYPES {
+            let params: crate::typegen::TypesParams = match params.arguments.into_iter().next() {
+                Some(argument) => serde_json::from_value(argument)
+                    .map_err(|err| tower_lsp::jsonrpc::Error::invalid_params(err.to_string()))?,
+                None => crate::typegen::TypesParams::default(),
+            };
+            let analysis = self.state.lock().await.host.analysis();
+            let types =
+                compute(analysis, None, move |analysis| analysis.types(params.language.into()))
+                    .await?;
+            return Ok(Some(serde_json::Value::String(types)));
+        }
+
+        let argument = params.arguments.into_iter().next().unwrap_or_default();
+        let execute_params = crate::execute::params(&params.command, argument)
+            .map_err(tower_lsp::jsonrpc::Error::invalid_params)?;
+        let config = self.config_for(&execute_params.text_document.uri).await;
+        let connection = crate::execute::Connection::from_config(config.as_ref())
+            .map_err(tower_lsp::jsonrpc::Error::invalid_params)?;
+        let snapshot = self.state.lock().await.snapshot(&execute_params.text_document.uri);
+        let Some((analysis, doc)) = snapshot else {
+            return Ok(None);
+        };
+        let Some(query) = crate::execute::query(&analysis, &doc, &execute_params) else {
+            return Ok(None);
+        };
+        match connection.execute(&query).await {
+            Ok(results) => Ok(serde_json::to_value(results).ok()),
+            Err(message) => Err(tower_lsp::jsonrpc::Error {
+                code: tower_lsp::jsonrpc::ErrorCode::InternalError,
+                message: message.into(),
+                data: None,
+            }),
         }
     }
 }
@@ -1,15 +1,164 @@
-mod completion;
-mod keywords;
+mod actions;
+mod check;
+mod convert;
+mod diagnostics;
+mod diagram;
+mod execute;
+mod fmt;
+mod introspect;
+mod lens;
 mod lsp;
-mod parser;
-mod position;
+mod migrate;
+mod progress;
+mod snapshot;
+mod transport;
+mod typegen;
 
-#[tokio::main]
-async fn main() {
-    tracing_subscriber::fmt().init();
+/// A language server for SurrealQL. Without a subcommand, it speaks LSP over stdin and stdout, or
+/// over the transport given by its options.
+#[derive(clap::Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    /// Speaks LSP over stdin and stdout, which is the default.
+    #[arg(long, conflicts_with_all = ["listen", "socket"])]
+    stdio: bool,
+    /// Accepts any number of clients on a TCP port of localhost, e.g. to share the server between
+    /// editor windows or to attach to it from a debugger.
+    #[arg(long, value_name = "PORT", conflicts_with = "socket")]
+    listen: Option<u16>,
+    /// Accepts any number of clients on a Unix domain socket, which is created at the path.
+    #[arg(long, value_name = "PATH")]
+    socket: Option<std::path::PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Reports the diagnostics of `.surql` files as the server would, exiting with a non-zero code
+    /// when any of them is an error.
+    Check {
+        /// The files to check, or directories to check every `.surql` file below.
+        #[arg(default_value = ".")]
+        paths: Vec<std::path::PathBuf>,
+        /// How the diagnostics are printed.
+        #[arg(long, value_enum, default_value_t = check::Format::Human)]
+        format: check::Format,
+    },
+    /// Formats `.surql` files in place with the `[format]` settings of `surrealql.toml`.
+    Fmt {
+        /// Prints a diff of every file that is not formatted instead, exiting with a non-zero code
+        /// when there is any.
+        #[arg(long)]
+        check: bool,
+        /// The files to format, or directories to format every `.surql` file below.
+        #[arg(default_value = ".")]
+        paths: Vec<std::path::PathBuf>,
+    },
+    /// Writes the schema of a workspace as an entity-relationship diagram of its tables, record
+    /// links and relations.
+    Diagram {
+        /// The workspace directory.
+        #[arg(default_value = ".")]
+        root: std::path::PathBuf,
+        /// The language of the diagram.
+        #[arg(long, value_enum, default_value_t = diagram::Format::Mermaid)]
+        format: diagram::Format,
+        /// The file to write the diagram to, instead of stdout.
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Writes the `REMOVE` and `DEFINE` statements migrating one schema to another, by default the
+    /// configured database to the schema of the workspace. Destructive changes are warned about.
+    Diff {
+        /// The workspace directory.
+        #[arg(default_value = ".")]
+        root: std::path::PathBuf,
+        /// The schema to migrate from: `workspace`, `database` or the path of a snapshot.
+        #[arg(long, default_value = "database")]
+        from: migrate::Source,
+        /// The schema to migrate to: `workspace`, `database` or the path of a snapshot.
+        #[arg(long, default_value = "workspace")]
+        to: migrate::Source,
+        /// The file to write the migration to, instead of stdout.
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Writes the types of the tables of a workspace, for application code to use.
+    Types {
+        /// The workspace directory.
+        #[arg(default_value = ".")]
+        root: std::path::PathBuf,
+        /// The language of the types.
+        #[arg(long, value_enum, default_value_t = typegen::Language::TypeScript)]
+        language: typegen::Language,
+        /// The file to write the types to, instead of stdout.
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Writes the effective schema of a workspace to a JSON snapshot, which another workspace can
+    /// load with the `schema.snapshots` option of `surrealql.toml`.
+    Snapshot {
+        /// The workspace directory.
+        #[arg(default_value = ".")]
+        root: std::path::PathBuf,
+        /// The file to write the snapshot to, instead of stdout.
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
 
-    let (stdin, stdout) = (tokio::io::stdin(), tokio::io::stdout());
+#[tokio::main]
+async fn main() -> std::process::ExitCode {
+    let cli = <Cli as clap::Parser>::parse();
+    let result = match cli.command {
+        None => serve(transport::Transport::new(cli.listen, cli.socket))
+            .await
+            .map(|()| std::process::ExitCode::SUCCESS),
+        Some(Command::Check { paths, format }) => check::run(&paths, format).map(|passed| {
+            if passed {
+                std::process::ExitCode::SUCCESS
+            } else {
+                std::process::ExitCode::FAILURE
+            }
+        }),
+        Some(Command::Fmt { check, paths }) => fmt::run(&paths, check).map(|formatted| {
+            if formatted {
+                std::process::ExitCode::SUCCESS
+            } else {
+                std::process::ExitCode::FAILURE
+            }
+        }),
+        Some(Command::Diagram { root, format, output }) => {
+            diagram::write(&root, format, output.as_deref())
+                .await
+                .map(|()| std::process::ExitCode::SUCCESS)
+        }
+        Some(Command::Diff { root, from, to, output }) => {
+            migrate::run(&root, &from, &to, output.as_deref())
+                .await
+                .map(|()| std::process::ExitCode::SUCCESS)
+        }
+        Some(Command::Snapshot { root, output }) => snapshot::write(&root, output.as_deref())
+            .await
+            .map(|()| std::process::ExitCode::SUCCESS),
+        Some(Command::Types { root, language, output }) => {
+            typegen::write(&root, language, output.as_deref())
+                .await
+                .map(|()| std::process::ExitCode::SUCCESS)
+        }
+    };
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::ExitCode::FAILURE
+        }
+    }
+}
 
-    let (service, socket) = tower_lsp::LspService::build(lsp::Backend::new).finish();
-    tower_lsp::Server::new(stdin, stdout, socket).serve(service).await;
+async fn serve(transport: transport::Transport) -> Result<(), String> {
+    // Logs go to stderr, as stdout may be the LSP transport.
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+    transport::serve(transport).await
 }
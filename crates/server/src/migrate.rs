@@ -0,0 +1,90 @@
+//! Generates the statements migrating one schema to another, e.g. the database to the workspace,
+//! for the `diff` subcommand and the `surrealql.generateMigration` command.
+
+/// Returns the migration between two schemas as a SurrealQL document, with its statements and
+/// warnings. Takes an optional [`MigrationParams`] argument.
+pub(crate) const GENERATE_MIGRATION: &str = "surrealql.generateMigration";
+
+/// A schema to migrate from or to.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(from = "String")]
+pub(crate) enum Source {
+    /// The `DEFINE` and `REMOVE` statements of the files of the workspace.
+    Workspace,
+    /// The database configured in the `[connection]` of `surrealql.toml`, which is introspected.
+    Database,
+    /// The schema snapshot at a path.
+    Snapshot(std::path::PathBuf),
+}
+
+impl From<String> for Source {
+    fn from(source: String) -> Self {
+        match source.as_str() {
+            "workspace" => Source::Workspace,
+            "database" => Source::Database,
+            _ => Source::Snapshot(std::path::PathBuf::from(source)),
+        }
+    }
+}
+
+/// The argument of the `surrealql.generateMigration` command.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct MigrationParams {
+    /// The schema to migrate from, the database by default.
+    pub(crate) from: Source,
+    /// The schema to migrate to, the workspace by default.
+    pub(crate) to: Source,
+}
+
+impl Default for MigrationParams {
+    fn default() -> Self {
+        Self { from: Source::Database, to: Source::Workspace }
+    }
+}
+
+/// Resolves `source` for the project of `config`, introspecting its database when it is named.
+/// A relative snapshot path is resolved against `root`.
+pub(crate) async fn resolve(
+    source: &Source,
+    config: Option<&surrealql_analysis::config::ProjectConfig>,
+    root: &std::path::Path,
+) -> Result<surrealql_analysis::SchemaSource, String> {
+    match source {
+        Source::Workspace => Ok(surrealql_analysis::SchemaSource::Workspace),
+        Source::Database => {
+            let connection = crate::execute::Connection::from_config(config)?;
+            let definitions = crate::introspect::introspect(&connection).await?;
+            Ok(surrealql_analysis::SchemaSource::Statements(definitions))
+        }
+        Source::Snapshot(path) => Ok(surrealql_analysis::SchemaSource::Snapshot(root.join(path))),
+    }
+}
+
+/// Writes the migration from `from` to `to` of the workspace at `root` to `output`, or to stdout,
+/// with its warnings as comments. The warnings are repeated on stderr.
+pub(crate) async fn run(
+    root: &std::path::Path,
+    from: &Source,
+    to: &Source,
+    output: Option<&std::path::Path>,
+) -> Result<(), String> {
+    let analysis = surrealql_analysis::AnalysisHost::load(&[root.to_path_buf()])?.analysis();
+    let config = analysis.configs().first();
+    let cwd = std::env::current_dir().map_err(|err| err.to_string())?;
+    let from = resolve(from, config, &cwd).await?;
+    let to = resolve(to, config, &cwd).await?;
+    let migration = analysis.migration(&from, &to)?;
+
+    for warning in &migration.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    match output {
+        Some(path) => std::fs::write(path, migration.to_string())
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err)),
+        None => {
+            print!("{}", migration);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Work done progress, which clients show while a long-running task such as indexing the workspace
+//! is under way.
+
+/// The `$/progress` notifications of one task. Nothing is sent to clients that do not show
+/// progress, or that refuse to create it.
+pub(crate) struct Progress {
+    client: tower_lsp::Client,
+    token: Option<tower_lsp::lsp_types::NumberOrString>,
+}
+
+impl Progress {
+    /// Creates the progress of a task called `title` and reports that it has begun.
+    pub(crate) async fn begin(client: &tower_lsp::Client, enabled: bool, title: &str) -> Self {
+        static NEXT_TOKEN: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let mut progress = Self { client: client.clone(), token: None };
+        if !enabled {
+            return progress;
+        }
+        let token = tower_lsp::lsp_types::NumberOrString::String(format!(
+            "surrealql-{}",
+            NEXT_TOKEN.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        let created = client
+            .send_request::<tower_lsp::lsp_types::request::WorkDoneProgressCreate>(
+                tower_lsp::lsp_types::WorkDoneProgressCreateParams { token: token.clone() },
+            )
+            .await;
+        if created.is_err() {
+            return progress;
+        }
+        progress.token = Some(token);
+        progress
+            .notify(tower_lsp::lsp_types::WorkDoneProgress::Begin(
+                tower_lsp::lsp_types::WorkDoneProgressBegin {
+                    title: String::from(title),
+                    percentage: Some(0),
+                    ..tower_lsp::lsp_types::WorkDoneProgressBegin::default()
+                },
+            ))
+            .await;
+        progress
+    }
+
+    /// Reports that `done` of `total` steps are done.
+    pub(crate) async fn report(&self, done: usize, total: usize) {
+        let percentage = (done * 100).checked_div(total).unwrap_or(100);
+        self.notify(tower_lsp::lsp_types::WorkDoneProgress::Report(
+            tower_lsp::lsp_types::WorkDoneProgressReport {
+                message: Some(format!("{}/{}", done, total)),
+                percentage: u32::try_from(percentage).ok(),
+                ..tower_lsp::lsp_types::WorkDoneProgressReport::default()
+            },
+        ))
+        .await;
+    }
+
+    /// Reports that the task has ended, with a `message` summing up its outcome.
+    pub(crate) async fn end(self, message: String) {
+        self.notify(tower_lsp::lsp_types::WorkDoneProgress::End(
+            tower_lsp::lsp_types::WorkDoneProgressEnd { message: Some(message) },
+        ))
+        .await;
+    }
+
+    async fn notify(&self, value: tower_lsp::lsp_types::WorkDoneProgress) {
+        let Some(token) = self.token.clone() else {
+            return;
+        };
+        self.client
+            .send_notification::<tower_lsp::lsp_types::notification::Progress>(
+                tower_lsp::lsp_types::ProgressParams {
+                    token,
+                    value: tower_lsp::lsp_types::ProgressParamsValue::WorkDone(value),
+                },
+            )
+            .await;
+    }
+}
@@ -0,0 +1,81 @@
+//! The `snapshot` subcommand, which exports the effective schema of a workspace so that another
+//! workspace can load it as a schema source.
+
+/// Loads the workspace at `root` as the server would see it: the schema sources of its
+/// `surrealql.toml`, or every `.surql` file when there is none, and its database when
+/// introspection is enabled.
+pub(crate) async fn load(
+    root: &std::path::Path,
+) -> Result<surrealql_analysis::AnalysisHost, String> {
+    let mut host = surrealql_analysis::AnalysisHost::load(&[root.to_path_buf()])?;
+    let connection = host
+        .analysis()
+        .configs()
+        .first()
+        .filter(|config| config.connection.as_ref().is_some_and(|connection| connection.introspect))
+        .map(|config| crate::execute::Connection::from_config(Some(config)))
+        .transpose()?;
+    if let Some(connection) = connection {
+        let uri = connection.schema_uri().ok_or("Invalid endpoint")?;
+        let definitions = crate::introspect::introspect(&connection).await?;
+        host.set_live_schema(&uri, definitions);
+    }
+    Ok(host)
+}
+
+/// Writes a snapshot of the workspace at `root`, as [`load`] reads it, to `output`, or to stdout.
+pub(crate) async fn write(
+    root: &std::path::Path,
+    output: Option<&std::path::Path>,
+) -> Result<(), String> {
+    let host = load(root).await?;
+    let json = serde_json::to_string_pretty(&host.analysis().schema_snapshot())
+        .map_err(|err| err.to_string())?;
+    match output {
+        Some(path) => std::fs::write(path, json + "\n")
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err)),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn a_written_snapshot_loads_as_the_same_schema() {
+        let dir = std::env::temp_dir().join(format!("surrealql-snapshot-{}", std::process::id()));
+        let (schema_repo, app) = (dir.join("schema-repo"), dir.join("app"));
+        std::fs::create_dir_all(&schema_repo).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::write(
+            schema_repo.join("schema.surql"),
+            "DEFINE TABLE person SCHEMAFULL;\n\
+             DEFINE FIELD name ON person TYPE string;\n\
+             DEFINE INDEX person_name ON person FIELDS name;\n\
+             DEFINE TABLE likes TYPE RELATION FROM person TO person;\n\
+             DEFINE FUNCTION fn::greet($name: string) -> string { RETURN $name; };\n",
+        )
+        .unwrap();
+        std::fs::write(app.join("surrealql.toml"), "[schema]\nsnapshots = [\"schema.json\"]\n")
+            .unwrap();
+
+        super::write(&schema_repo, Some(&app.join("schema.json"))).await.unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(app.join("schema.json")).unwrap())
+                .unwrap();
+        let loaded = surrealql_analysis::AnalysisHost::load(std::slice::from_ref(&app));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let snapshot = loaded.unwrap().analysis().schema_snapshot();
+        assert_eq!(serde_json::to_value(snapshot).unwrap(), written);
+        let tables: Vec<&str> = written["tables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|table| table["name"].as_str())
+            .collect();
+        assert_eq!(tables, ["likes", "person"]);
+    }
+}
@@ -0,0 +1,229 @@
+//! The transports the server speaks LSP over. Over TCP and Unix sockets, the server keeps running
+//! and accepts any number of clients, each with a session of its own.
+
+/// Where LSP clients connect from.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Transport {
+    /// The single client that started the server, over stdin and stdout.
+    Stdio,
+    /// Clients connecting to a TCP port of localhost.
+    Tcp(u16),
+    /// Clients connecting to a Unix domain socket.
+    Socket(std::path::PathBuf),
+}
+
+impl Transport {
+    /// Returns the transport chosen by the `--listen` and `--socket` options, which the command
+    /// line parser keeps from being combined.
+    pub(crate) fn new(listen: Option<u16>, socket: Option<std::path::PathBuf>) -> Self {
+        match (listen, socket) {
+            (Some(port), _) => Transport::Tcp(port),
+            (None, Some(path)) => Transport::Socket(path),
+            (None, None) => Transport::Stdio,
+        }
+    }
+}
+
+/// Serves LSP over `transport` until the client disconnects or, for listening transports, until
+/// the process is interrupted.
+pub(crate) async fn serve(transport: Transport) -> Result<(), String> {
+    match transport {
+        Transport::Stdio => {
+            session(tokio::io::stdin(), tokio::io::stdout()).await;
+            Ok(())
+        }
+        Transport::Tcp(port) => {
+            let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+                .await
+                .map_err(|err| format!("Failed to listen on port {}: {}", port, err))?;
+            tracing::info!("Listening on 127.0.0.1:{}", port);
+            tokio::select! {
+                () = accept_tcp(listener) => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+            Ok(())
+        }
+        Transport::Socket(path) => serve_socket(&path).await,
+    }
+}
+
+async fn accept_tcp(listener: tokio::net::TcpListener) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                tracing::info!("Client connected from {}", peer);
+                tokio::spawn(async move {
+                    let (read, write) = stream.into_split();
+                    session(read, write).await;
+                    tracing::info!("Client {} disconnected", peer);
+                });
+            }
+            Err(err) => {
+                // E.g. out of file descriptors, which may take a moment to clear.
+                tracing::warn!("Failed to accept a client: {}", err);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn serve_socket(path: &std::path::Path) -> Result<(), String> {
+    // A socket left behind by a server that did not exit cleanly is replaced, but not one that a
+    // running server still listens on.
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !std::os::unix::fs::FileTypeExt::is_socket(&metadata.file_type()) {
+            return Err(format!("{} exists and is not a socket", path.display()));
+        }
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(format!("{} is in use by another server", path.display()));
+        }
+        std::fs::remove_file(path)
+            .map_err(|err| format!("Failed to remove {}: {}", path.display(), err))?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .map_err(|err| format!("Failed to listen on {}: {}", path.display(), err))?;
+    tracing::info!("Listening on {}", path.display());
+
+    tokio::select! {
+        () = accept_unix(listener) => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    std::fs::remove_file(path)
+        .map_err(|err| format!("Failed to remove {}: {}", path.display(), err))
+}
+
+#[cfg(unix)]
+async fn accept_unix(listener: tokio::net::UnixListener) {
+    let mut clients = 0u64;
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                clients += 1;
+                let client = clients;
+                tracing::info!("Client {} connected", client);
+                tokio::spawn(async move {
+                    let (read, write) = stream.into_split();
+                    session(read, write).await;
+                    tracing::info!("Client {} disconnected", client);
+                });
+            }
+            Err(err) => {
+                // E.g. out of file descriptors, which may take a moment to clear.
+                tracing::warn!("Failed to accept a client: {}", err);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn serve_socket(_path: &std::path::Path) -> Result<(), String> {
+    Err(String::from("Unix sockets are not supported on this platform"))
+}
+
+/// Runs an LSP session with the client at the other end of `read` and `write`, which ends when
+/// the client exits or disconnects.
+async fn session(read: impl tokio::io::AsyncRead + Unpin, write: impl tokio::io::AsyncWrite) {
+    let (service, socket) = tower_lsp::LspService::build(crate::lsp::Backend::new).finish();
+    tower_lsp::Server::new(read, write, socket).serve(service).await;
+}
+
+#[cfg(test)]
+mod tests {
+    fn transport(args: &[&str]) -> Result<super::Transport, clap::error::ErrorKind> {
+        let args = std::iter::once("surrealql-lsp-server").chain(args.iter().copied());
+        let cli = <crate::Cli as clap::Parser>::try_parse_from(args).map_err(|err| err.kind())?;
+        assert!(cli.command.is_none());
+        Ok(super::Transport::new(cli.listen, cli.socket))
+    }
+
+    #[test]
+    fn options_choose_a_single_transport() {
+        assert_eq!(transport(&[]), Ok(super::Transport::Stdio));
+        assert_eq!(transport(&["--stdio"]), Ok(super::Transport::Stdio));
+        assert_eq!(transport(&["--listen", "9257"]), Ok(super::Transport::Tcp(9257)));
+        assert_eq!(
+            transport(&["--socket", "/tmp/surrealql.sock"]),
+            Ok(super::Transport::Socket(std::path::PathBuf::from("/tmp/surrealql.sock")))
+        );
+
+        let conflict = Err(clap::error::ErrorKind::ArgumentConflict);
+        assert_eq!(transport(&["--stdio", "--listen", "9257"]), conflict);
+        assert_eq!(transport(&["--stdio", "--socket", "/tmp/surrealql.sock"]), conflict);
+        assert_eq!(transport(&["--listen", "9257", "--socket", "/tmp/surrealql.sock"]), conflict);
+        assert_eq!(transport(&["--listen", "9257", "check"]), conflict);
+        assert_eq!(
+            transport(&["--listen", "localhost"]),
+            Err(clap::error::ErrorKind::ValueValidation)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_port_in_use_is_reported() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let error = super::serve(super::Transport::Tcp(port)).await.unwrap_err();
+        assert!(error.starts_with(&format!("Failed to listen on port {}: ", port)), "{}", error);
+    }
+
+    #[cfg(unix)]
+    fn socket_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("surrealql-{}-{}.sock", name, std::process::id()))
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sockets_are_only_replaced_when_left_behind() {
+        let path = socket_path("file");
+        std::fs::write(&path, "").unwrap();
+        let error = super::serve(super::Transport::Socket(path.clone())).await.unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error, format!("{} exists and is not a socket", path.display()));
+
+        let path = socket_path("in-use");
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let error = super::serve(super::Transport::Socket(path.clone())).await.unwrap_err();
+        drop(listener);
+        assert_eq!(error, format!("{} is in use by another server", path.display()));
+
+        // The socket of the listener dropped above is left behind, and is replaced.
+        let server = tokio::spawn(super::serve(super::Transport::Socket(path.clone())));
+        let stream = loop {
+            if let Ok(stream) = tokio::net::UnixStream::connect(&path).await {
+                break stream;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        let response = initialize(stream).await;
+        server.abort();
+        std::fs::remove_file(&path).unwrap();
+        assert!(response.contains("\"capabilities\""), "{}", response);
+    }
+
+    /// Sends an `initialize` request over `stream` and returns the body of the response.
+    #[cfg(unix)]
+    async fn initialize(mut stream: tokio::net::UnixStream) -> String {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#;
+        let request = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        tokio::io::AsyncWriteExt::write_all(&mut stream, request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = tokio::io::AsyncReadExt::read(&mut stream, &mut buffer).await.unwrap();
+            response.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&response);
+            let Some((header, body)) = text.split_once("\r\n\r\n") else {
+                continue;
+            };
+            let length: usize = header
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length: "))
+                .and_then(|length| length.trim().parse().ok())
+                .unwrap();
+            if read == 0 || body.len() >= length {
+                return body.to_string();
+            }
+        }
+    }
+}
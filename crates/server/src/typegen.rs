@@ -0,0 +1,56 @@
+//! Types for application code, generated from the schema by the `types` subcommand and the
+//! `surrealql.generateTypes` command, so that they no longer need to mirror it by hand.
+
+/// Returns the types of the tables of the workspace. Takes an optional [`TypesParams`] argument.
+pub(crate) const GENERATE_TYPES: &str = "surrealql.generateTypes";
+
+/// The language types are generated in.
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Language {
+    /// An `interface` per table.
+    #[default]
+    #[value(name = "typescript")]
+    #[serde(rename = "typescript")]
+    TypeScript,
+    /// A `struct` per table, with serde derives.
+    Rust,
+    /// A JSON Schema document with a definition per table.
+    JsonSchema,
+}
+
+impl From<Language> for surrealql_analysis::TypeLanguage {
+    fn from(language: Language) -> Self {
+        match language {
+            Language::TypeScript => surrealql_analysis::TypeLanguage::TypeScript,
+            Language::Rust => surrealql_analysis::TypeLanguage::Rust,
+            Language::JsonSchema => surrealql_analysis::TypeLanguage::JsonSchema,
+        }
+    }
+}
+
+/// The argument of the `surrealql.generateTypes` command.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct TypesParams {
+    pub(crate) language: Language,
+}
+
+/// Writes the types of the workspace at `root`, as the `snapshot` subcommand loads it, to
+/// `output`, or to stdout.
+pub(crate) async fn write(
+    root: &std::path::Path,
+    language: Language,
+    output: Option<&std::path::Path>,
+) -> Result<(), String> {
+    let host = crate::snapshot::load(root).await?;
+    let types = host.analysis().types(language.into());
+    match output {
+        Some(path) => std::fs::write(path, types)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err)),
+        None => {
+            print!("{}", types);
+            Ok(())
+        }
+    }
+}
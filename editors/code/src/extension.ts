@@ -29,7 +29,7 @@ export function activate(_: ExtensionContext) {
     const clientOptions: LanguageClientOptions = {
         documentSelector: [{ scheme: "file", language: "surql" }],
         synchronize: {
-            fileEvents: workspace.createFileSystemWatcher("**/.clientrc"),
+            fileEvents: workspace.createFileSystemWatcher("**/surrealql.toml"),
         },
     };
 